@@ -0,0 +1,9 @@
+CREATE TABLE refresh_token (
+    token_hash CHAR(64) NOT NULL,
+    user_id VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (token_hash),
+    FOREIGN KEY (user_id) REFERENCES user(user_id) ON DELETE CASCADE
+);
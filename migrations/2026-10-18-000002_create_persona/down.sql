@@ -0,0 +1,13 @@
+ALTER TABLE user
+    ADD COLUMN ai_diary_1 TEXT AFTER human_diary,
+    ADD COLUMN ai_diary_2 TEXT AFTER ai_diary_1,
+    ADD COLUMN ai_diary_3 TEXT AFTER ai_diary_2,
+    ADD COLUMN ai_diary_4 TEXT AFTER ai_diary_3;
+
+UPDATE user u JOIN persona_diary d ON d.user_id = u.user_id AND d.persona_id = 1 SET u.ai_diary_1 = d.content;
+UPDATE user u JOIN persona_diary d ON d.user_id = u.user_id AND d.persona_id = 2 SET u.ai_diary_2 = d.content;
+UPDATE user u JOIN persona_diary d ON d.user_id = u.user_id AND d.persona_id = 3 SET u.ai_diary_3 = d.content;
+UPDATE user u JOIN persona_diary d ON d.user_id = u.user_id AND d.persona_id = 4 SET u.ai_diary_4 = d.content;
+
+DROP TABLE IF EXISTS persona_diary;
+DROP TABLE IF EXISTS persona;
@@ -0,0 +1,39 @@
+CREATE TABLE persona (
+    persona_id INT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    prompt TEXT NOT NULL,
+    display_order INT NOT NULL,
+    is_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    PRIMARY KEY (persona_id)
+);
+
+INSERT INTO persona (persona_id, name, prompt, display_order) VALUES
+    (1, 'opposite', '入力テキストの感想・感情・意見を真逆の意味合いに書き換えてください。但し、口調・固有名詞と客観的事実は変更しないでください。', 1),
+    (2, 'optimistic', '入力テキストの感想・感情・意見など主観的な部分を楽観的に書き替えてください。但し、口調・固有名詞と客観的事実は変更しないでください。', 2),
+    (3, 'pessimistic', '入力テキストの感想・感情・意見など主観的な部分を悲観的に書き替えてください。但し、口調・固有名詞と客観的事実は変更しないでください。', 3),
+    (4, 'self-expansive', '入力テキストの感想・感情・意見など主観的な部分を自己拡張的に書き替えてください。但し、口調・固有名詞と客観的事実は変更しないでください。', 4);
+
+CREATE TABLE persona_diary (
+    user_id VARCHAR(255) NOT NULL,
+    persona_id INT NOT NULL,
+    content TEXT,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, persona_id),
+    FOREIGN KEY (user_id) REFERENCES user(user_id) ON DELETE CASCADE,
+    FOREIGN KEY (persona_id) REFERENCES persona(persona_id)
+);
+
+INSERT INTO persona_diary (user_id, persona_id, content)
+    SELECT user_id, 1, ai_diary_1 FROM user WHERE ai_diary_1 IS NOT NULL;
+INSERT INTO persona_diary (user_id, persona_id, content)
+    SELECT user_id, 2, ai_diary_2 FROM user WHERE ai_diary_2 IS NOT NULL;
+INSERT INTO persona_diary (user_id, persona_id, content)
+    SELECT user_id, 3, ai_diary_3 FROM user WHERE ai_diary_3 IS NOT NULL;
+INSERT INTO persona_diary (user_id, persona_id, content)
+    SELECT user_id, 4, ai_diary_4 FROM user WHERE ai_diary_4 IS NOT NULL;
+
+ALTER TABLE user
+    DROP COLUMN ai_diary_1,
+    DROP COLUMN ai_diary_2,
+    DROP COLUMN ai_diary_3,
+    DROP COLUMN ai_diary_4;
@@ -0,0 +1,18 @@
+ALTER TABLE user ADD COLUMN human_diary TEXT AFTER user_id;
+
+CREATE TABLE persona_diary (
+    user_id VARCHAR(255) NOT NULL,
+    persona_id INT NOT NULL,
+    content TEXT,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, persona_id),
+    FOREIGN KEY (user_id) REFERENCES user(user_id) ON DELETE CASCADE,
+    FOREIGN KEY (persona_id) REFERENCES persona(persona_id)
+);
+
+UPDATE user u JOIN diary d ON d.user_id = u.user_id AND d.diary_id = 0 SET u.human_diary = d.content;
+INSERT INTO persona_diary (user_id, persona_id, content, updated_at)
+    SELECT d.user_id, d.diary_id, d.content, d.updated_at FROM diary d
+    JOIN persona p ON p.persona_id = d.diary_id;
+
+DROP TABLE IF EXISTS diary;
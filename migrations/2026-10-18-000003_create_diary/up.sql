@@ -0,0 +1,18 @@
+CREATE TABLE diary (
+    user_id VARCHAR(255) NOT NULL,
+    diary_id INT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, diary_id),
+    FOREIGN KEY (user_id) REFERENCES user(user_id) ON DELETE CASCADE
+);
+
+INSERT INTO diary (user_id, diary_id, content)
+    SELECT user_id, 0, human_diary FROM user WHERE human_diary IS NOT NULL;
+INSERT INTO diary (user_id, diary_id, content, updated_at)
+    SELECT user_id, persona_id, content, updated_at FROM persona_diary WHERE content IS NOT NULL;
+
+DROP TABLE persona_diary;
+
+ALTER TABLE user DROP COLUMN human_diary;
@@ -0,0 +1,11 @@
+CREATE TABLE diary_revision (
+    revision_id BIGINT NOT NULL AUTO_INCREMENT,
+    user_id VARCHAR(255) NOT NULL,
+    diary_id INT NOT NULL,
+    content TEXT NOT NULL,
+    target_index INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (revision_id),
+    INDEX diary_revision_user_id (user_id, revision_id),
+    FOREIGN KEY (user_id) REFERENCES user(user_id) ON DELETE CASCADE
+);
@@ -0,0 +1,10 @@
+-- 1ユーザー1日記に戻すため、最新のエントリ以外は削除する
+DELETE d FROM diary d
+    JOIN (SELECT user_id, MAX(entry_date) AS latest FROM diary GROUP BY user_id) l
+    ON l.user_id = d.user_id
+    WHERE d.entry_date < l.latest;
+ALTER TABLE diary DROP PRIMARY KEY,
+    ADD PRIMARY KEY (user_id, diary_id),
+    DROP COLUMN entry_date;
+
+ALTER TABLE diary_revision DROP COLUMN entry_date;
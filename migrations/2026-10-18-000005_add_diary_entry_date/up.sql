@@ -0,0 +1,10 @@
+-- 既存の日記はユーザー作成日のエントリとして扱う
+ALTER TABLE diary ADD COLUMN entry_date DATE AFTER user_id;
+UPDATE diary d JOIN user u ON u.user_id = d.user_id SET d.entry_date = DATE(u.created_at);
+ALTER TABLE diary MODIFY entry_date DATE NOT NULL,
+    DROP PRIMARY KEY,
+    ADD PRIMARY KEY (user_id, entry_date, diary_id);
+
+ALTER TABLE diary_revision ADD COLUMN entry_date DATE AFTER user_id;
+UPDATE diary_revision r JOIN user u ON u.user_id = r.user_id SET r.entry_date = DATE(u.created_at);
+ALTER TABLE diary_revision MODIFY entry_date DATE NOT NULL;
@@ -0,0 +1,11 @@
+-- 表示端末ごとのセッション。ペアリングが済むと pairing_code を消して user_id を持つ
+CREATE TABLE display_session (
+    display_id VARCHAR(36) NOT NULL,
+    pairing_code CHAR(6),
+    user_id VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    paired_at TIMESTAMP NULL,
+    PRIMARY KEY (display_id),
+    UNIQUE KEY display_session_pairing_code (pairing_code),
+    FOREIGN KEY (user_id) REFERENCES user(user_id) ON DELETE SET NULL
+);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS mutation_job_task;
+DROP TABLE IF EXISTS mutation_job;
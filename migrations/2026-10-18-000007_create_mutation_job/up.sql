@@ -0,0 +1,22 @@
+-- 非同期モードの /mutate で登録する書き換えジョブ。プロセスが再起動しても残る
+CREATE TABLE mutation_job (
+    job_id VARCHAR(36) NOT NULL,
+    user_id VARCHAR(255) NOT NULL,
+    entry_date DATE NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (job_id),
+    FOREIGN KEY (user_id) REFERENCES user(user_id) ON DELETE CASCADE
+);
+
+-- ペルソナごとの処理。status は pending / running / done / failed
+CREATE TABLE mutation_job_task (
+    job_id VARCHAR(36) NOT NULL,
+    persona_id INT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (job_id, persona_id),
+    INDEX mutation_job_task_status (status, updated_at),
+    FOREIGN KEY (job_id) REFERENCES mutation_job(job_id) ON DELETE CASCADE
+);
@@ -0,0 +1,11 @@
+-- ペルソナごとの直近の書き換えの結果。失敗した場合も diary の内容は前回のまま残す
+CREATE TABLE diary_status (
+    user_id VARCHAR(255) NOT NULL,
+    entry_date DATE NOT NULL,
+    diary_id INT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    reason TEXT,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, entry_date, diary_id),
+    FOREIGN KEY (user_id) REFERENCES user(user_id) ON DELETE CASCADE
+);
@@ -0,0 +1,7 @@
+-- LLM の応答のキャッシュ。cache_key はモデルとプロンプトの SHA-256
+CREATE TABLE completion_cache (
+    cache_key CHAR(64) NOT NULL PRIMARY KEY,
+    model VARCHAR(255) NOT NULL,
+    output TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
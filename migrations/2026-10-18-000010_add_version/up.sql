@@ -0,0 +1,3 @@
+-- 楽観的排他制御のための版数。保存するたびに1つ増やし、読んだときと違えば保存しない
+ALTER TABLE diary ADD COLUMN version INT NOT NULL DEFAULT 1;
+ALTER TABLE user ADD COLUMN version INT NOT NULL DEFAULT 1;
@@ -0,0 +1,3 @@
+ALTER TABLE mutation_job_task DROP COLUMN target_index;
+ALTER TABLE mutation_job_task DROP COLUMN output_version;
+ALTER TABLE mutation_job_task DROP COLUMN output;
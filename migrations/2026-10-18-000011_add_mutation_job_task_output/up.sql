@@ -0,0 +1,5 @@
+-- 書き換えたがまだ保存していないペルソナの日記。ジョブの全ペルソナが終わったら人間の日記とまとめて保存する
+-- output_version は書き換えを始めた時点の日記の版数
+ALTER TABLE mutation_job_task ADD COLUMN output TEXT NULL;
+ALTER TABLE mutation_job_task ADD COLUMN output_version INT NULL;
+ALTER TABLE mutation_job_task ADD COLUMN target_index INT NULL;
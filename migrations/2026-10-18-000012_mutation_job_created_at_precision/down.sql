@@ -0,0 +1 @@
+ALTER TABLE mutation_job MODIFY created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
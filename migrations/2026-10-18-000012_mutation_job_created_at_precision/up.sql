@@ -0,0 +1,2 @@
+-- 同じユーザーのジョブの新旧を再起動後も比べられるよう、登録時刻をマイクロ秒まで残す
+ALTER TABLE mutation_job MODIFY created_at TIMESTAMP(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6);
@@ -0,0 +1,6 @@
+-- ユーザーのアクセストークンをまとめて失効させた時刻。これ以前に発行されたトークンはすべて無効
+-- ユーザーを削除した後も残すので、外部キーは付けない
+CREATE TABLE access_revocation (
+    user_id VARCHAR(255) NOT NULL PRIMARY KEY,
+    revoked_at TIMESTAMP NOT NULL
+);
@@ -1,2 +1,5 @@
+pub mod broadcast;
+pub mod cache;
 pub mod error;
+pub mod generation;
 pub mod usecase;
@@ -0,0 +1,218 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use getset::Getters;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+use crate::domain::entity::diary::Diary;
+use crate::domain::entity::user::UserId;
+
+// 受信側が追いつけずに溢れた分は捨てる。表示には最新の内容さえ届けばよい
+// 書き換え途中の更新はトークンごとに流れるので多めに取る
+const PROGRESS_CAPACITY: usize = 1024;
+// 保存した結果はペルソナごとに1件なので、途中経過に押し出されないよう別のチャンネルで流す
+const COMPLETE_CAPACITY: usize = 256;
+
+// ペルソナの書き換えの途中経過 (is_complete = false) と、保存した結果 (is_complete = true)
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct DiaryUpdate {
+    #[getset(get = "pub")]
+    user_id: UserId,
+    // 書き換えたエントリの日付。表示中の日付より前のエントリの更新は表示端末に流さない
+    #[getset(get = "pub")]
+    entry_date: NaiveDate,
+    #[getset(get = "pub")]
+    diary: Diary,
+    #[getset(get = "pub")]
+    human_length: i32,
+    #[getset(get = "pub")]
+    is_complete: bool,
+}
+
+impl DiaryUpdate {
+    pub fn new(
+        user_id: UserId,
+        entry_date: NaiveDate,
+        diary: Diary,
+        human_length: i32,
+        is_complete: bool,
+    ) -> Self {
+        Self {
+            user_id,
+            entry_date,
+            diary,
+            human_length,
+            is_complete,
+        }
+    }
+}
+
+// /mutate から表示端末の WebSocket へ更新を届ける。プロセス内でのみ共有する
+// 途中経過と保存した結果は別のチャンネルで流し、受信側で公開した順に並べ直す
+#[derive(Clone)]
+pub struct DiaryBroadcaster {
+    progress: broadcast::Sender<(u64, DiaryUpdate)>,
+    complete: broadcast::Sender<(u64, DiaryUpdate)>,
+    sequence: Arc<AtomicU64>,
+}
+
+impl DiaryBroadcaster {
+    pub fn new() -> Self {
+        let (progress, _) = broadcast::channel(PROGRESS_CAPACITY);
+        let (complete, _) = broadcast::channel(COMPLETE_CAPACITY);
+        Self {
+            progress,
+            complete,
+            sequence: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    // 購読している端末がなければ何もしない
+    pub fn publish(&self, update: DiaryUpdate) {
+        let sequence = self.sequence.fetch_add(1, Ordering::SeqCst);
+        let sender = if update.is_complete {
+            &self.complete
+        } else {
+            &self.progress
+        };
+        let _ = sender.send((sequence, update));
+    }
+
+    pub fn subscribe(&self) -> DiaryReceiver {
+        DiaryReceiver {
+            progress: self.progress.subscribe(),
+            complete: self.complete.subscribe(),
+            next_progress: None,
+            next_complete: None,
+        }
+    }
+}
+
+impl Default for DiaryBroadcaster {
+    fn default() -> Self { Self::new() }
+}
+
+// 2つのチャンネルから先頭を1件ずつ取り出しておき、先に公開された方を返す
+pub struct DiaryReceiver {
+    progress: broadcast::Receiver<(u64, DiaryUpdate)>,
+    complete: broadcast::Receiver<(u64, DiaryUpdate)>,
+    next_progress: Option<(u64, DiaryUpdate)>,
+    next_complete: Option<(u64, DiaryUpdate)>,
+}
+
+impl DiaryReceiver {
+    // 配信が終わった (サーバーの停止) 場合は None
+    pub async fn recv(&mut self) -> Option<DiaryUpdate> {
+        loop {
+            if let Some(update) = self.try_recv() {
+                return Some(update);
+            }
+            tokio::select! {
+                update = self.progress.recv() => match update {
+                    Ok(update) => self.next_progress = Some(update),
+                    Err(RecvError::Lagged(_)) => {},
+                    Err(RecvError::Closed) => return None,
+                },
+                update = self.complete.recv() => match update {
+                    Ok(update) => self.next_complete = Some(update),
+                    Err(RecvError::Lagged(_)) => {},
+                    Err(RecvError::Closed) => return None,
+                },
+            }
+        }
+    }
+
+    // 届いている更新がなければ待たずに None を返す
+    pub fn try_recv(&mut self) -> Option<DiaryUpdate> {
+        if self.next_progress.is_none() {
+            self.next_progress = try_next(&mut self.progress);
+        }
+        if self.next_complete.is_none() {
+            self.next_complete = try_next(&mut self.complete);
+        }
+        let progress_first = match (&self.next_progress, &self.next_complete) {
+            (Some((progress, _)), Some((complete, _))) => progress < complete,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        let next = if progress_first {
+            self.next_progress.take()
+        } else {
+            self.next_complete.take()
+        };
+        next.map(|(_, update)| update)
+    }
+}
+
+// 溢れて捨てられた分は飛ばす
+fn try_next(receiver: &mut broadcast::Receiver<(u64, DiaryUpdate)>) -> Option<(u64, DiaryUpdate)> {
+    loop {
+        match receiver.try_recv() {
+            Ok(update) => return Some(update),
+            Err(TryRecvError::Lagged(_)) => continue,
+            Err(TryRecvError::Empty | TryRecvError::Closed) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entity::diary::{DiaryContent, DiaryId};
+
+    fn update(text: &str, is_complete: bool) -> DiaryUpdate {
+        DiaryUpdate::new(
+            UserId::new(uuid::Uuid::new_v4().to_string()).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 7, 11).unwrap(),
+            Diary::new(
+                DiaryId::new(1).unwrap(),
+                DiaryContent::new(text.to_string()).unwrap(),
+            )
+            .unwrap(),
+            7,
+            is_complete,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_complete_updates_survive_progress_overflow() {
+        let broadcaster = DiaryBroadcaster::new();
+        let mut updates = broadcaster.subscribe();
+
+        broadcaster.publish(update("一文目．", true));
+        // 受信側が読まないうちに途中経過が溢れても、保存した結果は残る
+        for _ in 0..PROGRESS_CAPACITY * 2 {
+            broadcaster.publish(update("二文目", false));
+        }
+        broadcaster.publish(update("二文目．", true));
+
+        let first = updates.recv().await.unwrap();
+        assert!(*first.is_complete());
+        assert_eq!(first.diary().content().to_value(), "一文目．");
+        let mut completes = vec![];
+        while let Some(update) = updates.try_recv() {
+            if *update.is_complete() {
+                completes.push(update.diary().content().to_value().clone());
+            }
+        }
+        assert_eq!(completes, vec!["二文目．".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_updates_arrive_in_published_order() {
+        let broadcaster = DiaryBroadcaster::new();
+        let mut updates = broadcaster.subscribe();
+
+        broadcaster.publish(update("書き", false));
+        broadcaster.publish(update("書き換えた．", true));
+        broadcaster.publish(update("次", false));
+
+        let mut received = vec![];
+        while let Some(update) = updates.try_recv() {
+            received.push(*update.is_complete());
+        }
+        assert_eq!(received, vec![false, true, false]);
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+
+use crate::domain::entity::diary::DiaryId;
+use crate::domain::entity::user::UserId;
+
+// ユーザー・ペルソナごとに保持する文の数の上限。超えたらそのペルソナの分を捨てる
+const MAX_SENTENCES_PER_PERSONA: usize = 256;
+// 全体で保持する (ユーザー, ペルソナ) の数の上限。超えたら最も使われていないものから捨てる
+const MAX_PERSONAS: usize = 4_096;
+
+// 元の文のハッシュ -> 書き換え後の文
+type MutatedSentences = HashMap<String, String>;
+
+#[derive(Default)]
+struct Entries {
+    // (ユーザー, ペルソナ) -> (書き換え済みの文, 最後に使った順番)
+    personas: HashMap<(String, i32), (MutatedSentences, u64)>,
+    order: BTreeMap<u64, (String, i32)>,
+    tick: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, key: &(String, i32)) -> Option<&mut MutatedSentences> {
+        let (sentences, used) = self.personas.get_mut(key)?;
+        self.tick += 1;
+        self.order.remove(used);
+        *used = self.tick;
+        self.order.insert(self.tick, key.clone());
+        Some(sentences)
+    }
+}
+
+// 書き換え済みの文を、元の文のハッシュから引けるようにしておく
+// 打ち直しやバックスペースで同じ文が再び現れた場合に API を呼ばずに済ませるため
+// プロセス内でのみ共有し、再起動すると消える
+#[derive(Clone)]
+pub struct SentenceCache {
+    capacity: usize,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl Default for SentenceCache {
+    fn default() -> Self { Self::with_capacity(MAX_PERSONAS) }
+}
+
+impl SentenceCache {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Arc::new(Mutex::new(Entries::default())),
+        }
+    }
+
+    pub fn get(&self, user_id: &UserId, persona_id: &DiaryId, sentence: &str) -> Option<String> {
+        self.entries
+            .lock()
+            .unwrap()
+            .touch(&key(user_id, persona_id))
+            .and_then(|sentences| sentences.get(&hash(sentence)))
+            .cloned()
+    }
+
+    pub fn insert(&self, user_id: &UserId, persona_id: &DiaryId, sentence: &str, mutated: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let key = key(user_id, persona_id);
+        if entries.touch(&key).is_none() {
+            while entries.personas.len() >= self.capacity {
+                let Some((_, oldest)) = entries.order.pop_first() else {
+                    break;
+                };
+                entries.personas.remove(&oldest);
+            }
+            entries.tick += 1;
+            let tick = entries.tick;
+            entries.personas.insert(key.clone(), (HashMap::new(), tick));
+            entries.order.insert(tick, key.clone());
+        }
+        let sentences = entries.touch(&key).unwrap();
+        if sentences.len() >= MAX_SENTENCES_PER_PERSONA {
+            sentences.clear();
+        }
+        sentences.insert(hash(sentence), mutated.to_string());
+    }
+
+    // 削除したユーザーの分をすべて捨てる
+    pub fn remove_user(&self, user_id: &UserId) {
+        let mut entries = self.entries.lock().unwrap();
+        let removed: Vec<u64> = entries
+            .personas
+            .iter()
+            .filter(|((owner, _), _)| owner == user_id.as_str())
+            .map(|(_, (_, used))| *used)
+            .collect();
+        for used in removed {
+            if let Some(key) = entries.order.remove(&used) {
+                entries.personas.remove(&key);
+            }
+        }
+    }
+}
+
+fn key(user_id: &UserId, persona_id: &DiaryId) -> (String, i32) {
+    (user_id.as_str().to_string(), persona_id.to_id())
+}
+
+fn hash(sentence: &str) -> String { hex::encode(Sha256::digest(sentence.as_bytes())) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_scoped_per_user_and_persona() {
+        let cache = SentenceCache::new();
+        let user = UserId::new("cache_user".to_string()).unwrap();
+        let other_user = UserId::new("other_user".to_string()).unwrap();
+        let persona = DiaryId::new(1).unwrap();
+        let other_persona = DiaryId::new(2).unwrap();
+
+        cache.insert(&user, &persona, "晴れた．", "曇った．");
+
+        assert_eq!(
+            cache.get(&user, &persona, "晴れた．"),
+            Some("曇った．".to_string())
+        );
+        assert_eq!(cache.get(&user, &other_persona, "晴れた．"), None);
+        assert_eq!(cache.get(&other_user, &persona, "晴れた．"), None);
+        assert_eq!(cache.get(&user, &persona, "晴れた"), None);
+    }
+
+    #[test]
+    fn test_least_recently_used_persona_is_evicted_and_users_can_be_removed() {
+        let cache = SentenceCache::with_capacity(2);
+        let user = UserId::new("cache_user".to_string()).unwrap();
+        let other_user = UserId::new("other_user".to_string()).unwrap();
+        let persona = DiaryId::new(1).unwrap();
+        let other_persona = DiaryId::new(2).unwrap();
+
+        cache.insert(&user, &persona, "一．", "一(改)．");
+        cache.insert(&user, &other_persona, "二．", "二(改)．");
+        cache.get(&user, &persona, "一．");
+        // 全体の上限を超えたので、最も使われていない (user, other_persona) を捨てる
+        cache.insert(&other_user, &persona, "三．", "三(改)．");
+
+        assert_eq!(cache.get(&user, &other_persona, "二．"), None);
+        assert!(cache.get(&user, &persona, "一．").is_some());
+
+        cache.remove_user(&user);
+        assert_eq!(cache.get(&user, &persona, "一．"), None);
+        assert!(cache.get(&other_user, &persona, "三．").is_some());
+    }
+}
@@ -11,6 +11,12 @@ pub enum ApplicationError {
         entity_type: &'static str,
         user_id: String,
     },
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    TooManyRequests(String),
     #[error(transparent)]
     InfrastructureError(anyhow::Error),
     #[error("{0}")]
@@ -28,6 +34,7 @@ impl From<DomainError> for ApplicationError {
                 entity_type,
                 user_id,
             },
+            DomainError::Conflict(message) => ApplicationError::Conflict(message),
             DomainError::InfrastructureError(_) => {
                 ApplicationError::InfrastructureError(anyhow::Error::new(err))
             },
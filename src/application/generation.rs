@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{watch, Mutex as AsyncMutex, MutexGuard};
+
+use crate::domain::entity::user::UserId;
+use crate::domain::service::llm::CacheMode;
+
+struct UserGeneration {
+    latest: watch::Sender<u64>,
+    save_lock: Arc<AsyncMutex<()>>,
+}
+
+// ユーザーごとに /mutate の世代を数える
+// 新しいリクエストが始まると、それより前の世代の書き換えは途中で打ち切り、保存もしない
+// ユーザーの Generation がすべて破棄されたら、そのユーザーの分は取り除く
+#[derive(Clone, Default)]
+pub struct MutationGenerations {
+    users: Arc<Mutex<HashMap<String, UserGeneration>>>,
+}
+
+impl MutationGenerations {
+    pub fn new() -> Self { Self::default() }
+
+    // 新しい世代を始める。実行中の古い世代には superseded() で通知される
+    pub fn begin(&self, user_id: &UserId) -> Generation {
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .entry(user_id.as_str().to_string())
+            .or_insert_with(|| UserGeneration {
+                latest: watch::channel(0).0,
+                save_lock: Arc::new(AsyncMutex::new(())),
+            });
+        let number = *user.latest.borrow() + 1;
+        user.latest.send_replace(number);
+
+        Generation {
+            number,
+            latest: user.latest.subscribe(),
+            save_lock: Arc::clone(&user.save_lock),
+            cache_mode: CacheMode::Use,
+            users: Arc::clone(&self.users),
+            user_id: user_id.as_str().to_string(),
+        }
+    }
+
+    #[cfg(test)]
+    fn user_count(&self) -> usize { self.users.lock().unwrap().len() }
+}
+
+#[derive(Clone)]
+pub struct Generation {
+    number: u64,
+    latest: watch::Receiver<u64>,
+    save_lock: Arc<AsyncMutex<()>>,
+    // この /mutate で LLM の応答キャッシュを使うかどうか
+    cache_mode: CacheMode,
+    users: Arc<Mutex<HashMap<String, UserGeneration>>>,
+    user_id: String,
+}
+
+impl Generation {
+    pub fn with_cache_mode(mut self, cache_mode: CacheMode) -> Self {
+        self.cache_mode = cache_mode;
+        self
+    }
+
+    pub fn cache_mode(&self) -> CacheMode { self.cache_mode }
+
+    pub fn is_current(&self) -> bool { *self.latest.borrow() == self.number }
+
+    // 新しい世代が始まるまで待つ
+    pub async fn superseded(&self) {
+        let mut latest = self.latest.clone();
+        let number = self.number;
+        // 送信側は受信側が残っている間は取り除かれないので、閉じられることはない
+        let _ = latest.wait_for(|latest| *latest != number).await;
+    }
+
+    // 保存の間はロックを持つ。確認から保存までの間に新しい世代が先に保存してしまうのを防ぐ
+    // 最新の世代でなければ None
+    pub async fn lock_if_current(&self) -> Option<MutexGuard<'_, ()>> {
+        let guard = self.save_lock.lock().await;
+        self.is_current().then_some(guard)
+    }
+}
+
+impl Drop for Generation {
+    // 受信側は Generation ごとに1つなので、自分の分だけ残っていれば最後の1つ
+    // begin と同じロックの中で確かめるので、その間に新しい世代が始まることはない
+    fn drop(&mut self) {
+        let mut users = self.users.lock().unwrap();
+        if users
+            .get(&self.user_id)
+            .is_some_and(|user| user.latest.receiver_count() <= 1)
+        {
+            users.remove(&self.user_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_newer_generation_supersedes_older() {
+        let generations = MutationGenerations::new();
+        let user_id = UserId::new("generation_test_user".to_string()).unwrap();
+        let other_id = UserId::new("other_user".to_string()).unwrap();
+
+        let first = generations.begin(&user_id);
+        let other = generations.begin(&other_id);
+        assert!(first.is_current());
+
+        let second = generations.begin(&user_id);
+
+        assert!(!first.is_current());
+        assert!(second.is_current());
+        // 別のユーザーの世代には影響しない
+        assert!(other.is_current());
+        tokio::time::timeout(Duration::from_millis(100), first.superseded())
+            .await
+            .unwrap();
+        assert!(first.lock_if_current().await.is_none());
+        assert!(second.lock_if_current().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_user_is_removed_after_last_generation() {
+        let generations = MutationGenerations::new();
+        let user_id = UserId::new("generation_test_user".to_string()).unwrap();
+
+        let first = generations.begin(&user_id);
+        let second = generations.begin(&user_id);
+        let copied = second.clone();
+        drop(first);
+        drop(second);
+        assert_eq!(generations.user_count(), 1);
+        // 残っている世代は最新のまま
+        assert!(copied.is_current());
+
+        drop(copied);
+        assert_eq!(generations.user_count(), 0);
+        assert!(generations.begin(&user_id).is_current());
+    }
+}
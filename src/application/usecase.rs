@@ -1,5 +1,11 @@
 pub mod delete;
 pub mod diary;
+pub mod display;
+pub mod entry;
 pub mod init;
+pub mod job;
 pub mod mutate;
+pub mod persona;
 pub mod result;
+pub mod revision;
+pub mod token;
@@ -1,17 +1,39 @@
+use crate::application::cache::SentenceCache;
 use crate::application::error::ApplicationError;
 use crate::domain::entity::user::UserId;
+use crate::domain::repository::token::RefreshTokenRepository;
 use crate::domain::repository::user::UserRepository;
 
 #[derive(Clone)]
-pub struct DeleteUsecase<R: UserRepository> {
+pub struct DeleteUsecase<R: UserRepository, T: RefreshTokenRepository> {
     user_repository: R,
+    token_repository: T,
+    // 書き換え済みの文。MutateUsecase と共有しているものを渡す
+    sentence_cache: Option<SentenceCache>,
 }
 
-impl<R: UserRepository> DeleteUsecase<R> {
-    pub fn new(user_repository: R) -> Self { Self { user_repository } }
+impl<R: UserRepository, T: RefreshTokenRepository> DeleteUsecase<R, T> {
+    pub fn new(user_repository: R, token_repository: T) -> Self {
+        Self {
+            user_repository,
+            token_repository,
+            sentence_cache: None,
+        }
+    }
+
+    pub fn with_sentence_cache(self, sentence_cache: SentenceCache) -> Self {
+        Self {
+            sentence_cache: Some(sentence_cache),
+            ..self
+        }
+    }
 
     pub async fn delete_user(&self, user_id: &UserId) -> Result<(), ApplicationError> {
+        self.token_repository.revoke_all(user_id).await?;
         self.user_repository.delete_user(user_id).await?;
+        if let Some(sentence_cache) = &self.sentence_cache {
+            sentence_cache.remove_user(user_id);
+        }
         Ok(())
     }
 }
@@ -1,38 +1,185 @@
+use chrono::NaiveDate;
+
 use crate::application::error::ApplicationError;
 use crate::domain::entity::diary::{DiaryContent, DiaryId};
-use crate::domain::repository::user::UserRepository;
+use crate::domain::entity::display::DisplayId;
+use crate::domain::entity::status::DiaryStatus;
+use crate::domain::entity::user::UserId;
+use crate::domain::repository::diary::DiaryRepository;
+use crate::domain::repository::display::DisplaySessionRepository;
 
 #[derive(Clone)]
-pub struct GetDiaryUseCase<R: UserRepository> {
-    user_repository: R,
+pub struct GetDiaryUseCase<S: DisplaySessionRepository, D: DiaryRepository> {
+    display_repository: S,
+    diary_repository: D,
 }
 
-impl<R: UserRepository> GetDiaryUseCase<R> {
-    pub fn new(user_repository: R) -> Self { Self { user_repository } }
+impl<S: DisplaySessionRepository, D: DiaryRepository> GetDiaryUseCase<S, D> {
+    pub fn new(display_repository: S, diary_repository: D) -> Self {
+        Self {
+            display_repository,
+            diary_repository,
+        }
+    }
 
-    pub async fn get_current_user_diary(
+    // 表示端末にペアリングされたユーザー。未登録の端末やペアリング前は None
+    pub async fn find_paired_user(
         &self,
+        display_id: &DisplayId,
+    ) -> Result<Option<UserId>, ApplicationError> {
+        Ok(self
+            .display_repository
+            .find_by_id(display_id)
+            .await?
+            .and_then(|session| session.user_id().clone()))
+    }
+
+    // 表示端末にペアリングされたユーザーの日記を返す
+    // 書き換えに失敗していても前回の日記を返し、失敗したことは status で伝える
+    pub async fn get_display_diary(
+        &self,
+        display_id: &DisplayId,
         diary_id: &DiaryId,
-    ) -> Result<(DiaryContent, DiaryContent), ApplicationError> {
-        let user_id = match self.user_repository.find_current_user().await.unwrap() {
-            Some(user) => user.id().clone(),
-            None => return Err(ApplicationError::Validation("Not Found".to_string())),
+    ) -> Result<(DiaryContent, DiaryContent, Option<DiaryStatus>), ApplicationError> {
+        let user_id = match self.find_paired_user(display_id).await? {
+            Some(user_id) => user_id,
+            None => return Err(ApplicationError::Validation("Not Paired".to_string())),
         };
 
-        let ai_diary = match self.user_repository.find_by_id(&user_id).await.unwrap() {
-            Some(user_data) => user_data.get_diary_by_id(diary_id),
-            None => None,
-        }
-        .unwrap();
-        let ai_diary_content = ai_diary.content().clone();
+        // 表示には人間の日記がある最新のエントリを使う
+        let (entry_date, user_diary_content) = match self.find_display_entry(&user_id).await? {
+            Some(entry) => entry,
+            None => {
+                return Err(ApplicationError::NotFound {
+                    entity_type: "Diary",
+                    user_id: user_id.as_str().to_string(),
+                })
+            },
+        };
+
+        // 最初の書き換えから失敗している場合は、まだAIの日記がない
+        let ai_diary_content = self
+            .diary_repository
+            .find_by_id(&user_id, &entry_date, diary_id)
+            .await?
+            .map_or(DiaryContent::new(String::new())?, |diary| {
+                diary.content().clone()
+            });
+        let status = self
+            .diary_repository
+            .find_status(&user_id, &entry_date, diary_id)
+            .await?;
+
+        Ok((ai_diary_content, user_diary_content, status))
+    }
+
+    // 表示端末に出している日記の日付。日記がまだなければ None
+    pub async fn find_display_date(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Option<NaiveDate>, ApplicationError> {
+        Ok(self
+            .find_display_entry(user_id)
+            .await?
+            .map(|(entry_date, _)| entry_date))
+    }
 
-        let user_diary = match self.user_repository.find_by_id(&user_id).await.unwrap() {
-            Some(user_data) => user_data.human_diary().clone(),
-            None => None,
+    // 最新の日付に人間の日記が無ければ (古いデータなど)、人間の日記がある前の日付まで遡る
+    async fn find_display_entry(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Option<(NaiveDate, DiaryContent)>, ApplicationError> {
+        let human_diary_id = DiaryId::new(0).unwrap();
+        let latest_date = match self.diary_repository.find_latest_date(user_id).await? {
+            Some(latest_date) => latest_date,
+            None => return Ok(None),
+        };
+        if let Some(diary) = self
+            .diary_repository
+            .find_by_id(user_id, &latest_date, &human_diary_id)
+            .await?
+        {
+            return Ok(Some((latest_date, diary.content().clone())));
         }
-        .unwrap();
-        let user_diary_content = user_diary.content().clone();
 
-        Ok((ai_diary_content, user_diary_content))
+        Ok(self
+            .diary_repository
+            .find_entries(user_id)
+            .await?
+            .into_iter()
+            .find_map(|entry| {
+                entry
+                    .find(&human_diary_id)
+                    .map(|diary| (*entry.date(), diary.content().clone()))
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::domain::entity::diary::Diary;
+    use crate::domain::entity::display::DisplaySession;
+    use crate::infrastructure::database::memory::{
+        InMemoryDiaryRepository, InMemoryDisplaySessionRepository,
+    };
+
+    fn diary(id: i32, text: &str) -> Diary {
+        Diary::new(
+            DiaryId::new(id).unwrap(),
+            DiaryContent::new(text.to_string()).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_display_skips_entry_without_human_diary() {
+        let display_repository = InMemoryDisplaySessionRepository::new();
+        let diary_repository = InMemoryDiaryRepository::new();
+        let user_id = UserId::new("display_user".to_string()).unwrap();
+        let display_id = DisplayId::new("display".to_string());
+        display_repository
+            .create(&DisplaySession::new(
+                display_id.clone(),
+                None,
+                Some(user_id.clone()),
+                Utc::now().naive_utc(),
+            ))
+            .await
+            .unwrap();
+        let usecase = GetDiaryUseCase::new(display_repository, diary_repository.clone());
+        let persona_id = DiaryId::new(1).unwrap();
+
+        // 日記が1つも無ければ NotFound
+        assert!(matches!(
+            usecase.get_display_diary(&display_id, &persona_id).await,
+            Err(ApplicationError::NotFound { .. })
+        ));
+
+        let yesterday = NaiveDate::from_ymd_opt(2024, 7, 10).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 7, 11).unwrap();
+        diary_repository
+            .update_all(
+                &user_id,
+                &yesterday,
+                &[diary(0, "昨日の日記．"), diary(1, "昨日の書き換え．")],
+                &[],
+            )
+            .await
+            .unwrap();
+        // 今日の分はペルソナの日記しか無い
+        diary_repository
+            .update_all(&user_id, &today, &[diary(1, "今日の書き換え．")], &[])
+            .await
+            .unwrap();
+
+        let (ai_content, human_content, _) = usecase
+            .get_display_diary(&display_id, &persona_id)
+            .await
+            .unwrap();
+        assert_eq!(ai_content.to_value(), "昨日の書き換え．");
+        assert_eq!(human_content.to_value(), "昨日の日記．");
     }
 }
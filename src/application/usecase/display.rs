@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::application::error::ApplicationError;
+use crate::domain::entity::display::{DisplayId, DisplaySession};
+use crate::domain::entity::user::UserId;
+use crate::domain::repository::display::DisplaySessionRepository;
+
+// 使用中のコードと重なった場合に引き直す回数
+const PAIRING_CODE_ATTEMPTS: usize = 5;
+// 6桁のコードを総当たりで試されないよう、書き手ごとに一定時間内に間違えられる回数を制限する
+const MAX_PAIRING_FAILURES: u32 = 5;
+const PAIRING_FAILURE_WINDOW: Duration = Duration::from_secs(60);
+// 書き手を作り直せば上の制限は逃れられるので、誰が間違えたかに関わらず、間違いがこの回数に達したら
+// 使われていないコードを全て使えなくする。間違ったコードはどの端末のコードへの試行にもなるため
+const MAX_CODE_GUESSES: u32 = 20;
+
+#[derive(Clone)]
+pub struct DisplayUseCase<S: DisplaySessionRepository> {
+    display_repository: S,
+    // ユーザーID -> (間違えた回数, 最初に間違えた時刻)
+    pairing_failures: Arc<Mutex<HashMap<String, (u32, Instant)>>>,
+    // 最後にコードを使えなくしてから、全ての書き手が間違えた回数
+    code_guesses: Arc<Mutex<u32>>,
+}
+
+impl<S: DisplaySessionRepository> DisplayUseCase<S> {
+    pub fn new(display_repository: S) -> Self {
+        Self {
+            display_repository,
+            pairing_failures: Arc::new(Mutex::new(HashMap::new())),
+            code_guesses: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    // 表示端末を登録し、画面に出す6桁のペアリングコードを発行する
+    // 期限切れのまま使われなかったコードは先に消し、同じコードをもう一度発行できるようにする
+    pub async fn register(&self) -> Result<DisplaySession, ApplicationError> {
+        self.display_repository
+            .release_expired_codes(DisplaySession::pairing_issued_before(
+                Utc::now().naive_utc(),
+            ))
+            .await?;
+        for _ in 0..PAIRING_CODE_ATTEMPTS {
+            let pairing_code = format!("{:06}", Uuid::new_v4().as_u128() % 1_000_000);
+            if self
+                .display_repository
+                .find_by_pairing_code(&pairing_code)
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+
+            let session = DisplaySession::new(
+                DisplayId::new(Uuid::new_v4().to_string()),
+                Some(pairing_code),
+                None,
+                Utc::now().naive_utc(),
+            );
+            self.display_repository.create(&session).await?;
+            return Ok(session);
+        }
+
+        Err(ApplicationError::Unexpected(
+            "failed to allocate a pairing code".to_string(),
+        ))
+    }
+
+    // 書き手が入力したペアリングコードの表示端末にユーザーを紐付ける
+    pub async fn pair(
+        &self,
+        user_id: &UserId,
+        pairing_code: &str,
+    ) -> Result<DisplayId, ApplicationError> {
+        if self.is_pairing_limited(user_id) {
+            return Err(ApplicationError::TooManyRequests(
+                "too many pairing attempts".to_string(),
+            ));
+        }
+        let result = self.try_pair(user_id, pairing_code).await;
+        if matches!(
+            result,
+            Err(ApplicationError::NotFound { .. } | ApplicationError::Validation(_))
+        ) {
+            self.record_pairing_failure(user_id).await?;
+        }
+        result
+    }
+
+    async fn try_pair(
+        &self,
+        user_id: &UserId,
+        pairing_code: &str,
+    ) -> Result<DisplayId, ApplicationError> {
+        let not_found = || ApplicationError::NotFound {
+            entity_type: "DisplaySession",
+            user_id: user_id.as_str().to_string(),
+        };
+        let session = match self
+            .display_repository
+            .find_by_pairing_code(pairing_code)
+            .await?
+        {
+            Some(session) => session,
+            None => return Err(not_found()),
+        };
+        if session.is_pairing_expired(Utc::now().naive_utc()) {
+            return Err(ApplicationError::Validation(
+                "pairing code has expired".to_string(),
+            ));
+        }
+
+        // 読んでから紐付けるまでに他の書き手が同じコードを使っていた場合
+        if !self
+            .display_repository
+            .pair(session.id(), pairing_code, user_id)
+            .await?
+        {
+            return Err(not_found());
+        }
+
+        Ok(session.id().clone())
+    }
+
+    fn is_pairing_limited(&self, user_id: &UserId) -> bool {
+        let mut failures = self.pairing_failures.lock().unwrap();
+        failures.retain(|_, (_, since)| since.elapsed() < PAIRING_FAILURE_WINDOW);
+        failures
+            .get(user_id.as_str())
+            .is_some_and(|(count, _)| *count >= MAX_PAIRING_FAILURES)
+    }
+
+    async fn record_pairing_failure(&self, user_id: &UserId) -> Result<(), ApplicationError> {
+        self.pairing_failures
+            .lock()
+            .unwrap()
+            .entry(user_id.as_str().to_string())
+            .or_insert((0, Instant::now()))
+            .0 += 1;
+
+        let rotate = {
+            let mut guesses = self.code_guesses.lock().unwrap();
+            *guesses += 1;
+            let rotate = *guesses >= MAX_CODE_GUESSES;
+            if rotate {
+                *guesses = 0;
+            }
+            rotate
+        };
+        // 表示端末は期限切れのときと同じく、登録し直して新しいコードを受け取る
+        if rotate {
+            self.display_repository
+                .release_expired_codes(Utc::now().naive_utc())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::infrastructure::database::memory::InMemoryDisplaySessionRepository;
+
+    fn setup() -> (
+        DisplayUseCase<InMemoryDisplaySessionRepository>,
+        InMemoryDisplaySessionRepository,
+        UserId,
+    ) {
+        let display_repository = InMemoryDisplaySessionRepository::new();
+        let usecase = DisplayUseCase::new(display_repository.clone());
+        let user_id = UserId::new("display_test_user".to_string()).unwrap();
+        (usecase, display_repository, user_id)
+    }
+
+    #[tokio::test]
+    async fn test_pair_binds_user_to_display() {
+        let (usecase, display_repository, user_id) = setup();
+        let session = usecase.register().await.unwrap();
+        let pairing_code = session.pairing_code().clone().unwrap();
+        assert_eq!(pairing_code.len(), 6);
+
+        let display_id = usecase.pair(&user_id, &pairing_code).await.unwrap();
+
+        assert_eq!(&display_id, session.id());
+        let paired = display_repository
+            .find_by_id(&display_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(paired.user_id(), &Some(user_id.clone()));
+        // 同じコードで別の書き手が横取りすることはできない
+        let other = UserId::new("other_user".to_string()).unwrap();
+        assert!(matches!(
+            usecase.pair(&other, &pairing_code).await,
+            Err(ApplicationError::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pair_is_limited_after_repeated_failures() {
+        let (usecase, _, user_id) = setup();
+        let session = usecase.register().await.unwrap();
+        let pairing_code = session.pairing_code().clone().unwrap();
+        let wrong_code = if pairing_code == "000000" {
+            "000001"
+        } else {
+            "000000"
+        };
+
+        for _ in 0..MAX_PAIRING_FAILURES {
+            assert!(matches!(
+                usecase.pair(&user_id, wrong_code).await,
+                Err(ApplicationError::NotFound { .. })
+            ));
+        }
+
+        // 正しいコードでも、しばらくは試せない
+        assert!(matches!(
+            usecase.pair(&user_id, &pairing_code).await,
+            Err(ApplicationError::TooManyRequests(_))
+        ));
+        let other = UserId::new("other_user".to_string()).unwrap();
+        assert!(usecase.pair(&other, &pairing_code).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_codes_are_released_after_guesses_from_many_users() {
+        let (usecase, display_repository, _) = setup();
+        let session = usecase.register().await.unwrap();
+        let pairing_code = session.pairing_code().clone().unwrap();
+        let wrong_code = if pairing_code == "000000" {
+            "000001"
+        } else {
+            "000000"
+        };
+
+        // 書き手を作り直しながら試しても、間違いは全体で数える
+        for attempt in 0..MAX_CODE_GUESSES {
+            let user_id = UserId::new(format!("guessing_user_{}", attempt)).unwrap();
+            assert!(matches!(
+                usecase.pair(&user_id, wrong_code).await,
+                Err(ApplicationError::NotFound { .. })
+            ));
+        }
+
+        assert!(display_repository
+            .find_by_pairing_code(&pairing_code)
+            .await
+            .unwrap()
+            .is_none());
+        let other = UserId::new("other_user".to_string()).unwrap();
+        assert!(matches!(
+            usecase.pair(&other, &pairing_code).await,
+            Err(ApplicationError::NotFound { .. })
+        ));
+        // 登録し直せば新しいコードで紐付けられる
+        let session = usecase.register().await.unwrap();
+        let pairing_code = session.pairing_code().clone().unwrap();
+        assert!(usecase.pair(&other, &pairing_code).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_register_releases_expired_codes() {
+        let (usecase, display_repository, _) = setup();
+        let expired = DisplaySession::new(
+            DisplayId::new("expired_display".to_string()),
+            Some("123456".to_string()),
+            None,
+            Utc::now().naive_utc() - Duration::minutes(11),
+        );
+        display_repository.create(&expired).await.unwrap();
+
+        usecase.register().await.unwrap();
+
+        assert!(display_repository
+            .find_by_pairing_code("123456")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pair_rejects_expired_code() {
+        let (usecase, display_repository, user_id) = setup();
+        let issued_at = Utc::now().naive_utc() - Duration::minutes(11);
+        let session = DisplaySession::new(
+            DisplayId::new("expired_display".to_string()),
+            Some("123456".to_string()),
+            None,
+            issued_at,
+        );
+        display_repository.create(&session).await.unwrap();
+
+        let result = usecase.pair(&user_id, "123456").await;
+
+        assert!(matches!(result, Err(ApplicationError::Validation(_))));
+    }
+}
@@ -0,0 +1,34 @@
+use chrono::NaiveDate;
+
+use crate::application::error::ApplicationError;
+use crate::domain::entity::entry::DiaryEntry;
+use crate::domain::entity::user::UserId;
+use crate::domain::repository::diary::DiaryRepository;
+
+#[derive(Clone)]
+pub struct GetEntriesUseCase<D: DiaryRepository> {
+    diary_repository: D,
+}
+
+impl<D: DiaryRepository> GetEntriesUseCase<D> {
+    pub fn new(diary_repository: D) -> Self { Self { diary_repository } }
+
+    pub async fn get_entries(&self, user_id: &UserId) -> Result<Vec<DiaryEntry>, ApplicationError> {
+        Ok(self.diary_repository.find_entries(user_id).await?)
+    }
+
+    pub async fn get_entry(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+    ) -> Result<DiaryEntry, ApplicationError> {
+        let diaries = self.diary_repository.find_all(user_id, entry_date).await?;
+        if diaries.is_empty() {
+            return Err(ApplicationError::NotFound {
+                entity_type: "DiaryEntry",
+                user_id: user_id.as_str().to_string(),
+            });
+        }
+        Ok(DiaryEntry::new(*entry_date, diaries))
+    }
+}
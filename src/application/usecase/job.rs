@@ -0,0 +1,660 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use log::warn;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::application::error::ApplicationError;
+use crate::application::generation::Generation;
+use crate::application::usecase::mutate::{MutateUsecase, PersonaMutation, PersonaOutcome};
+use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+use crate::domain::entity::job::{JobId, JobStatus, MutationJob, PersonaJob};
+use crate::domain::entity::user::UserId;
+use crate::domain::repository::diary::DiaryRepository;
+use crate::domain::repository::job::MutationJobRepository;
+use crate::domain::repository::persona::PersonaRepository;
+use crate::domain::repository::revision::DiaryRevisionRepository;
+use crate::domain::repository::user::UserRepository;
+use crate::domain::service::llm::{CacheMode, LlmClient};
+
+pub const DEFAULT_WORKERS: usize = 4;
+// 1人のペルソナ分を試す回数。使い切ったら failed にする
+const MAX_ATTEMPTS: i32 = 3;
+// 登録の通知を取りこぼしても、この間隔でキューを見直す
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone)]
+pub struct MutationJobUseCase<
+    J: MutationJobRepository,
+    R: UserRepository,
+    D: DiaryRepository,
+    V: DiaryRevisionRepository,
+    P: PersonaRepository,
+    C: LlmClient,
+> {
+    job_repository: Arc<J>,
+    user_repository: Arc<R>,
+    persona_repository: Arc<P>,
+    mutate_usecase: Arc<MutateUsecase<R, D, V, P, C>>,
+    // ジョブごとの書き換えの世代。再起動後に拾ったジョブは最初に処理するときに作る
+    generations: Arc<Mutex<HashMap<String, Generation>>>,
+    notify: Arc<Notify>,
+}
+
+impl<
+        J: MutationJobRepository,
+        R: UserRepository,
+        D: DiaryRepository,
+        V: DiaryRevisionRepository,
+        P: PersonaRepository,
+        C: LlmClient,
+    > MutationJobUseCase<J, R, D, V, P, C>
+{
+    pub fn new(
+        job_repository: J,
+        user_repository: R,
+        persona_repository: P,
+        mutate_usecase: MutateUsecase<R, D, V, P, C>,
+    ) -> Self {
+        Self {
+            job_repository: Arc::new(job_repository),
+            user_repository: Arc::new(user_repository),
+            persona_repository: Arc::new(persona_repository),
+            mutate_usecase: Arc::new(mutate_usecase),
+            generations: Arc::new(Mutex::new(HashMap::new())),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    // 有効なペルソナごとの処理を pending で登録し、すぐに返す
+    pub async fn enqueue(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        content: &DiaryContent,
+        cache_mode: CacheMode,
+    ) -> Result<MutationJob, ApplicationError> {
+        if self.user_repository.find_by_id(user_id).await?.is_none() {
+            return Err(ApplicationError::NotFound {
+                entity_type: "User",
+                user_id: user_id.as_str().to_string(),
+            });
+        }
+
+        let personas = self.persona_repository.find_enabled().await?;
+        let job = MutationJob::new(
+            JobId::new(Uuid::new_v4().to_string()),
+            user_id.clone(),
+            *entry_date,
+            content.clone(),
+            Utc::now().naive_utc(),
+            personas
+                .iter()
+                .map(|persona| PersonaJob::new(persona.id().clone(), JobStatus::Pending, 0))
+                .collect(),
+        );
+        // 登録した時点で世代を進め、書き換え中の古いリクエストを打ち切る
+        // キャッシュを使わない指定は世代と一緒にメモリ上にだけ持つので、再起動後に再開した分はキャッシュを使う
+        let generation = self
+            .mutate_usecase
+            .begin_generation(user_id)
+            .with_cache_mode(cache_mode);
+        self.job_repository.create(&job).await?;
+        if job.persona_jobs().is_empty() {
+            self.mutate_usecase
+                .save_entry(user_id, entry_date, content, &[], &generation, None)
+                .await?;
+            return Ok(job);
+        }
+        self.generations
+            .lock()
+            .unwrap()
+            .insert(job.id().as_str().to_string(), generation);
+        self.notify.notify_waiters();
+
+        Ok(job)
+    }
+
+    // 他のユーザーのジョブは存在しないものとして扱う
+    pub async fn get_job(
+        &self,
+        user_id: &UserId,
+        id: &JobId,
+    ) -> Result<MutationJob, ApplicationError> {
+        match self.job_repository.find_by_id(id).await? {
+            Some(job) if job.user_id() == user_id => Ok(job),
+            _ => Err(ApplicationError::NotFound {
+                entity_type: "MutationJob",
+                user_id: user_id.as_str().to_string(),
+            }),
+        }
+    }
+
+    // 前のプロセスで処理中だったものをキューに戻し、保存する前に落ちたジョブを締め直す。ワーカーを起動する前に呼ぶ
+    pub async fn recover(&self) -> Result<(), ApplicationError> {
+        self.job_repository.requeue_running().await?;
+        // 世代は登録順に作るので、同じユーザーのジョブは新しい方が最新の世代になる
+        for job in self.job_repository.find_unsaved().await? {
+            self.generation_for(&job);
+        }
+        self.finish_unsaved().await;
+        Ok(())
+    }
+
+    pub fn spawn_workers(self: Arc<Self>, count: usize) {
+        for _ in 0..count {
+            let usecase = Arc::clone(&self);
+            tokio::spawn(async move { usecase.work().await });
+        }
+    }
+
+    async fn work(&self) {
+        loop {
+            let notified = self.notify.notified();
+            match self.run_next().await {
+                Ok(true) => continue,
+                Ok(false) => {},
+                Err(err) => warn!("mutation worker failed: {}", err),
+            }
+            tokio::select! {
+                _ = notified => {},
+                _ = tokio::time::sleep(POLL_INTERVAL) => {},
+            }
+        }
+    }
+
+    // キューからペルソナ1人分を処理する。待っているものが無ければ保存に失敗したジョブを締め直し、
+    // どちらも無ければ false を返す
+    pub async fn run_next(&self) -> Result<bool, ApplicationError> {
+        let (job, persona_id) = match self.job_repository.claim_next().await? {
+            Some(claimed) => claimed,
+            None => return Ok(self.finish_unsaved().await),
+        };
+        // 再起動後は世代がメモリに残っていないので、ジョブの新旧は DB の登録順で決める
+        // 同じユーザーの新しいジョブがあれば、書き換えずに打ち切る
+        if self
+            .job_repository
+            .find_latest_id(job.user_id())
+            .await?
+            .as_ref()
+            != Some(job.id())
+        {
+            self.job_repository
+                .update_status(job.id(), &persona_id, JobStatus::Superseded)
+                .await?;
+            self.finish_if_complete(job.id()).await?;
+            return Ok(true);
+        }
+        let generation = self.generation_for(&job);
+
+        let outcome = self
+            .mutate_usecase
+            .mutate_persona(
+                job.user_id(),
+                job.entry_date(),
+                &persona_id,
+                job.content(),
+                &generation,
+            )
+            .await;
+        // LLM の失敗も保存の失敗と同じく再試行する
+        // 書き換えた日記はジョブに残し、全ペルソナが終わってから人間の日記とまとめて保存する
+        let status = match outcome {
+            Ok(PersonaMutation::Mutated {
+                diary,
+                target_index,
+            }) => match self
+                .job_repository
+                .save_output(job.id(), &persona_id, &diary, target_index)
+                .await
+            {
+                Ok(()) => {
+                    self.finish_if_complete(job.id()).await?;
+                    return Ok(true);
+                },
+                Err(err) => retry_or_fail(&job, &persona_id, err.to_string()),
+            },
+            Ok(PersonaMutation::Finished(PersonaOutcome::Failed(reason))) => {
+                retry_or_fail(&job, &persona_id, reason)
+            },
+            Ok(PersonaMutation::Finished(_)) => JobStatus::Superseded,
+            Err(err) => retry_or_fail(&job, &persona_id, err.to_string()),
+        };
+        self.job_repository
+            .update_status(job.id(), &persona_id, status)
+            .await?;
+
+        if status.is_finished() {
+            self.finish_if_complete(job.id()).await?;
+        }
+        Ok(true)
+    }
+
+    fn generation_for(&self, job: &MutationJob) -> Generation {
+        self.generations
+            .lock()
+            .unwrap()
+            .entry(job.id().as_str().to_string())
+            .or_insert_with(|| self.mutate_usecase.begin_generation(job.user_id()))
+            .clone()
+    }
+
+    // 全ペルソナの処理が終わったジョブは、書き換えた日記と人間の日記を1つのトランザクションで保存して締める
+    // 保存待ちの mutated は保存と同じトランザクションで done になる
+    async fn finish_if_complete(&self, id: &JobId) -> Result<(), ApplicationError> {
+        let job = match self.job_repository.find_by_id(id).await? {
+            Some(job) if job.is_settled() => job,
+            _ => return Ok(()),
+        };
+        // 最後の2人が同時に終わっても、保存するのは世代を取り出した方だけ
+        let generation = match self.generations.lock().unwrap().remove(id.as_str()) {
+            Some(generation) => generation,
+            None => return Ok(()),
+        };
+        // 再起動後に締め直す場合も、同じユーザーの新しいジョブがあれば保存しない
+        let superseded = job
+            .persona_jobs()
+            .iter()
+            .any(|persona_job| *persona_job.status() == JobStatus::Superseded)
+            || self
+                .job_repository
+                .find_latest_id(job.user_id())
+                .await?
+                .as_ref()
+                != Some(id);
+        if superseded {
+            return self.supersede_outputs(&job).await;
+        }
+        let mutated: Vec<(Diary, i32)> = job
+            .persona_jobs()
+            .iter()
+            .filter_map(|persona_job| persona_job.output().clone())
+            .collect();
+        let saved = match self
+            .mutate_usecase
+            .save_entry(
+                job.user_id(),
+                job.entry_date(),
+                job.content(),
+                &mutated,
+                &generation,
+                Some(id),
+            )
+            .await
+        {
+            Ok(saved) => saved,
+            Err(err) => {
+                // 保存できなかったジョブは mutated のまま残るので、世代を戻して後で締め直す
+                if let Ok(Some(job)) = self.job_repository.find_by_id(id).await {
+                    if job.is_unsaved() {
+                        self.generations
+                            .lock()
+                            .unwrap()
+                            .insert(id.as_str().to_string(), generation);
+                    }
+                }
+                return Err(err);
+            },
+        };
+        // 保存する前に新しいリクエストが来ていた場合は、書き換えたペルソナも打ち切られたことにする
+        if !saved {
+            self.supersede_outputs(&job).await?;
+        }
+        Ok(())
+    }
+
+    // 保存に失敗して世代を戻したジョブを締め直す。1つでも締められれば true を返す
+    // 世代を取り出した方だけが保存するので、他のワーカーが保存している最中のジョブには触らない
+    async fn finish_unsaved(&self) -> bool {
+        let jobs = match self.job_repository.find_unsaved().await {
+            Ok(jobs) => jobs,
+            Err(err) => {
+                warn!("failed to find unsaved mutation jobs: {}", err);
+                return false;
+            },
+        };
+        let mut finished = false;
+        for job in jobs {
+            if !self
+                .generations
+                .lock()
+                .unwrap()
+                .contains_key(job.id().as_str())
+            {
+                continue;
+            }
+            match self.finish_if_complete(job.id()).await {
+                Ok(()) => finished = true,
+                Err(err) => warn!("failed to save mutation job {}: {}", job.id().as_str(), err),
+            }
+        }
+        finished
+    }
+
+    // 保存せずに締めるジョブの、保存待ちのペルソナを打ち切られたことにする
+    async fn supersede_outputs(&self, job: &MutationJob) -> Result<(), ApplicationError> {
+        for persona_job in job.persona_jobs() {
+            if *persona_job.status() == JobStatus::Mutated {
+                self.job_repository
+                    .update_status(job.id(), persona_job.persona_id(), JobStatus::Superseded)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 試行回数が残っていれば pending に戻し、使い切っていれば failed にする
+fn retry_or_fail(job: &MutationJob, persona_id: &DiaryId, err: String) -> JobStatus {
+    let attempts = job
+        .find(persona_id)
+        .map_or(MAX_ATTEMPTS, |persona_job| *persona_job.attempts());
+    if attempts < MAX_ATTEMPTS {
+        warn!(
+            "mutation job {} failed for persona {} (attempt {}): {}",
+            job.id().as_str(),
+            persona_id.to_id(),
+            attempts,
+            err
+        );
+        JobStatus::Pending
+    } else {
+        warn!(
+            "mutation job {} gave up on persona {}: {}",
+            job.id().as_str(),
+            persona_id.to_id(),
+            err
+        );
+        JobStatus::Failed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::broadcast::DiaryBroadcaster;
+    use crate::domain::entity::diary::DiaryId;
+    use crate::domain::entity::persona::Persona;
+    use crate::infrastructure::api::mock::{MockLlmClient, MockReply};
+    use crate::infrastructure::database::memory::{
+        InMemoryDiaryRepository, InMemoryDiaryRevisionRepository, InMemoryMutationJobRepository,
+        InMemoryPersonaRepository, InMemoryUserRepository,
+    };
+
+    type TestUseCase = MutationJobUseCase<
+        InMemoryMutationJobRepository,
+        InMemoryUserRepository,
+        InMemoryDiaryRepository,
+        InMemoryDiaryRevisionRepository,
+        InMemoryPersonaRepository,
+        MockLlmClient,
+    >;
+
+    fn today() -> NaiveDate { NaiveDate::from_ymd_opt(2024, 7, 11).unwrap() }
+
+    async fn setup() -> (
+        TestUseCase,
+        InMemoryMutationJobRepository,
+        InMemoryDiaryRepository,
+        MockLlmClient,
+        UserId,
+    ) {
+        let job_repository = InMemoryMutationJobRepository::new();
+        let user_repository = InMemoryUserRepository::new();
+        let diary_repository = InMemoryDiaryRepository::new().with_jobs(job_repository.clone());
+        let persona_repository = InMemoryPersonaRepository::new(
+            (1..=2)
+                .map(|id| {
+                    Persona::new(
+                        DiaryId::new(id).unwrap(),
+                        format!("persona_{}", id),
+                        format!("プロンプト{}", id),
+                        id,
+                    )
+                })
+                .collect(),
+        );
+        let user_id = UserId::new("job_test_user".to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        let llm_client = MockLlmClient::new().with_fixed_response("書き換えた．");
+        let mutate_usecase = MutateUsecase::new(
+            llm_client.clone(),
+            "gpt-4-turbo".to_string(),
+            user_repository.clone(),
+            diary_repository.clone(),
+            InMemoryDiaryRevisionRepository::new(),
+            persona_repository.clone(),
+            DiaryBroadcaster::new(),
+        );
+        let usecase = MutationJobUseCase::new(
+            job_repository.clone(),
+            user_repository,
+            persona_repository,
+            mutate_usecase,
+        );
+        (
+            usecase,
+            job_repository,
+            diary_repository,
+            llm_client,
+            user_id,
+        )
+    }
+
+    // 前のプロセスで登録されたまま残っていたジョブ
+    fn stored_job(id: &str, user_id: &UserId, text: &str, personas: &[i32]) -> MutationJob {
+        MutationJob::new(
+            JobId::new(id.to_string()),
+            user_id.clone(),
+            today(),
+            DiaryContent::new(text.to_string()).unwrap(),
+            Utc::now().naive_utc(),
+            personas
+                .iter()
+                .map(|id| PersonaJob::new(DiaryId::new(*id).unwrap(), JobStatus::Pending, 0))
+                .collect(),
+        )
+    }
+
+    async fn diary_text(
+        diary_repository: &InMemoryDiaryRepository,
+        user_id: &UserId,
+        id: i32,
+    ) -> Option<String> {
+        diary_repository
+            .find_by_id(user_id, &today(), &DiaryId::new(id).unwrap())
+            .await
+            .unwrap()
+            .map(|diary| diary.content().to_value().clone())
+    }
+
+    #[tokio::test]
+    async fn test_queued_job_is_processed_per_persona() {
+        let (usecase, _, diary_repository, _, user_id) = setup().await;
+        let content = DiaryContent::new("一文目．".to_string()).unwrap();
+
+        let job = usecase
+            .enqueue(&user_id, &today(), &content, CacheMode::Use)
+            .await
+            .unwrap();
+        assert_eq!(job.status(), JobStatus::Pending);
+        assert_eq!(job.persona_jobs().len(), 2);
+
+        assert!(usecase.run_next().await.unwrap());
+        let running = usecase.get_job(&user_id, job.id()).await.unwrap();
+        assert_eq!(running.status(), JobStatus::Running);
+        // 人間の日記もペルソナの日記も、全ペルソナが終わるまで保存しない
+        assert_eq!(diary_text(&diary_repository, &user_id, 0).await, None);
+        assert_eq!(diary_text(&diary_repository, &user_id, 1).await, None);
+
+        assert!(usecase.run_next().await.unwrap());
+        assert!(!usecase.run_next().await.unwrap());
+
+        let done = usecase.get_job(&user_id, job.id()).await.unwrap();
+        assert_eq!(done.status(), JobStatus::Done);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 2).await.unwrap(),
+            "書き換えた．"
+        );
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 0).await.unwrap(),
+            "一文目．"
+        );
+        // 他のユーザーからは見えない
+        let other = UserId::new("other_user".to_string()).unwrap();
+        assert!(matches!(
+            usecase.get_job(&other, job.id()).await,
+            Err(ApplicationError::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_failed_persona_is_retried_then_marked_failed() {
+        let (usecase, job_repository, diary_repository, llm_client, user_id) = setup().await;
+        let job = stored_job("retry_job", &user_id, "一文目．", &[1]);
+        job_repository.create(&job).await.unwrap();
+        for _ in 0..MAX_ATTEMPTS {
+            llm_client.push_reply(MockReply::Unavailable("connection reset".to_string()));
+        }
+
+        // 試行回数が残っている間は pending に戻る
+        for attempt in 1..MAX_ATTEMPTS {
+            assert!(usecase.run_next().await.unwrap());
+            let retried = usecase.get_job(&user_id, job.id()).await.unwrap();
+            assert_eq!(retried.status(), JobStatus::Pending);
+            assert_eq!(retried.persona_jobs()[0].attempts(), &attempt);
+        }
+        assert!(usecase.run_next().await.unwrap());
+        assert!(!usecase.run_next().await.unwrap());
+
+        let failed = usecase.get_job(&user_id, job.id()).await.unwrap();
+        assert_eq!(failed.status(), JobStatus::Failed);
+        assert_eq!(failed.persona_jobs()[0].attempts(), &MAX_ATTEMPTS);
+        assert_eq!(llm_client.requests().len(), MAX_ATTEMPTS as usize);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 0).await.unwrap(),
+            "一文目．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_older_job_is_superseded_after_restart() {
+        let (usecase, job_repository, diary_repository, _, user_id) = setup().await;
+        let older = stored_job("older_job", &user_id, "古い日記．", &[1, 2]);
+        let newer = stored_job("newer_job", &user_id, "新しい日記．", &[1, 2]);
+        job_repository.create(&older).await.unwrap();
+        job_repository.create(&newer).await.unwrap();
+
+        while usecase.run_next().await.unwrap() {}
+
+        let older = usecase.get_job(&user_id, older.id()).await.unwrap();
+        assert_eq!(older.status(), JobStatus::Superseded);
+        let newer = usecase.get_job(&user_id, newer.id()).await.unwrap();
+        assert_eq!(newer.status(), JobStatus::Done);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 0).await.unwrap(),
+            "新しい日記．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_job_is_saved_again_after_save_failure() {
+        let (usecase, _, diary_repository, _, user_id) = setup().await;
+        let content = DiaryContent::new("一文目．".to_string()).unwrap();
+        let job = usecase
+            .enqueue(&user_id, &today(), &content, CacheMode::Use)
+            .await
+            .unwrap();
+        diary_repository.fail_next_writes(1);
+
+        assert!(usecase.run_next().await.unwrap());
+        assert!(usecase.run_next().await.is_err());
+        // 保存できなかったジョブは done にならず、書き換えた日記を残したまま待つ
+        let unsaved = usecase.get_job(&user_id, job.id()).await.unwrap();
+        assert_eq!(unsaved.status(), JobStatus::Running);
+        assert!(unsaved.is_unsaved());
+        assert_eq!(diary_text(&diary_repository, &user_id, 0).await, None);
+
+        // キューが空になったワーカーが締め直す
+        assert!(usecase.run_next().await.unwrap());
+        assert!(!usecase.run_next().await.unwrap());
+        let done = usecase.get_job(&user_id, job.id()).await.unwrap();
+        assert_eq!(done.status(), JobStatus::Done);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 0).await.unwrap(),
+            "一文目．"
+        );
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await.unwrap(),
+            "書き換えた．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_save_supersedes_job() {
+        let (usecase, _, diary_repository, _, user_id) = setup().await;
+        let content = DiaryContent::new("一文目．".to_string()).unwrap();
+        let job = usecase
+            .enqueue(&user_id, &today(), &content, CacheMode::Use)
+            .await
+            .unwrap();
+
+        assert!(usecase.run_next().await.unwrap());
+        // 書き換えてから保存するまでの間に、他のリクエストが同じペルソナの日記を保存した
+        let other = Diary::new(
+            DiaryId::new(1).unwrap(),
+            DiaryContent::new("横から．".to_string()).unwrap(),
+        )
+        .unwrap();
+        diary_repository
+            .update_all(&user_id, &today(), &[other], &[])
+            .await
+            .unwrap();
+        assert!(usecase.run_next().await.unwrap());
+        assert!(!usecase.run_next().await.unwrap());
+
+        let superseded = usecase.get_job(&user_id, job.id()).await.unwrap();
+        assert_eq!(superseded.status(), JobStatus::Superseded);
+        assert_eq!(diary_text(&diary_repository, &user_id, 0).await, None);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await.unwrap(),
+            "横から．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unsaved_job_is_finished_on_recover() {
+        let (usecase, job_repository, diary_repository, _, user_id) = setup().await;
+        // 全ペルソナの書き換えを終えたところで前のプロセスが落ちた
+        let job = stored_job("unsaved_job", &user_id, "一文目．", &[1, 2]);
+        job_repository.create(&job).await.unwrap();
+        for id in 1..=2 {
+            let output = Diary::new(
+                DiaryId::new(id).unwrap(),
+                DiaryContent::new(format!("ペルソナ{}．", id)).unwrap(),
+            )
+            .unwrap();
+            job_repository
+                .save_output(job.id(), &DiaryId::new(id).unwrap(), &output, 0)
+                .await
+                .unwrap();
+        }
+
+        usecase.recover().await.unwrap();
+
+        let done = usecase.get_job(&user_id, job.id()).await.unwrap();
+        assert_eq!(done.status(), JobStatus::Done);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 0).await.unwrap(),
+            "一文目．"
+        );
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 2).await.unwrap(),
+            "ペルソナ2．"
+        );
+        assert!(!usecase.run_next().await.unwrap());
+    }
+}
@@ -1,195 +1,607 @@
 use std::sync::Arc;
 
+use chrono::{NaiveDate, Utc};
+use getset::Getters;
+use log::info;
 use tokio::task;
 
+use crate::application::broadcast::{DiaryBroadcaster, DiaryUpdate};
+use crate::application::cache::SentenceCache;
 use crate::application::error::ApplicationError;
+use crate::application::generation::{Generation, MutationGenerations};
 use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
-use crate::domain::entity::user::{User, UserId};
+use crate::domain::entity::job::JobId;
+use crate::domain::entity::persona::Persona;
+use crate::domain::entity::revision::DiaryRevision;
+use crate::domain::entity::status::DiaryStatus;
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+use crate::domain::repository::diary::DiaryRepository;
+use crate::domain::repository::persona::PersonaRepository;
+use crate::domain::repository::revision::DiaryRevisionRepository;
 use crate::domain::repository::user::UserRepository;
-use crate::infrastructure::api::openai::OpenAiClient;
+use crate::domain::service::diff::SentenceDiff;
+use crate::domain::service::llm::{CacheMode, LlmClient, LlmRequest};
 
-static PROMPTS: [&str; 4] = [
-    "入力テキストの感想・感情・意見を真逆の意味合いに書き換えてください。但し、口調・固有名詞と客観的事実は変更しないでください。",
-    "入力テキストの感想・感情・意見など主観的な部分を楽観的に書き替えてください。但し、口調・固有名詞と客観的事実は変更しないでください。",
-    "入力テキストの感想・感情・意見など主観的な部分を悲観的に書き替えてください。但し、口調・固有名詞と客観的事実は変更しないでください。",
-    "入力テキストの感想・感情・意見など主観的な部分を自己拡張的に書き替えてください。但し、口調・固有名詞と客観的事実は変更しないでください。",
-];
+// ペルソナごとの書き換えの結果。Superseded は新しいリクエストに打ち切られて保存していない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersonaOutcome {
+    Saved,
+    Failed(String),
+    Superseded,
+}
+
+// 保存する前のペルソナの書き換え結果
+// Mutated の日記は人間の日記とまとめて save_entry で保存する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersonaMutation {
+    Mutated { diary: Diary, target_index: i32 },
+    Finished(PersonaOutcome),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct MutationResult {
+    #[getset(get = "pub")]
+    mutated_length: i32,
+    #[getset(get = "pub")]
+    personas: Vec<(DiaryId, PersonaOutcome)>,
+}
 
 #[derive(Clone)]
-pub struct MutateUsecase<R: UserRepository> {
-    client: Arc<OpenAiClient>,
+pub struct MutateUsecase<
+    R: UserRepository,
+    D: DiaryRepository,
+    V: DiaryRevisionRepository,
+    P: PersonaRepository,
+    C: LlmClient,
+> {
+    client: Arc<C>,
+    // 書き換えに使うモデル。接続先の設定から渡す
+    model: String,
     user_repository: Arc<R>,
+    diary_repository: Arc<D>,
+    revision_repository: Arc<V>,
+    persona_repository: Arc<P>,
+    sentence_cache: SentenceCache,
+    broadcaster: DiaryBroadcaster,
+    generations: MutationGenerations,
 }
 
-impl<R: UserRepository> MutateUsecase<R> {
-    pub fn new(client: OpenAiClient, user_repository: R) -> Self {
+impl<
+        R: UserRepository,
+        D: DiaryRepository,
+        V: DiaryRevisionRepository,
+        P: PersonaRepository,
+        C: LlmClient,
+    > MutateUsecase<R, D, V, P, C>
+{
+    pub fn new(
+        client: C,
+        model: String,
+        user_repository: R,
+        diary_repository: D,
+        revision_repository: V,
+        persona_repository: P,
+        broadcaster: DiaryBroadcaster,
+    ) -> Self {
         Self {
             client: Arc::new(client),
+            model,
             user_repository: Arc::new(user_repository),
+            diary_repository: Arc::new(diary_repository),
+            revision_repository: Arc::new(revision_repository),
+            persona_repository: Arc::new(persona_repository),
+            sentence_cache: SentenceCache::new(),
+            broadcaster,
+            generations: MutationGenerations::new(),
         }
     }
 
-    pub async fn process_mutation_by_id(
-        self: Arc<Self>,
-        target_id: &DiaryId,
-        target_index: i32,
-        user_data: &User,
+    // ペルソナの日記を書き換えるが、保存はしない
+    // 返す日記の version は書き換えを始めた時点の版数で、LLM の応答を待つ間に他のリクエストが保存していたら保存時に Conflict になる
+    async fn mutate_diary(
+        &self,
+        persona: &Persona,
+        aligned: Option<(&SentenceDiff, &Diary)>,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
         new_content: &DiaryContent,
-    ) -> Result<(), ApplicationError> {
-        let new_text = new_content.to_value();
-        let prompt = get_prompt_by_id(target_id.to_id()).unwrap();
-        let mut mutated_text = String::new();
-
-        if target_index >= new_content.to_length() {
-            // NOTE: 仕様上発生しないが、念のため 新しい日記の長さ以上の部分がtarget_indexに指定された場合長さだけ合わせる
-            let mutated_diary = user_data.clone().get_diary_by_id(target_id).unwrap();
-            mutated_text = mutated_diary.content().get_to(new_content.to_length());
-            println!("{:?}", mutated_text);
-        } else {
-            let mutated_diary = user_data
-                .clone()
-                .get_diary_by_id(target_id)
-                .unwrap_or_else(|| {
-                    Diary::new(
-                        target_id.clone(),
-                        DiaryContent::new("".to_string()).unwrap(),
+        generation: &Generation,
+    ) -> Result<PersonaMutation, ApplicationError> {
+        let target_id = persona.id();
+        let base_version = self
+            .diary_repository
+            .find_by_id(user_id, entry_date, target_id)
+            .await?
+            .map_or(0, |diary| *diary.version());
+
+        // aligned が無ければ全文を書き換える
+        let source_text = aligned.map_or(new_content.to_value().as_str(), |(diff, _)| {
+            diff.changed_text()
+        });
+        let splice = |mutated: &str| match aligned {
+            Some((diff, previous)) => diff.splice(previous.content(), mutated).unwrap(),
+            None => mutated.to_string(),
+        };
+
+        // 書き換えの途中経過も、差し込んだ後の全文として流す
+        let publish_progress = |mutated: &str| {
+            if generation.is_current() {
+                self.publish(
+                    user_id,
+                    entry_date,
+                    target_id,
+                    splice(mutated),
+                    new_content.to_length(),
+                    false,
+                )
+            }
+        };
+        let result = self
+            .mutate_sentences(user_id, persona, source_text, generation, &publish_progress)
+            .await;
+
+        let mutated = match result {
+            Ok(Some(mutated)) => mutated,
+            Ok(None) => return Ok(PersonaMutation::Finished(PersonaOutcome::Superseded)),
+            // 失敗した場合は日記を前回のまま残し、理由だけ記録する
+            Err(err) => {
+                let _save_guard = match generation.lock_if_current().await {
+                    Some(guard) => guard,
+                    None => return Ok(PersonaMutation::Finished(PersonaOutcome::Superseded)),
+                };
+                let reason = err.to_string();
+                self.diary_repository
+                    .update_status(
+                        user_id,
+                        entry_date,
+                        &DiaryStatus::failed(
+                            target_id.clone(),
+                            reason.clone(),
+                            Utc::now().naive_utc(),
+                        ),
                     )
-                    .unwrap()
-                });
-            mutated_text.push_str(mutated_diary.content().get_to(target_index).as_str());
-
-            println!("{:?}", new_content.get_from(target_index));
-            if !new_text.trim().is_empty() {
-                let content = format!(
-                    "{} ただし、改行は入力文そのままにすること。\n また、文章が不完全であるなどの場合は書き換え可能な部分を書き換えた後、不完全な部分だけはそのままで返してください。 \n 入力に対する書き換え結果以外のシステムメッセージなどの文章は入れないでください \n ================ \n{}",
-                    prompt,
-                    &new_content.get_from(target_index)
-                );
+                    .await?;
+                return Ok(PersonaMutation::Finished(PersonaOutcome::Failed(reason)));
+            },
+        };
+
+        Ok(PersonaMutation::Mutated {
+            diary: Diary::new(
+                target_id.clone(),
+                DiaryContent::new(splice(&mutated)).unwrap(),
+            )
+            .unwrap()
+            .with_version(base_version),
+            target_index: aligned.map_or(0, |(diff, _)| diff.target_index()),
+        })
+    }
+
+    fn publish(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary_id: &DiaryId,
+        text: String,
+        human_length: i32,
+        is_complete: bool,
+    ) {
+        self.broadcaster.publish(DiaryUpdate::new(
+            user_id.clone(),
+            *entry_date,
+            Diary::new(diary_id.clone(), DiaryContent::new(text).unwrap()).unwrap(),
+            human_length,
+            is_complete,
+        ));
+    }
 
-                let response = self
-                    .client
-                    .post(&serde_json::json!({
-                        "model": "gpt-4-turbo",
-                        "messages": [{"role": "user", "content": content}]
-                    }))
-                    .await;
-
-                match response {
-                    Ok(res) => {
-                        let res_json = res.json::<serde_json::Value>().await.unwrap();
-                        if let Some(mutated_response) =
-                            res_json["choices"][0]["message"]["content"].as_str()
-                        {
-                            let processed_text = process_output(mutated_response.to_string());
-                            print!("{:?}", processed_text);
-                            mutated_text.push_str(&process_output(processed_text));
-                        } else {
-                            mutated_text.push_str("Failed to mutate text.");
-                        }
-                    },
-                    Err(_) => {
-                        mutated_text.push_str("Error communicating with API.");
-                    },
+    // 以前に書き換えた文はその結果を使い、まだ書き換えていない文だけをまとめて1回で送る
+    // 応答は文に分けて元の文に対応させ、文ごとにキャッシュしておく
+    // 応答の文の数が合わない場合は、間に書き換え済みの文を挟んでいれば範囲全体を送り直し、送った範囲をまとめて置き換える
+    // on_progress には書き換え中の範囲より前の文と、応答の途中までを連結して渡す
+    // 新しい世代のリクエストが来たら、応答を待たずに打ち切って None を返す
+    async fn mutate_sentences(
+        &self,
+        user_id: &UserId,
+        persona: &Persona,
+        text: &str,
+        generation: &Generation,
+        on_progress: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<Option<String>, DomainError> {
+        if !generation.is_current() {
+            return Ok(None);
+        }
+        let sentences = DiaryContent::new(text.to_string()).unwrap().sentences();
+        let is_blank = |index: usize| sentences[index].trim().is_empty();
+        let mut mutated: Vec<Option<String>> = sentences
+            .iter()
+            .map(|sentence| {
+                if sentence.trim().is_empty() {
+                    return Some(sentence.clone());
                 }
-            } else {
-                mutated_text.push_str(new_text);
+                match generation.cache_mode() {
+                    CacheMode::Use => self.sentence_cache.get(user_id, persona.id(), sentence),
+                    CacheMode::Bypass => None,
+                }
+            })
+            .collect();
+
+        let unseen: Vec<usize> = (0..sentences.len())
+            .filter(|&index| mutated[index].is_none())
+            .collect();
+        let (first, last) = match (unseen.first(), unseen.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => {
+                let text: String = mutated.into_iter().flatten().collect();
+                if !text.is_empty() {
+                    on_progress(&text);
+                }
+                return Ok(Some(text));
+            },
+        };
+        // 改行だけの文は送る範囲に含めるが、応答との対応には使わない
+        let mut targets: Vec<usize> = (first..=last)
+            .filter(|&index| mutated[index].is_none() || is_blank(index))
+            .collect();
+        let before: String = mutated[..first]
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        loop {
+            let source: String = targets
+                .iter()
+                .map(|&index| sentences[index].as_str())
+                .collect();
+            let on_partial = |partial: &str| {
+                on_progress(&format!(
+                    "{}{}",
+                    before,
+                    process_output(partial.to_string())
+                ))
+            };
+            let reply = tokio::select! {
+                result = self.request_mutation(persona, &source, generation.cache_mode(), &on_partial) => result?,
+                _ = generation.superseded() => return Ok(None),
+            };
+
+            let slots: Vec<usize> = targets
+                .iter()
+                .copied()
+                .filter(|&index| !is_blank(index))
+                .collect();
+            let replies: Vec<String> = DiaryContent::new(reply.clone())
+                .unwrap()
+                .sentences()
+                .into_iter()
+                .filter(|sentence| !sentence.trim().is_empty())
+                .collect();
+            if replies.len() == slots.len() {
+                for (index, reply) in slots.into_iter().zip(replies) {
+                    self.sentence_cache
+                        .insert(user_id, persona.id(), &sentences[index], &reply);
+                    mutated[index] = Some(reply);
+                }
+                break;
+            }
+            if targets.len() == last - first + 1 {
+                // 文ごとには対応させられないので、送った範囲をまとめて置き換える
+                for slot in &mut mutated[first..=last] {
+                    *slot = Some(String::new());
+                }
+                mutated[first] = Some(reply);
+                break;
             }
+            targets = (first..=last).collect();
         }
+        Ok(Some(mutated.into_iter().flatten().collect()))
+    }
 
-        let mutated_content = &DiaryContent::new(mutated_text).unwrap();
-        self.save_diary(user_data.id(), target_id, mutated_content)
-            .await?;
+    async fn request_mutation(
+        &self,
+        persona: &Persona,
+        text: &str,
+        cache_mode: CacheMode,
+        on_partial: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<String, DomainError> {
+        let content = format!(
+            "{} ただし、改行は入力文そのままにすること。\n また、文章が不完全であるなどの場合は書き換え可能な部分を書き換えた後、不完全な部分だけはそのままで返してください。 \n 入力に対する書き換え結果以外のシステムメッセージなどの文章は入れないでください \n ================ \n{}",
+            persona.prompt(),
+            text
+        );
 
-        Ok(())
+        let mutated_response = self
+            .client
+            .complete_stream(
+                &LlmRequest::new(self.model.clone(), content).with_cache_mode(cache_mode),
+                on_partial,
+            )
+            .await?;
+        Ok(process_output(mutated_response))
     }
 
     pub async fn mutate_text(
         self: Arc<Self>,
         user_id: &UserId,
+        entry_date: &NaiveDate,
         new_content: &DiaryContent,
-    ) -> Result<i32, ApplicationError> {
-        let user_data = match self.user_repository.find_by_id(user_id).await.unwrap() {
-            Some(data) => data,
-            None => {
-                return Err(ApplicationError::NotFound {
-                    entity_type: "User",
-                    user_id: (*user_id.as_str()).to_string(),
-                });
-            },
-        };
+        cache_mode: CacheMode,
+    ) -> Result<MutationResult, ApplicationError> {
+        if self.user_repository.find_by_id(user_id).await?.is_none() {
+            return Err(ApplicationError::NotFound {
+                entity_type: "User",
+                user_id: (*user_id.as_str()).to_string(),
+            });
+        }
+        // 書き続けている間は /mutate が次々に届くので、同じユーザーの古いリクエストは打ち切る
+        let generation = self.generations.begin(user_id).with_cache_mode(cache_mode);
 
-        let target_index = match &user_data.human_diary {
-            Some(old_diary) => find_target_index(new_content, old_diary.content()),
-            None => 0,
-        };
+        // 差分は同じ日付のエントリとの間で取る
+        let diaries = self.diary_repository.find_all(user_id, entry_date).await?;
+        let diff = find_diary(&diaries, &human_diary_id())
+            .map(|old_diary| SentenceDiff::between(old_diary.content(), new_content));
 
-        let ids = vec![1, 2, 3, 4];
+        // 有効なペルソナの数だけ並列に書き換える
+        let personas = self.persona_repository.find_enabled().await?;
         let mut tasks = vec![];
         let shared_self = Arc::clone(&self);
-        for id in ids {
+        for persona in personas {
             let shared_self = Arc::clone(&shared_self);
-            let user_data = user_data.clone();
+            let user_id = user_id.clone();
+            let entry_date = *entry_date;
+            let diff = diff.clone();
+            let previous = self
+                .previous_diary(&user_id, &entry_date, &diaries, persona.id())
+                .await?;
             let new_content = new_content.clone();
+            let generation = generation.clone();
             tasks.push(task::spawn(async move {
-                let target_id = &DiaryId::new(id).unwrap();
+                let persona_id = persona.id().clone();
                 shared_self
-                    .process_mutation_by_id(target_id, target_index, &user_data, &new_content)
+                    .mutate_diary(
+                        &persona,
+                        align(diff.as_ref(), previous.as_ref()),
+                        &user_id,
+                        &entry_date,
+                        &new_content,
+                        &generation,
+                    )
                     .await
+                    .map(|mutation| (persona_id, mutation))
             }));
         }
 
+        let mut mutations = vec![];
         for task in tasks {
             match task.await {
-                Ok(result) => result?,
+                Ok(result) => mutations.push(result?),
                 Err(e) => return Err(ApplicationError::Unexpected(e.to_string())), // or another variant that suits the error
             }
         }
 
-        let human_diary_id = &DiaryId::new(0).unwrap();
-        self.save_diary(user_id, human_diary_id, new_content)
+        // 全ペルソナの結果が揃ってから、人間の日記と一緒にまとめて保存する
+        // 打ち切られた場合や保存できなかった場合も、入力の長さはそのまま返す
+        let mutated: Vec<(Diary, i32)> = mutations
+            .iter()
+            .filter_map(|(_, mutation)| match mutation {
+                PersonaMutation::Mutated {
+                    diary,
+                    target_index,
+                } => Some((diary.clone(), *target_index)),
+                PersonaMutation::Finished(_) => None,
+            })
+            .collect();
+        let saved_outcome = if self
+            .save_entry(
+                user_id,
+                entry_date,
+                new_content,
+                &mutated,
+                &generation,
+                None,
+            )
+            .await?
+        {
+            PersonaOutcome::Saved
+        } else {
+            PersonaOutcome::Superseded
+        };
+
+        Ok(MutationResult {
+            mutated_length: new_content.to_length(),
+            personas: mutations
+                .into_iter()
+                .map(|(persona_id, mutation)| match mutation {
+                    PersonaMutation::Mutated { .. } => (persona_id, saved_outcome.clone()),
+                    PersonaMutation::Finished(outcome) => (persona_id, outcome),
+                })
+                .collect(),
+        })
+    }
+
+    // ユーザーを削除したときに書き換え済みの文を捨てられるよう、DeleteUsecase と共有する
+    pub fn sentence_cache(&self) -> SentenceCache { self.sentence_cache.clone() }
+
+    // 書き換えの世代を進める。キューから処理する場合はジョブごとに1つ持つ
+    pub fn begin_generation(&self, user_id: &UserId) -> Generation {
+        self.generations.begin(user_id)
+    }
+
+    // 1人のペルソナ分だけ書き換えるが、保存はしない。差分は処理する時点で保存されている日記との間で取る
+    pub async fn mutate_persona(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        persona_id: &DiaryId,
+        new_content: &DiaryContent,
+        generation: &Generation,
+    ) -> Result<PersonaMutation, ApplicationError> {
+        let persona = match self
+            .persona_repository
+            .find_enabled()
+            .await?
+            .into_iter()
+            .find(|persona| persona.id() == persona_id)
+        {
+            Some(persona) => persona,
+            None => {
+                return Err(ApplicationError::NotFound {
+                    entity_type: "Persona",
+                    user_id: user_id.as_str().to_string(),
+                })
+            },
+        };
+
+        let diaries = self.diary_repository.find_all(user_id, entry_date).await?;
+        let diff = find_diary(&diaries, &human_diary_id())
+            .map(|old_diary| SentenceDiff::between(old_diary.content(), new_content));
+        let previous = self
+            .previous_diary(user_id, entry_date, &diaries, persona_id)
             .await?;
+        self.mutate_diary(
+            &persona,
+            align(diff.as_ref(), previous.as_ref()),
+            user_id,
+            entry_date,
+            new_content,
+            generation,
+        )
+        .await
+    }
 
-        Ok(new_content.to_length())
+    // 前回失敗したペルソナの日記は人間の日記より古いままなので、差分の基準にしない
+    async fn previous_diary(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        persona_id: &DiaryId,
+    ) -> Result<Option<Diary>, ApplicationError> {
+        let status = self
+            .diary_repository
+            .find_status(user_id, entry_date, persona_id)
+            .await?;
+        if status.as_ref().is_some_and(DiaryStatus::is_failed) {
+            return Ok(None);
+        }
+        Ok(find_diary(diaries, persona_id))
     }
 
-    pub async fn save_diary(
+    // 人間の日記と書き換えたペルソナの日記、ペルソナの成功の状態を1つのトランザクションで保存する
+    // 打ち切られた場合や、どれかの日記を他のリクエストが先に保存していた場合は何も保存せずに false を返す
+    // 版は経過を追うための記録なのでコミットした後に追記する。追記に失敗すると日記は保存済みのまま版だけが欠ける
+    // ジョブから保存する場合は job_id を渡し、同じトランザクションでジョブの保存待ちの処理を done にする
+    pub async fn save_entry(
         &self,
         user_id: &UserId,
-        diary_id: &DiaryId,
-        diary: &DiaryContent,
-    ) -> Result<(), ApplicationError> {
-        self.user_repository
-            .update_diary(
-                user_id,
-                &Diary::new(diary_id.clone(), diary.clone()).unwrap(),
-            )
+        entry_date: &NaiveDate,
+        new_content: &DiaryContent,
+        mutated: &[(Diary, i32)],
+        generation: &Generation,
+        job_id: Option<&JobId>,
+    ) -> Result<bool, ApplicationError> {
+        let _save_guard = match generation.lock_if_current().await {
+            Some(guard) => guard,
+            None => return Ok(false),
+        };
+        let old_diary = self
+            .diary_repository
+            .find_by_id(user_id, entry_date, &human_diary_id())
             .await?;
+        let human_index = old_diary.as_ref().map_or(0, |old_diary| {
+            SentenceDiff::between(old_diary.content(), new_content).target_index()
+        });
+        let human = Diary::new(human_diary_id(), new_content.clone())
+            .unwrap()
+            .with_version(old_diary.map_or(0, |old_diary| *old_diary.version()));
+        // 人間の日記は最後に並べ、版も最後に追記する
+        let entry: Vec<(Diary, i32)> = mutated
+            .iter()
+            .cloned()
+            .chain([(human, human_index)])
+            .collect();
 
-        Ok(())
+        let diaries: Vec<Diary> = entry.iter().map(|(diary, _)| diary.clone()).collect();
+        let statuses: Vec<DiaryStatus> = mutated
+            .iter()
+            .map(|(diary, _)| DiaryStatus::ok(diary.id().clone(), Utc::now().naive_utc()))
+            .collect();
+        let written = match job_id {
+            Some(job_id) => {
+                self.diary_repository
+                    .update_all_for_job(job_id, user_id, entry_date, &diaries, &statuses)
+                    .await
+            },
+            None => {
+                self.diary_repository
+                    .update_all(user_id, entry_date, &diaries, &statuses)
+                    .await
+            },
+        };
+        match written {
+            Err(DomainError::Conflict(message)) => {
+                info!("discarding stale mutation: {}", message);
+                return Ok(false);
+            },
+            result => result?,
+        }
+        for (diary, target_index) in &entry {
+            self.append_revision(user_id, entry_date, diary, *target_index)
+                .await?;
+        }
+        for (diary, _) in mutated {
+            self.publish(
+                user_id,
+                entry_date,
+                diary.id(),
+                diary.content().to_value().clone(),
+                new_content.to_length(),
+                true,
+            );
+        }
+        Ok(true)
     }
-}
 
-fn get_prompt_by_id(id: i32) -> Option<&'static str> {
-    if id >= 1 && (id as usize) <= PROMPTS.len() {
-        Some(PROMPTS[(id - 1) as usize])
-    } else {
-        None
+    // 現在の日記を上書きした後も経過を追えるよう、版を追記しておく
+    async fn append_revision(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary: &Diary,
+        target_index: i32,
+    ) -> Result<(), ApplicationError> {
+        let revision = DiaryRevision::new(
+            *entry_date,
+            diary.clone(),
+            target_index,
+            Utc::now().naive_utc(),
+        );
+        self.revision_repository.append(user_id, &revision).await?;
+
+        Ok(())
     }
 }
 
-// new_contentがold_contentの部分書き換えである場合には0を返す
-// new_contentがold_contentのさらに後ろに追加されたものである場合にはold_contentの長さを返す
-fn find_target_index(new_content: &DiaryContent, old_content: &DiaryContent) -> i32 {
-    let new_text = new_content.to_value();
-    let old_text = old_content.to_value();
+fn human_diary_id() -> DiaryId { DiaryId::new(0).unwrap() }
 
-    if new_text.starts_with(old_text) {
-        old_content.to_length()
-    } else {
-        0
+// 前回のAIの日記が人間の日記と文単位で対応していれば、変更された文だけを書き換えて差し込む
+// 対応が崩れている場合や初回は全文を書き換える
+fn align<'a>(
+    diff: Option<&'a SentenceDiff>,
+    previous: Option<&'a Diary>,
+) -> Option<(&'a SentenceDiff, &'a Diary)> {
+    match (diff, previous) {
+        (Some(diff), Some(previous)) if diff.is_aligned_with(previous.content()) => {
+            Some((diff, previous))
+        },
+        _ => None,
     }
 }
 
+fn find_diary(diaries: &[Diary], id: &DiaryId) -> Option<Diary> {
+    diaries.iter().find(|diary| diary.id() == id).cloned()
+}
+
 fn process_output(input: String) -> String {
     if let Some(pos) = input.rfind("===") {
         input[(pos + 3)..].trim().to_string()
@@ -197,3 +609,704 @@ fn process_output(input: String) -> String {
         input.trim().to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::infrastructure::api::mock::{MockLlmClient, MockReply};
+    use crate::infrastructure::database::memory::{
+        InMemoryDiaryRepository, InMemoryDiaryRevisionRepository, InMemoryPersonaRepository,
+        InMemoryUserRepository,
+    };
+
+    type TestUsecase = MutateUsecase<
+        InMemoryUserRepository,
+        InMemoryDiaryRepository,
+        InMemoryDiaryRevisionRepository,
+        InMemoryPersonaRepository,
+        MockLlmClient,
+    >;
+
+    fn today() -> NaiveDate { NaiveDate::from_ymd_opt(2024, 7, 11).unwrap() }
+
+    fn personas(count: i32) -> Vec<Persona> {
+        (1..=count)
+            .map(|id| {
+                Persona::new(
+                    DiaryId::new(id).unwrap(),
+                    format!("persona_{}", id),
+                    format!("プロンプト{}", id),
+                    id,
+                )
+            })
+            .collect()
+    }
+
+    async fn setup(client: MockLlmClient) -> (Arc<TestUsecase>, InMemoryDiaryRepository, UserId) {
+        setup_with(
+            client,
+            personas(4),
+            InMemoryDiaryRevisionRepository::new(),
+            DiaryBroadcaster::new(),
+        )
+        .await
+    }
+
+    async fn setup_with(
+        client: MockLlmClient,
+        personas: Vec<Persona>,
+        revision_repository: InMemoryDiaryRevisionRepository,
+        broadcaster: DiaryBroadcaster,
+    ) -> (Arc<TestUsecase>, InMemoryDiaryRepository, UserId) {
+        let user_repository = InMemoryUserRepository::new();
+        let diary_repository = InMemoryDiaryRepository::new();
+        let user_id = UserId::new("mutate_test_user".to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        let usecase = Arc::new(MutateUsecase::new(
+            client,
+            "gpt-4-turbo".to_string(),
+            user_repository,
+            diary_repository.clone(),
+            revision_repository,
+            InMemoryPersonaRepository::new(personas),
+            broadcaster,
+        ));
+        (usecase, diary_repository, user_id)
+    }
+
+    async fn diary_text(
+        diary_repository: &InMemoryDiaryRepository,
+        user_id: &UserId,
+        id: i32,
+    ) -> String {
+        diary_repository
+            .find_by_id(user_id, &today(), &DiaryId::new(id).unwrap())
+            .await
+            .unwrap()
+            .unwrap()
+            .content()
+            .to_value()
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_saves_all_personas() {
+        let client = MockLlmClient::new().with_fixed_response("書き換えた．");
+        let (usecase, diary_repository, user_id) = setup(client.clone()).await;
+        let content = DiaryContent::new("今日は晴れた．".to_string()).unwrap();
+
+        let result = usecase
+            .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+            .await
+            .unwrap();
+
+        assert_eq!(*result.mutated_length(), 7);
+        assert!(result
+            .personas()
+            .iter()
+            .all(|(_, outcome)| *outcome == PersonaOutcome::Saved));
+        assert_eq!(client.requests().len(), 4);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 0).await,
+            "今日は晴れた．"
+        );
+        for id in 1..=4 {
+            assert_eq!(
+                diary_text(&diary_repository, &user_id, id).await,
+                "書き換えた．"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_fans_out_over_enabled_personas() {
+        let client = MockLlmClient::new().with_fixed_response("書き換えた．");
+        let (usecase, diary_repository, user_id) = setup_with(
+            client.clone(),
+            personas(3),
+            InMemoryDiaryRevisionRepository::new(),
+            DiaryBroadcaster::new(),
+        )
+        .await;
+        let content = DiaryContent::new("今日は晴れた．".to_string()).unwrap();
+
+        usecase
+            .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+            .await
+            .unwrap();
+
+        let requests = client.requests();
+        assert_eq!(requests.len(), 3);
+        for id in 1..=3 {
+            let prompt = format!("プロンプト{}", id);
+            assert!(requests
+                .iter()
+                .any(|request| request.prompt().starts_with(&prompt)));
+        }
+        // 人間の日記 + 3ペルソナ分
+        assert_eq!(
+            diary_repository
+                .find_all(&user_id, &today())
+                .await
+                .unwrap()
+                .len(),
+            4
+        );
+        assert!(diary_repository
+            .find_by_id(&user_id, &today(), &DiaryId::new(4).unwrap())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_only_sends_appended_text() {
+        let client = MockLlmClient::new().with_fixed_response("続き．");
+        let (usecase, diary_repository, user_id) = setup(client.clone()).await;
+
+        let first = DiaryContent::new("一文目．".to_string()).unwrap();
+        Arc::clone(&usecase)
+            .mutate_text(&user_id, &today(), &first, CacheMode::Use)
+            .await
+            .unwrap();
+        let second = DiaryContent::new("一文目．二文目．".to_string()).unwrap();
+        usecase
+            .mutate_text(&user_id, &today(), &second, CacheMode::Use)
+            .await
+            .unwrap();
+
+        let last_request = client.requests().last().unwrap().clone();
+        assert!(last_request.prompt().ends_with("二文目．"));
+        assert!(!last_request.prompt().contains("一文目"));
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "続き．続き．"
+        );
+    }
+
+    // 入力文の各文末に「(改)」を付けて返す
+    fn marking_client() -> MockLlmClient {
+        MockLlmClient::new().with_responder(|request| {
+            let input = request.prompt().rsplit("\n").next().unwrap();
+            MockReply::Text(input.replace('．', "(改)．"))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_splices_mid_text_edit() {
+        let client = marking_client();
+        let (usecase, diary_repository, user_id) = setup(client.clone()).await;
+        let first = DiaryContent::new("朝起きた．昼は晴れた．夜に寝た．".to_string()).unwrap();
+        Arc::clone(&usecase)
+            .mutate_text(&user_id, &today(), &first, CacheMode::Use)
+            .await
+            .unwrap();
+
+        let edited = DiaryContent::new("朝起きた．昼は雨だった．夜に寝た．".to_string()).unwrap();
+        usecase
+            .mutate_text(&user_id, &today(), &edited, CacheMode::Use)
+            .await
+            .unwrap();
+
+        let last_request = client.requests().last().unwrap().clone();
+        assert!(last_request.prompt().ends_with("\n昼は雨だった．"));
+        // 初回も編集後も、ペルソナごとに1回ずつ
+        assert_eq!(client.requests().len(), 8);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "朝起きた(改)．昼は雨だった(改)．夜に寝た(改)．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_appends_revisions() {
+        let revision_repository = InMemoryDiaryRevisionRepository::new();
+        let (usecase, _, user_id) = setup_with(
+            marking_client(),
+            personas(2),
+            revision_repository.clone(),
+            DiaryBroadcaster::new(),
+        )
+        .await;
+
+        for text in ["一文目．", "一文目．二文目．"] {
+            let content = DiaryContent::new(text.to_string()).unwrap();
+            Arc::clone(&usecase)
+                .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+                .await
+                .unwrap();
+        }
+
+        let revisions = revision_repository.find_by_user(&user_id).await.unwrap();
+        // (2ペルソナ + 人間) x 2回。人間の入力は各回の最後に記録される
+        assert_eq!(revisions.len(), 6);
+        let human: Vec<&DiaryRevision> = revisions
+            .iter()
+            .filter(|revision| revision.diary().id().to_id() == 0)
+            .collect();
+        assert_eq!(human.len(), 2);
+        assert_eq!(human[0].diary().content().to_value(), "一文目．");
+        assert_eq!(*human[0].target_index(), 0);
+        assert_eq!(*human[1].target_index(), 4);
+        assert!(revisions
+            .iter()
+            .any(
+                |revision| revision.diary().content().to_value() == "一文目(改)．二文目(改)．"
+                    && *revision.target_index() == 4
+            ));
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_reuses_cached_sentences() {
+        let client = marking_client();
+        let (usecase, diary_repository, user_id) = setup(client.clone()).await;
+
+        for text in ["一文目．二文目．", "一文目．", "一文目．二文目．"] {
+            let content = DiaryContent::new(text.to_string()).unwrap();
+            Arc::clone(&usecase)
+                .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+                .await
+                .unwrap();
+        }
+
+        // 消して打ち直した二文目は書き換え済みのものを使う
+        assert_eq!(client.requests().len(), 4);
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "一文目(改)．二文目(改)．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_bypasses_cache_on_request() {
+        let client = marking_client();
+        let (usecase, _, user_id) = setup(client.clone()).await;
+
+        for (text, cache_mode) in [
+            ("一文目．二文目．", CacheMode::Use),
+            ("一文目．", CacheMode::Use),
+            ("一文目．二文目．", CacheMode::Bypass),
+        ] {
+            let content = DiaryContent::new(text.to_string()).unwrap();
+            Arc::clone(&usecase)
+                .mutate_text(&user_id, &today(), &content, cache_mode)
+                .await
+                .unwrap();
+        }
+
+        // 打ち直した二文目も書き換え済みのものを使わずに送る
+        let requests = client.requests();
+        assert_eq!(requests.len(), 8);
+        assert!(requests[4..]
+            .iter()
+            .all(|request| *request.cache_mode() == CacheMode::Bypass));
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_sends_unseen_sentences_together() {
+        let client = marking_client();
+        let (usecase, diary_repository, user_id) = setup_with(
+            client.clone(),
+            personas(1),
+            InMemoryDiaryRevisionRepository::new(),
+            DiaryBroadcaster::new(),
+        )
+        .await;
+
+        for text in ["一．二．三．", "四．二．五．"] {
+            let content = DiaryContent::new(text.to_string()).unwrap();
+            Arc::clone(&usecase)
+                .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+                .await
+                .unwrap();
+        }
+
+        // 書き換え済みの二は送らず、四と五を1回で送る
+        let requests = client.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].prompt().ends_with("\n四．五．"));
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "四(改)．二(改)．五(改)．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_replaces_range_when_reply_merges_sentences() {
+        // 2文以上を受け取ると1文にまとめてしまう
+        let client = MockLlmClient::new().with_responder(|request| {
+            let input = request.prompt().rsplit("\n").next().unwrap();
+            match input.matches('．').count() {
+                1 => MockReply::Text(input.replace('．', "(改)．")),
+                _ => MockReply::Text("まとめた．".to_string()),
+            }
+        });
+        let (usecase, diary_repository, user_id) = setup_with(
+            client.clone(),
+            personas(1),
+            InMemoryDiaryRevisionRepository::new(),
+            DiaryBroadcaster::new(),
+        )
+        .await;
+
+        for text in ["一．", "一．二．", "四．二．五．"] {
+            let content = DiaryContent::new(text.to_string()).unwrap();
+            Arc::clone(&usecase)
+                .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+                .await
+                .unwrap();
+        }
+
+        // 四と五だけでは対応が取れないので、二を挟んだ範囲全体を送り直して置き換える
+        let requests = client.requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[3].prompt().ends_with("\n四．二．五．"));
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "まとめた．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_remutates_misaligned_diary() {
+        let client = marking_client();
+        let (usecase, diary_repository, user_id) = setup(client.clone()).await;
+        let human_id = DiaryId::new(0).unwrap();
+        let old = DiaryContent::new("一文目．二文目．".to_string()).unwrap();
+        diary_repository
+            .update_all(
+                &user_id,
+                &today(),
+                &[Diary::new(human_id, old).unwrap()],
+                &[],
+            )
+            .await
+            .unwrap();
+        // AIが2文を1文にまとめてしまった場合
+        let merged = DiaryContent::new("一文目と二文目．".to_string()).unwrap();
+        diary_repository
+            .update_all(
+                &user_id,
+                &today(),
+                &[Diary::new(DiaryId::new(1).unwrap(), merged).unwrap()],
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let edited = DiaryContent::new("一文目．三文目．".to_string()).unwrap();
+        usecase
+            .mutate_text(&user_id, &today(), &edited, CacheMode::Use)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "一文目(改)．三文目(改)．"
+        );
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 2).await,
+            "一文目(改)．三文目(改)．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_with_injected_failure() {
+        let client = MockLlmClient::new()
+            .with_fixed_response("書き換えた．")
+            .with_latency(Duration::from_millis(10));
+        let (usecase, diary_repository, user_id) = setup(client.clone()).await;
+        let first = DiaryContent::new("今日は晴れた．".to_string()).unwrap();
+        Arc::clone(&usecase)
+            .mutate_text(&user_id, &today(), &first, CacheMode::Use)
+            .await
+            .unwrap();
+
+        client.push_reply(MockReply::Unavailable("connection refused".to_string()));
+        client.push_reply(MockReply::Malformed("missing content".to_string()));
+        let second = DiaryContent::new("今日は晴れた．夜は雨だった．".to_string()).unwrap();
+        let result = usecase
+            .mutate_text(&user_id, &today(), &second, CacheMode::Use)
+            .await
+            .unwrap();
+
+        let failed: Vec<&DiaryId> = result
+            .personas()
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, PersonaOutcome::Failed(_)))
+            .map(|(persona_id, _)| persona_id)
+            .collect();
+        assert_eq!(failed.len(), 2);
+        for id in 1..=4 {
+            let persona_id = DiaryId::new(id).unwrap();
+            let status = diary_repository
+                .find_status(&user_id, &today(), &persona_id)
+                .await
+                .unwrap()
+                .unwrap();
+            // 失敗したペルソナの日記は前回のまま残り、理由は status に記録される
+            if failed.contains(&&persona_id) {
+                assert!(status.is_failed());
+                assert!(status.reason().is_some());
+                assert_eq!(
+                    diary_text(&diary_repository, &user_id, id).await,
+                    "書き換えた．"
+                );
+            } else {
+                assert!(!status.is_failed());
+                assert_eq!(
+                    diary_text(&diary_repository, &user_id, id).await,
+                    "書き換えた．書き換えた．"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_keeps_entries_per_date() {
+        let client = marking_client();
+        let (usecase, diary_repository, user_id) = setup(client.clone()).await;
+        let next_day = today().succ_opt().unwrap();
+
+        let first = DiaryContent::new("一日目．".to_string()).unwrap();
+        Arc::clone(&usecase)
+            .mutate_text(&user_id, &today(), &first, CacheMode::Use)
+            .await
+            .unwrap();
+        let second = DiaryContent::new("二日目．".to_string()).unwrap();
+        usecase
+            .mutate_text(&user_id, &next_day, &second, CacheMode::Use)
+            .await
+            .unwrap();
+
+        // 別の日のエントリとは差分を取らず、前の日の日記も上書きしない
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "一日目(改)．"
+        );
+        let entries = diary_repository.find_entries(&user_id).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(*entries[0].date(), next_day);
+        assert_eq!(
+            entries[0]
+                .find(&DiaryId::new(1).unwrap())
+                .unwrap()
+                .content()
+                .to_value(),
+            "二日目(改)．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_publishes_each_persona() {
+        let broadcaster = DiaryBroadcaster::new();
+        let mut updates = broadcaster.subscribe();
+        let (usecase, _, user_id) = setup_with(
+            marking_client(),
+            personas(2),
+            InMemoryDiaryRevisionRepository::new(),
+            broadcaster,
+        )
+        .await;
+        let content = DiaryContent::new("今日は晴れた．".to_string()).unwrap();
+
+        usecase
+            .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+            .await
+            .unwrap();
+
+        let mut published = vec![];
+        while let Some(update) = updates.try_recv() {
+            if *update.is_complete() {
+                published.push(update);
+            }
+        }
+        // 人間の日記は流さず、ペルソナごとに1件ずつ
+        assert_eq!(published.len(), 2);
+        published.sort_by_key(|update| update.diary().id().to_id());
+        assert_eq!(published[0].diary().id().to_id(), 1);
+        assert_eq!(published[0].user_id(), &user_id);
+        assert_eq!(
+            published[1].diary().content().to_value(),
+            "今日は晴れた(改)．"
+        );
+        assert_eq!(*published[1].human_length(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_publishes_progress_before_saving() {
+        let broadcaster = DiaryBroadcaster::new();
+        let mut updates = broadcaster.subscribe();
+        let (usecase, _, user_id) = setup_with(
+            marking_client(),
+            personas(1),
+            InMemoryDiaryRevisionRepository::new(),
+            broadcaster,
+        )
+        .await;
+        let content = DiaryContent::new("一文目．二文目．".to_string()).unwrap();
+
+        usecase
+            .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+            .await
+            .unwrap();
+
+        let mut published = vec![];
+        while let Some(update) = updates.try_recv() {
+            published.push((
+                update.diary().content().to_value().clone(),
+                *update.is_complete(),
+            ));
+        }
+        assert_eq!(
+            published,
+            vec![
+                ("一文目(改)．二文目(改)．".to_string(), false),
+                ("一文目(改)．二文目(改)．".to_string(), true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_drops_superseded_request() {
+        let client = marking_client().with_latency(Duration::from_millis(50));
+        let revision_repository = InMemoryDiaryRevisionRepository::new();
+        let (usecase, diary_repository, user_id) = setup_with(
+            client.clone(),
+            personas(2),
+            revision_repository.clone(),
+            DiaryBroadcaster::new(),
+        )
+        .await;
+
+        let first = {
+            let usecase = Arc::clone(&usecase);
+            let user_id = user_id.clone();
+            tokio::spawn(async move {
+                let content = DiaryContent::new("一文目．".to_string()).unwrap();
+                usecase
+                    .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let second = DiaryContent::new("二文目．".to_string()).unwrap();
+        usecase
+            .mutate_text(&user_id, &today(), &second, CacheMode::Use)
+            .await
+            .unwrap();
+        first.await.unwrap().unwrap();
+
+        assert_eq!(diary_text(&diary_repository, &user_id, 0).await, "二文目．");
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "二文目(改)．"
+        );
+        // 古いリクエストの結果は版にも残らない
+        let revisions = revision_repository.find_by_user(&user_id).await.unwrap();
+        assert_eq!(revisions.len(), 3);
+        assert!(revisions.iter().all(|revision| !revision
+            .diary()
+            .content()
+            .to_value()
+            .contains("一文目")));
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_discards_output_saved_over_elsewhere() {
+        let client = marking_client().with_latency(Duration::from_millis(50));
+        let (usecase, diary_repository, user_id) = setup_with(
+            client,
+            personas(1),
+            InMemoryDiaryRevisionRepository::new(),
+            DiaryBroadcaster::new(),
+        )
+        .await;
+
+        let mutation = {
+            let usecase = Arc::clone(&usecase);
+            let user_id = user_id.clone();
+            tokio::spawn(async move {
+                let content = DiaryContent::new("一文目．".to_string()).unwrap();
+                usecase
+                    .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+                    .await
+            })
+        };
+        // LLM の応答を待っている間に、別のプロセスが同じ日記を保存した
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let newer = Diary::new(
+            DiaryId::new(1).unwrap(),
+            DiaryContent::new("新しい日記．".to_string()).unwrap(),
+        )
+        .unwrap();
+        diary_repository
+            .update_all(&user_id, &today(), &[newer], &[])
+            .await
+            .unwrap();
+
+        let result = mutation.await.unwrap().unwrap();
+
+        assert_eq!(
+            result.personas(),
+            &vec![(DiaryId::new(1).unwrap(), PersonaOutcome::Superseded)]
+        );
+        assert_eq!(
+            diary_text(&diary_repository, &user_id, 1).await,
+            "新しい日記．"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mutate_text_saves_entry_all_or_nothing() {
+        let client = marking_client().with_latency(Duration::from_millis(50));
+        let revision_repository = InMemoryDiaryRevisionRepository::new();
+        let (usecase, diary_repository, user_id) = setup_with(
+            client,
+            personas(2),
+            revision_repository.clone(),
+            DiaryBroadcaster::new(),
+        )
+        .await;
+
+        let mutation = {
+            let usecase = Arc::clone(&usecase);
+            let user_id = user_id.clone();
+            tokio::spawn(async move {
+                let content = DiaryContent::new("一文目．".to_string()).unwrap();
+                usecase
+                    .mutate_text(&user_id, &today(), &content, CacheMode::Use)
+                    .await
+            })
+        };
+        // ペルソナ2の日記だけが別のプロセスに先に保存された
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let newer = Diary::new(
+            DiaryId::new(2).unwrap(),
+            DiaryContent::new("新しい日記．".to_string()).unwrap(),
+        )
+        .unwrap();
+        diary_repository
+            .update_all(&user_id, &today(), std::slice::from_ref(&newer), &[])
+            .await
+            .unwrap();
+
+        let result = mutation.await.unwrap().unwrap();
+
+        // 人間の日記とAIの日記が食い違わないよう、どれも保存しない
+        assert!(result
+            .personas()
+            .iter()
+            .all(|(_, outcome)| *outcome == PersonaOutcome::Superseded));
+        let diaries = diary_repository.find_all(&user_id, &today()).await.unwrap();
+        assert_eq!(diaries, vec![newer.with_version(1)]);
+        assert!(revision_repository
+            .find_by_user(&user_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
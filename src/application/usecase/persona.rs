@@ -0,0 +1,16 @@
+use crate::application::error::ApplicationError;
+use crate::domain::entity::persona::Persona;
+use crate::domain::repository::persona::PersonaRepository;
+
+#[derive(Clone)]
+pub struct GetPersonasUseCase<P: PersonaRepository> {
+    persona_repository: P,
+}
+
+impl<P: PersonaRepository> GetPersonasUseCase<P> {
+    pub fn new(persona_repository: P) -> Self { Self { persona_repository } }
+
+    pub async fn get_enabled_personas(&self) -> Result<Vec<Persona>, ApplicationError> {
+        Ok(self.persona_repository.find_enabled().await?)
+    }
+}
@@ -17,8 +17,18 @@ impl<R: UserRepository> UpdateResultUseCase<R> {
         is_public: bool,
         favorite_id: &DiaryId,
     ) -> Result<(), ApplicationError> {
+        let user = match self.user_repository.find_by_id(user_id).await? {
+            Some(user) => user,
+            None => {
+                return Err(ApplicationError::NotFound {
+                    entity_type: "User",
+                    user_id: user_id.as_str().to_string(),
+                })
+            },
+        };
+        // 読んでから保存するまでの間に他のリクエストが保存していれば Conflict を返す
         self.user_repository
-            .update_result(user_id, is_public, favorite_id)
+            .update_result(user_id, is_public, favorite_id, *user.version())
             .await?;
 
         Ok(())
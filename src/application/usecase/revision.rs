@@ -0,0 +1,24 @@
+use crate::application::error::ApplicationError;
+use crate::domain::entity::revision::DiaryRevision;
+use crate::domain::entity::user::UserId;
+use crate::domain::repository::revision::DiaryRevisionRepository;
+
+#[derive(Clone)]
+pub struct GetRevisionsUseCase<V: DiaryRevisionRepository> {
+    revision_repository: V,
+}
+
+impl<V: DiaryRevisionRepository> GetRevisionsUseCase<V> {
+    pub fn new(revision_repository: V) -> Self {
+        Self {
+            revision_repository,
+        }
+    }
+
+    pub async fn get_revisions(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<DiaryRevision>, ApplicationError> {
+        Ok(self.revision_repository.find_by_user(user_id).await?)
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::application::error::ApplicationError;
+use crate::domain::entity::token::{RefreshToken, RefreshTokenHash};
+use crate::domain::entity::user::UserId;
+use crate::domain::repository::token::RefreshTokenRepository;
+
+// 展示中に長く書き続けても切れないよう、アクセストークンより長めに取る
+const REFRESH_TOKEN_LIFETIME_HOURS: i64 = 12;
+
+#[derive(Clone)]
+pub struct TokenUsecase<T: RefreshTokenRepository> {
+    token_repository: T,
+}
+
+impl<T: RefreshTokenRepository> TokenUsecase<T> {
+    pub fn new(token_repository: T) -> Self { Self { token_repository } }
+
+    // 新しいリフレッシュトークンを発行し、平文を返す (保存するのはハッシュのみ)
+    pub async fn issue_refresh_token(&self, user_id: &UserId) -> Result<String, ApplicationError> {
+        let plain = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let expires_at = (Utc::now() + Duration::hours(REFRESH_TOKEN_LIFETIME_HOURS)).naive_utc();
+        let token = RefreshToken::new(
+            RefreshTokenHash::from_plain(&plain),
+            user_id.clone(),
+            expires_at,
+            None,
+        );
+        self.token_repository.create(&token).await?;
+
+        Ok(plain)
+    }
+
+    // 使われたリフレッシュトークンを失効させ、同じユーザーに新しいものを発行する
+    // 失効済みのトークンが再利用された場合は漏洩とみなし、そのユーザーのトークンをすべて失効させる
+    pub async fn rotate_refresh_token(
+        &self,
+        plain: &str,
+    ) -> Result<(UserId, String), ApplicationError> {
+        let hash = RefreshTokenHash::from_plain(plain);
+        let token = match self.token_repository.find_by_hash(&hash).await? {
+            Some(token) => token,
+            None => {
+                return Err(ApplicationError::Unauthorized(
+                    "refresh token is invalid".to_string(),
+                ))
+            },
+        };
+
+        if token.is_revoked() {
+            self.token_repository.revoke_all(token.user_id()).await?;
+            return Err(ApplicationError::Unauthorized(
+                "refresh token has been revoked".to_string(),
+            ));
+        }
+        if token.is_expired(Utc::now().naive_utc()) {
+            return Err(ApplicationError::Unauthorized(
+                "refresh token has expired".to_string(),
+            ));
+        }
+
+        // 読んでから失効させるまでの間に、同じトークンで別のリクエストが先にローテーションした
+        if !self.token_repository.revoke(&hash).await? {
+            self.token_repository.revoke_all(token.user_id()).await?;
+            return Err(ApplicationError::Unauthorized(
+                "refresh token has been revoked".to_string(),
+            ));
+        }
+        let new_token = self.issue_refresh_token(token.user_id()).await?;
+
+        Ok((token.user_id().clone(), new_token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::error::DomainError;
+    use crate::domain::repository::token::RefreshTokenRepository;
+    use crate::infrastructure::database::memory::InMemoryRefreshTokenRepository;
+
+    fn setup() -> (
+        TokenUsecase<InMemoryRefreshTokenRepository>,
+        InMemoryRefreshTokenRepository,
+        UserId,
+    ) {
+        let token_repository = InMemoryRefreshTokenRepository::new();
+        let usecase = TokenUsecase::new(token_repository.clone());
+        let user_id = UserId::new("token_test_user".to_string()).unwrap();
+        (usecase, token_repository, user_id)
+    }
+
+    #[tokio::test]
+    async fn test_rotate_refresh_token() {
+        let (usecase, _, user_id) = setup();
+        let first = usecase.issue_refresh_token(&user_id).await.unwrap();
+
+        let (rotated_user_id, second) = usecase.rotate_refresh_token(&first).await.unwrap();
+
+        assert_eq!(rotated_user_id, user_id);
+        assert_ne!(first, second);
+        assert!(usecase.rotate_refresh_token(&second).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reused_refresh_token_revokes_family() {
+        let (usecase, _, user_id) = setup();
+        let first = usecase.issue_refresh_token(&user_id).await.unwrap();
+        let (_, second) = usecase.rotate_refresh_token(&first).await.unwrap();
+
+        let reused = usecase.rotate_refresh_token(&first).await;
+
+        assert!(matches!(reused, Err(ApplicationError::Unauthorized(_))));
+        assert!(matches!(
+            usecase.rotate_refresh_token(&second).await,
+            Err(ApplicationError::Unauthorized(_))
+        ));
+    }
+
+    // 失効する前の状態を返し続ける。同じトークンで同時にローテーションした場合の読み取りを再現する
+    #[derive(Clone)]
+    struct StaleReadRepository(InMemoryRefreshTokenRepository);
+
+    #[async_trait::async_trait]
+    impl RefreshTokenRepository for StaleReadRepository {
+        async fn create(&self, token: &RefreshToken) -> Result<(), DomainError> {
+            self.0.create(token).await
+        }
+
+        async fn find_by_hash(
+            &self,
+            hash: &RefreshTokenHash,
+        ) -> Result<Option<RefreshToken>, DomainError> {
+            Ok(self.0.find_by_hash(hash).await?.map(|token| {
+                RefreshToken::new(
+                    token.hash().clone(),
+                    token.user_id().clone(),
+                    *token.expires_at(),
+                    None,
+                )
+            }))
+        }
+
+        async fn revoke(&self, hash: &RefreshTokenHash) -> Result<bool, DomainError> {
+            self.0.revoke(hash).await
+        }
+
+        async fn revoke_all(&self, user_id: &UserId) -> Result<(), DomainError> {
+            self.0.revoke_all(user_id).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_rotation_is_single_use() {
+        let token_repository = InMemoryRefreshTokenRepository::new();
+        let usecase = TokenUsecase::new(StaleReadRepository(token_repository.clone()));
+        let user_id = UserId::new("token_test_user".to_string()).unwrap();
+        let first = usecase.issue_refresh_token(&user_id).await.unwrap();
+
+        let (_, second) = usecase.rotate_refresh_token(&first).await.unwrap();
+        // 失効前に読んだ2つ目のリクエストは、失効させられずに拒否される
+        let raced = usecase.rotate_refresh_token(&first).await;
+
+        assert!(matches!(raced, Err(ApplicationError::Unauthorized(_))));
+        let second = token_repository
+            .find_by_hash(&RefreshTokenHash::from_plain(&second))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(second.is_revoked());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_or_revoked_refresh_token() {
+        let (usecase, token_repository, user_id) = setup();
+        let token = usecase.issue_refresh_token(&user_id).await.unwrap();
+
+        assert!(matches!(
+            usecase.rotate_refresh_token("unknown").await,
+            Err(ApplicationError::Unauthorized(_))
+        ));
+
+        token_repository.revoke_all(&user_id).await.unwrap();
+
+        assert!(matches!(
+            usecase.rotate_refresh_token(&token).await,
+            Err(ApplicationError::Unauthorized(_))
+        ));
+    }
+}
@@ -1 +1,7 @@
+pub mod admin;
+pub mod error;
+pub mod extractor;
+pub mod guard;
 pub mod jwt;
+pub mod keys;
+pub mod revocation;
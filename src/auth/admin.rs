@@ -0,0 +1,62 @@
+use std::env;
+
+use sha2::{Digest, Sha256};
+
+// スタッフ用トークンを発行するための事前共有クレデンシャル
+// ADMIN_CREDENTIAL が未設定の場合は発行できない
+#[derive(Clone)]
+pub struct AdminCredential {
+    digest: Option<[u8; 32]>,
+}
+
+impl AdminCredential {
+    pub fn new(credential: Option<&str>) -> Self {
+        Self {
+            digest: credential
+                .filter(|credential| !credential.is_empty())
+                .map(digest),
+        }
+    }
+
+    pub fn from_env() -> Self { Self::new(env::var("ADMIN_CREDENTIAL").ok().as_deref()) }
+
+    pub fn is_enabled(&self) -> bool { self.digest.is_some() }
+
+    // 長さや一致位置で時間差が出ないよう、ハッシュ同士を全バイト比較する
+    pub fn verify(&self, credential: &str) -> bool {
+        match self.digest {
+            Some(expected) => {
+                let actual = digest(credential);
+                expected
+                    .iter()
+                    .zip(actual.iter())
+                    .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+            },
+            None => false,
+        }
+    }
+}
+
+fn digest(credential: &str) -> [u8; 32] { Sha256::digest(credential.as_bytes()).into() }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_credential() {
+        let credential = AdminCredential::new(Some("staff-only"));
+
+        assert!(credential.is_enabled());
+        assert!(credential.verify("staff-only"));
+        assert!(!credential.verify("staff-onl"));
+        assert!(!credential.verify(""));
+    }
+
+    #[test]
+    fn test_disabled_without_credential() {
+        assert!(!AdminCredential::new(None).verify(""));
+        assert!(!AdminCredential::new(Some("")).is_enabled());
+    }
+}
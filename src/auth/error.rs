@@ -0,0 +1,73 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use jsonwebtoken::errors::ErrorKind;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Authorization header is missing.")]
+    MissingHeader,
+    #[error("Authorization header must use the Bearer scheme.")]
+    InvalidScheme,
+    #[error("Token has expired.")]
+    Expired,
+    #[error("Token signature is invalid.")]
+    InvalidSignature,
+    #[error("Token is malformed.")]
+    Malformed,
+    #[error("Token has been revoked.")]
+    Revoked,
+    #[error("This operation requires the {0} role.")]
+    Forbidden(&'static str),
+    #[error("Authentication is not configured.")]
+    NotConfigured,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AuthErrorResponse {
+    pub error: String,
+    pub message: String,
+}
+
+impl AuthError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingHeader => "missing_authorization",
+            AuthError::InvalidScheme => "invalid_scheme",
+            AuthError::Expired => "token_expired",
+            AuthError::InvalidSignature => "invalid_signature",
+            AuthError::Malformed => "malformed_token",
+            AuthError::Revoked => "token_revoked",
+            AuthError::Forbidden(_) => "forbidden",
+            AuthError::NotConfigured => "auth_not_configured",
+        }
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AuthError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        match err.kind() {
+            ErrorKind::ExpiredSignature => AuthError::Expired,
+            ErrorKind::InvalidSignature => AuthError::InvalidSignature,
+            _ => AuthError::Malformed,
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AuthError::NotConfigured => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(AuthErrorResponse {
+            error: self.code().to_string(),
+            message: self.to_string(),
+        })
+    }
+}
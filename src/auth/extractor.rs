@@ -0,0 +1,182 @@
+use actix_web::dev::Payload;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, FromRequest, HttpRequest};
+use futures_util::future::{ready, Ready};
+
+use crate::auth::error::AuthError;
+use crate::auth::jwt::{Claims, Role};
+use crate::auth::keys::JwtKeys;
+use crate::auth::revocation::RevocationStore;
+use crate::domain::entity::user::UserId;
+
+// Authorization: Bearer <token> を検証し、トークンの sub をユーザーIDとして取り出す
+// 来場者のトークンだけを受け付ける。管理者のトークンの sub はユーザーではないので Forbidden にする
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub user_id: UserId,
+    pub claims: Claims,
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authenticate(req).and_then(|user| match user.claims.role {
+            Role::Visitor => Ok(user),
+            _ => Err(AuthError::Forbidden(Role::Visitor.as_str())),
+        }))
+    }
+}
+
+// 役割を問わずにトークンを検証する。役割は呼び出し側で確かめる
+pub fn authenticate(req: &HttpRequest) -> Result<AuthenticatedUser, AuthError> {
+    let keys = req
+        .app_data::<web::Data<JwtKeys>>()
+        .ok_or(AuthError::NotConfigured)?;
+    let revocations = req
+        .app_data::<web::Data<RevocationStore>>()
+        .ok_or(AuthError::NotConfigured)?;
+
+    let auth_header = req
+        .headers()
+        .get(AUTHORIZATION)
+        .ok_or(AuthError::MissingHeader)?
+        .to_str()
+        .map_err(|_| AuthError::InvalidScheme)?;
+    let token = match auth_header.split_once(' ') {
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case("Bearer") => token.trim(),
+        _ => return Err(AuthError::InvalidScheme),
+    };
+
+    let claims = keys.decode::<Claims>(token)?;
+    if revocations.is_revoked(&claims) {
+        return Err(AuthError::Revoked);
+    }
+    let user_id = UserId::new(claims.sub.clone()).map_err(|_| AuthError::Malformed)?;
+    Ok(AuthenticatedUser { user_id, claims })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, ResponseError};
+    use chrono::Duration;
+
+    use super::*;
+    use crate::auth::error::AuthErrorResponse;
+
+    fn test_keys() -> JwtKeys {
+        JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap()
+    }
+
+    fn token(keys: &JwtKeys, lifetime: Duration) -> String {
+        let user_id = UserId::new("extractor_test_user".to_string()).unwrap();
+        keys.encode(&Claims::new(&user_id, lifetime)).unwrap()
+    }
+
+    async fn extract(header: Option<String>) -> Result<AuthenticatedUser, AuthError> {
+        extract_with(header, RevocationStore::new()).await
+    }
+
+    async fn extract_with(
+        header: Option<String>,
+        revocations: RevocationStore,
+    ) -> Result<AuthenticatedUser, AuthError> {
+        let mut request = test::TestRequest::default()
+            .app_data(web::Data::new(test_keys()))
+            .app_data(web::Data::new(revocations));
+        if let Some(header) = header {
+            request = request.insert_header((AUTHORIZATION, header));
+        }
+        let (req, mut payload) = request.to_http_parts();
+        AuthenticatedUser::from_request(&req, &mut payload).await
+    }
+
+    #[actix_rt::test]
+    async fn test_valid_token() {
+        let token = token(&test_keys(), Duration::hours(1));
+
+        let user = extract(Some(format!("Bearer {}", token))).await.unwrap();
+
+        assert_eq!(user.user_id.as_str(), "extractor_test_user");
+    }
+
+    #[actix_rt::test]
+    async fn test_revoked_token() {
+        let keys = test_keys();
+        let token = token(&keys, Duration::hours(1));
+        let claims = keys.decode::<Claims>(&token).unwrap();
+        let revocations = RevocationStore::new();
+        revocations.revoke_token(&claims);
+
+        let result = extract_with(Some(format!("Bearer {}", token)), revocations).await;
+
+        assert!(matches!(result, Err(AuthError::Revoked)));
+    }
+
+    #[actix_rt::test]
+    async fn test_admin_token_is_not_a_visitor() {
+        let operator_id = UserId::new("admin".to_string()).unwrap();
+        let token = test_keys()
+            .encode(&Claims::new(&operator_id, Duration::hours(1)).with_role(Role::Admin))
+            .unwrap();
+
+        let result = extract(Some(format!("Bearer {}", token))).await;
+
+        assert!(matches!(result, Err(AuthError::Forbidden("visitor"))));
+    }
+
+    #[actix_rt::test]
+    async fn test_missing_header() {
+        assert!(matches!(extract(None).await, Err(AuthError::MissingHeader)));
+    }
+
+    #[actix_rt::test]
+    async fn test_wrong_scheme() {
+        let token = token(&test_keys(), Duration::hours(1));
+
+        let result = extract(Some(format!("Basic {}", token))).await;
+
+        assert!(matches!(result, Err(AuthError::InvalidScheme)));
+    }
+
+    #[actix_rt::test]
+    async fn test_expired_token() {
+        let token = token(&test_keys(), Duration::hours(-2));
+
+        let result = extract(Some(format!("Bearer {}", token))).await;
+
+        assert!(matches!(result, Err(AuthError::Expired)));
+    }
+
+    #[actix_rt::test]
+    async fn test_bad_signature() {
+        let other_keys =
+            JwtKeys::new(vec![("test".to_string(), b"other_secret".to_vec())], "test").unwrap();
+        let token = token(&other_keys, Duration::hours(1));
+
+        let result = extract(Some(format!("Bearer {}", token))).await;
+
+        assert!(matches!(result, Err(AuthError::InvalidSignature)));
+    }
+
+    #[actix_rt::test]
+    async fn test_malformed_token() {
+        let result = extract(Some("Bearer not-a-jwt".to_string())).await;
+
+        assert!(matches!(result, Err(AuthError::Malformed)));
+    }
+
+    #[actix_rt::test]
+    async fn test_error_response_body() {
+        let response = AuthError::Expired.error_response();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body: AuthErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.error, "token_expired");
+    }
+}
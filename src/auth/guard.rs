@@ -0,0 +1,148 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_service::{Service, Transform};
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, ServiceRequest, ServiceResponse};
+use actix_web::{Error, ResponseError};
+use futures_util::future::{ok, Ready};
+
+use crate::auth::error::AuthError;
+use crate::auth::extractor::authenticate;
+use crate::auth::jwt::Role;
+
+// scope 全体を特定の role を持つトークンに限定する
+// トークンが無効なら 401、role が足りなければ 403 を返す
+pub struct RequireRole {
+    role: Role,
+}
+
+impl RequireRole {
+    pub fn admin() -> Self { Self { role: Role::Admin } }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequireRoleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequireRoleMiddleware {
+            service: Rc::new(service),
+            role: self.role,
+        })
+    }
+}
+
+pub struct RequireRoleMiddleware<S> {
+    service: Rc<S>,
+    role: Role,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let authorized = authenticate(req.request()).and_then(|user| {
+            if user.claims.role == self.role {
+                Ok(())
+            } else {
+                Err(AuthError::Forbidden(self.role.as_str()))
+            }
+        });
+
+        match authorized {
+            Ok(()) => {
+                let fut = self.service.call(req);
+                Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+            },
+            Err(err) => {
+                let response = req
+                    .into_response(err.error_response())
+                    .map_into_right_body();
+                Box::pin(async move { Ok(response) })
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpResponse};
+    use chrono::Duration;
+
+    use super::*;
+    use crate::auth::jwt::Claims;
+    use crate::auth::keys::JwtKeys;
+    use crate::auth::revocation::RevocationStore;
+    use crate::domain::entity::user::UserId;
+
+    fn test_keys() -> JwtKeys {
+        JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap()
+    }
+
+    fn token(role: Role) -> String {
+        let user_id = UserId::new("guard_test_user".to_string()).unwrap();
+        test_keys()
+            .encode(&Claims::new(&user_id, Duration::hours(1)).with_role(role))
+            .unwrap()
+    }
+
+    async fn call(token: Option<String>) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_keys()))
+                .app_data(web::Data::new(RevocationStore::new()))
+                .service(
+                    web::scope("/admin")
+                        .wrap(RequireRole::admin())
+                        .route("/ping", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        let mut request = test::TestRequest::get().uri("/admin/ping");
+        if let Some(token) = token {
+            request = request.insert_header(("Authorization", format!("Bearer {}", token)));
+        }
+        test::call_service(&app, request.to_request())
+            .await
+            .status()
+    }
+
+    #[actix_rt::test]
+    async fn test_admin_token_is_allowed() {
+        assert_eq!(call(Some(token(Role::Admin))).await, StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_visitor_token_is_forbidden() {
+        assert_eq!(
+            call(Some(token(Role::Visitor))).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_missing_token_is_unauthorized() {
+        assert_eq!(call(None).await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,36 +1,73 @@
-use actix_web::HttpRequest;
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+use crate::auth::keys::JwtKeys;
 use crate::domain::entity::user::UserId;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    exp: i32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Visitor,
+    Admin,
 }
 
-pub fn get_user_id_from_jwt(token: &str, secret: &[u8]) -> Result<String, anyhow::Error> {
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret),
-        &Validation::new(Algorithm::HS256),
-    )?;
-    Ok(token_data.claims.sub)
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Visitor => "visitor",
+            Role::Admin => "admin",
+        }
+    }
 }
 
-pub fn get_user_id_from_req(req: HttpRequest) -> Result<UserId, anyhow::Error> {
-    let auth_header = req
-        .headers()
-        .get("Authorization")
-        .unwrap()
-        .to_str()
-        .unwrap();
-    let token = auth_header.trim_start_matches("Bearer ");
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: usize,
+    pub iat: usize,
+    // トークンごとの識別子。失効の管理に使う
+    pub jti: String,
+    // role を持たないトークンは来場者として扱う
+    #[serde(default)]
+    pub role: Role,
+}
+
+impl Claims {
+    pub fn new(user_id: &UserId, lifetime: Duration) -> Self {
+        let now = Utc::now();
+        let expiration = now
+            .checked_add_signed(lifetime)
+            .expect("valid timestamp")
+            .timestamp() as usize;
 
-    println!("{}", token);
+        Self {
+            sub: user_id.as_str().to_string(),
+            exp: expiration,
+            iat: now.timestamp() as usize,
+            jti: Uuid::new_v4().to_string(),
+            role: Role::Visitor,
+        }
+    }
+
+    pub fn with_role(self, role: Role) -> Self { Self { role, ..self } }
+}
+
+pub const ACCESS_TOKEN_LIFETIME_HOURS: i64 = 1;
+// スタッフが展示1日分使えるよう長めに取る
+const ADMIN_TOKEN_LIFETIME_HOURS: i64 = 12;
+
+pub fn issue_access_token(keys: &JwtKeys, user_id: &UserId) -> Result<String, anyhow::Error> {
+    keys.encode(&Claims::new(
+        user_id,
+        Duration::hours(ACCESS_TOKEN_LIFETIME_HOURS),
+    ))
+}
 
-    // JWTトークンからユーザーIDを抽出
-    let user_id = get_user_id_from_jwt(token, b"your_secret_key").unwrap();
-    Ok(UserId::new(user_id).unwrap())
+pub fn issue_admin_token(keys: &JwtKeys, operator_id: &UserId) -> Result<String, anyhow::Error> {
+    keys.encode(
+        &Claims::new(operator_id, Duration::hours(ADMIN_TOKEN_LIFETIME_HOURS))
+            .with_role(Role::Admin),
+    )
 }
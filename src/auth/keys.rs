@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::{env, fs};
+
+use anyhow::{anyhow, bail};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::auth::error::AuthError;
+
+// JWTの署名鍵の集合。kid ごとに鍵を保持し、署名には active_kid の鍵を使う
+// 検証は token header の kid で鍵を選ぶため、active_kid を切り替えても発行済みのトークンは失効しない
+#[derive(Clone)]
+pub struct JwtKeys {
+    keys: Arc<HashMap<String, Vec<u8>>>,
+    active_kid: String,
+}
+
+impl JwtKeys {
+    pub fn new(keys: Vec<(String, Vec<u8>)>, active_kid: &str) -> Result<Self, anyhow::Error> {
+        if keys.is_empty() {
+            bail!("no JWT signing key is configured");
+        }
+        let keys: HashMap<String, Vec<u8>> = keys.into_iter().collect();
+        if !keys.contains_key(active_kid) {
+            bail!(r#"active JWT key "{}" is not configured"#, active_kid);
+        }
+        Ok(Self {
+            keys: Arc::new(keys),
+            active_kid: active_kid.to_string(),
+        })
+    }
+
+    // JWT_KEYS ("kid:secret,kid:secret") または JWT_KEYS_FILE (1行に1つ "kid:secret") から読み込む
+    // JWT_ACTIVE_KID が未指定の場合は最初の鍵で署名する
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        let source = match (env::var("JWT_KEYS"), env::var("JWT_KEYS_FILE")) {
+            (Ok(keys), _) => keys,
+            (Err(_), Ok(path)) => fs::read_to_string(&path)
+                .map_err(|err| anyhow!("failed to read JWT_KEYS_FILE {}: {}", path, err))?,
+            _ => bail!("JWT_KEYS or JWT_KEYS_FILE must be set"),
+        };
+
+        let keys = parse_keys(&source)?;
+        let active_kid = match env::var("JWT_ACTIVE_KID") {
+            Ok(kid) => kid,
+            Err(_) => keys
+                .first()
+                .map(|(kid, _)| kid.clone())
+                .ok_or_else(|| anyhow!("no JWT signing key is configured"))?,
+        };
+        Self::new(keys, &active_kid)
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, anyhow::Error> {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(self.active_kid.clone());
+        let secret = &self.keys[&self.active_kid];
+        Ok(encode(&header, claims, &EncodingKey::from_secret(secret))?)
+    }
+
+    // 未知の kid は検証できないため署名不正として扱う
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<T, AuthError> {
+        let header = decode_header(token)?;
+        let kid = header.kid.ok_or(AuthError::Malformed)?;
+        let secret = self.keys.get(&kid).ok_or(AuthError::InvalidSignature)?;
+        let token_data = decode::<T>(
+            token,
+            &DecodingKey::from_secret(secret),
+            &Validation::new(Algorithm::HS256),
+        )?;
+        Ok(token_data.claims)
+    }
+}
+
+fn parse_keys(source: &str) -> Result<Vec<(String, Vec<u8>)>, anyhow::Error> {
+    source
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((kid, secret)) if !kid.trim().is_empty() && !secret.trim().is_empty() => {
+                Ok((kid.trim().to_string(), secret.trim().as_bytes().to_vec()))
+            },
+            _ => Err(anyhow!(
+                r#"invalid JWT key entry "{}", expected "kid:secret""#,
+                entry
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestClaims {
+        sub: String,
+        exp: usize,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims {
+            sub: "user".to_string(),
+            exp: (Utc::now() + Duration::hours(1)).timestamp() as usize,
+        }
+    }
+
+    #[test]
+    fn test_encode_and_decode() {
+        let keys = JwtKeys::new(vec![("day1".to_string(), b"secret1".to_vec())], "day1").unwrap();
+
+        let token = keys.encode(&claims()).unwrap();
+
+        assert_eq!(decode_header(&token).unwrap().kid.as_deref(), Some("day1"));
+        assert_eq!(keys.decode::<TestClaims>(&token).unwrap(), claims());
+    }
+
+    #[test]
+    fn test_rotated_key_still_verifies_old_tokens() {
+        let day1 = JwtKeys::new(vec![("day1".to_string(), b"secret1".to_vec())], "day1").unwrap();
+        let token = day1.encode(&claims()).unwrap();
+
+        let rotated = JwtKeys::new(
+            vec![
+                ("day1".to_string(), b"secret1".to_vec()),
+                ("day2".to_string(), b"secret2".to_vec()),
+            ],
+            "day2",
+        )
+        .unwrap();
+
+        assert!(rotated.decode::<TestClaims>(&token).is_ok());
+        let new_token = rotated.encode(&claims()).unwrap();
+        assert_eq!(
+            decode_header(&new_token).unwrap().kid.as_deref(),
+            Some("day2")
+        );
+        assert!(day1.decode::<TestClaims>(&new_token).is_err());
+    }
+
+    #[test]
+    fn test_retired_key_is_rejected() {
+        let day1 = JwtKeys::new(vec![("day1".to_string(), b"secret1".to_vec())], "day1").unwrap();
+        let token = day1.encode(&claims()).unwrap();
+
+        let day2 = JwtKeys::new(vec![("day2".to_string(), b"secret2".to_vec())], "day2").unwrap();
+
+        assert!(matches!(
+            day2.decode::<TestClaims>(&token),
+            Err(AuthError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_missing_keys_fail() {
+        assert!(JwtKeys::new(vec![], "day1").is_err());
+        assert!(JwtKeys::new(vec![("day1".to_string(), b"secret1".to_vec())], "day2").is_err());
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let keys = parse_keys("day1:secret1, day2:secret2\nday3:secret3\n").unwrap();
+        let kids: Vec<&str> = keys.iter().map(|(kid, _)| kid.as_str()).collect();
+
+        assert_eq!(kids, vec!["day1", "day2", "day3"]);
+        assert_eq!(keys[1].1, b"secret2".to_vec());
+        assert!(parse_keys("day1").is_err());
+        assert!(parse_keys("day1:").is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::auth::jwt::{Claims, ACCESS_TOKEN_LIFETIME_HOURS};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+use crate::domain::repository::revocation::AccessRevocationRepository;
+
+#[derive(Default)]
+struct RevocationState {
+    // jti -> exp
+    tokens: HashMap<String, usize>,
+    // sub -> 失効させた時刻。これ以前に発行されたトークンはすべて無効
+    users: HashMap<String, usize>,
+}
+
+// 失効させたアクセストークンの一覧。プロセス内で共有し、有効期限を過ぎたものは順次捨てる
+// ユーザー単位の失効は削除したユーザーのトークンを止めるためのものなので、store があれば保存して再起動後も読み込む
+// トークン単位の失効 (ログアウト) は再起動すると消えるが、アクセストークン自体の寿命が短いため許容している
+#[derive(Clone, Default)]
+pub struct RevocationStore {
+    state: Arc<Mutex<RevocationState>>,
+    store: Option<Arc<dyn AccessRevocationRepository>>,
+}
+
+impl RevocationStore {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_store(self, store: Arc<dyn AccessRevocationRepository>) -> Self {
+        Self {
+            store: Some(store),
+            ..self
+        }
+    }
+
+    // 起動時に、まだ有効なアクセストークンが残っていうるユーザー単位の失効を読み込む
+    pub async fn load(&self) -> Result<(), DomainError> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+        let revoked_after = Utc::now() - Duration::hours(ACCESS_TOKEN_LIFETIME_HOURS);
+        let revocations = store.find_after(revoked_after.naive_utc()).await?;
+        let mut state = self.state.lock().unwrap();
+        for (user_id, revoked_at) in revocations {
+            let revoked_at = revoked_at.and_utc().timestamp() as usize;
+            let latest = state.users.entry(user_id.as_str().to_string()).or_default();
+            *latest = (*latest).max(revoked_at);
+        }
+        Ok(())
+    }
+
+    pub fn revoke_token(&self, claims: &Claims) {
+        let mut state = self.state.lock().unwrap();
+        prune(&mut state);
+        state.tokens.insert(claims.jti.clone(), claims.exp);
+    }
+
+    // ユーザーがこれまでに受け取ったアクセストークンをまとめて失効させる
+    // 保存に失敗してもこのプロセスの中では失効させておく
+    pub async fn revoke_user(&self, user_id: &UserId) -> Result<(), DomainError> {
+        let revoked_at = now();
+        {
+            let mut state = self.state.lock().unwrap();
+            prune(&mut state);
+            state.users.insert(user_id.as_str().to_string(), revoked_at);
+        }
+        match &self.store {
+            Some(store) => {
+                let revoked_at = DateTime::from_timestamp(revoked_at as i64, 0)
+                    .unwrap()
+                    .naive_utc();
+                store.save(user_id, revoked_at).await
+            },
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_revoked(&self, claims: &Claims) -> bool {
+        let state = self.state.lock().unwrap();
+        state.tokens.contains_key(&claims.jti)
+            || state
+                .users
+                .get(&claims.sub)
+                .is_some_and(|revoked_at| claims.iat <= *revoked_at)
+    }
+}
+
+fn now() -> usize { Utc::now().timestamp() as usize }
+
+fn prune(state: &mut RevocationState) {
+    let now = now();
+    let lifetime = (ACCESS_TOKEN_LIFETIME_HOURS * 60 * 60) as usize;
+    state.tokens.retain(|_, exp| *exp > now);
+    state
+        .users
+        .retain(|_, revoked_at| *revoked_at + lifetime > now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::database::memory::InMemoryAccessRevocationRepository;
+
+    fn claims(sub: &str, lifetime: Duration) -> Claims {
+        Claims::new(&UserId::new(sub.to_string()).unwrap(), lifetime)
+    }
+
+    #[test]
+    fn test_revoke_token_by_jti() {
+        let store = RevocationStore::new();
+        let revoked = claims("user", Duration::hours(1));
+        let other = claims("user", Duration::hours(1));
+
+        store.revoke_token(&revoked);
+
+        assert!(store.is_revoked(&revoked));
+        assert!(!store.is_revoked(&other));
+    }
+
+    fn user_id(sub: &str) -> UserId { UserId::new(sub.to_string()).unwrap() }
+
+    #[tokio::test]
+    async fn test_revoke_user_tokens() {
+        let store = RevocationStore::new();
+        let first = claims("user", Duration::hours(1));
+        let second = claims("user", Duration::hours(1));
+        let other_user = claims("other", Duration::hours(1));
+
+        store.revoke_user(&user_id("user")).await.unwrap();
+
+        assert!(store.is_revoked(&first));
+        assert!(store.is_revoked(&second));
+        assert!(!store.is_revoked(&other_user));
+    }
+
+    #[test]
+    fn test_expired_entries_are_pruned() {
+        let store = RevocationStore::new();
+        let expired = claims("user", Duration::hours(-2));
+
+        store.revoke_token(&expired);
+        store.revoke_token(&claims("user", Duration::hours(1)));
+
+        assert!(!store.is_revoked(&expired));
+    }
+
+    #[tokio::test]
+    async fn test_user_revocation_survives_restart() {
+        let repository = Arc::new(InMemoryAccessRevocationRepository::new());
+        let issued = claims("user", Duration::hours(1));
+        let store = RevocationStore::new().with_store(repository.clone());
+        store.revoke_user(&user_id("user")).await.unwrap();
+
+        // 再起動してメモリ上の失効が消えても、保存した分を読み込めば失効したまま
+        let restarted = RevocationStore::new().with_store(repository);
+        assert!(!restarted.is_revoked(&issued));
+        restarted.load().await.unwrap();
+        assert!(restarted.is_revoked(&issued));
+        assert!(!restarted.is_revoked(&claims("other", Duration::hours(1))));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod entity;
 pub mod error;
 pub mod repository;
+pub mod service;
@@ -1,2 +1,9 @@
 pub mod diary;
+pub mod display;
+pub mod entry;
+pub mod job;
+pub mod persona;
+pub mod revision;
+pub mod status;
+pub mod token;
 pub mod user;
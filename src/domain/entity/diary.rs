@@ -8,7 +8,8 @@ pub struct ValidDiaryId(i32);
 
 impl ValidDiaryId {
     pub fn new(id: i32) -> Result<Self, DomainError> {
-        if (0..=4).contains(&id) {
+        // 1以上はペルソナのid。どのペルソナが存在するかは persona テーブルで決まる
+        if id >= 0 {
             Ok(ValidDiaryId(id))
         } else {
             Err(DomainError::Validation("invalid diary id".to_string()))
@@ -34,6 +35,9 @@ impl DiaryId {
     pub fn to_id(&self) -> i32 { self.id.value() }
 }
 
+// 和文・欧文の文末記号と改行で文を区切る
+static SENTENCE_TERMINATORS: [char; 7] = ['。', '．', '！', '？', '!', '?', '\n'];
+
 #[derive(Debug, Clone, PartialEq, Eq, Validate)]
 pub struct DiaryContent {
     #[validate(length(min = 1))]
@@ -48,12 +52,20 @@ impl DiaryContent {
     pub fn to_str(&self) -> &str { self.value.as_str() }
     pub fn to_json(&self) -> String { serde_json::to_string(&self.value).unwrap() }
     pub fn to_length(&self) -> i32 { self.value.chars().count() as i32 }
-    pub fn get_from(&self, nth: i32) -> String {
-        self.value.chars().skip(nth as usize).collect() // n文字目より後の文字列を取得
-    }
-
-    pub fn get_to(&self, nth: i32) -> String {
-        self.value.chars().take(nth as usize).collect() // n文字目より前の文字列を取得
+    // 区切り文字の直後で文に分ける。区切り文字は直前の文に含めるので、連結すると元の文字列に戻る
+    pub fn sentences(&self) -> Vec<String> {
+        let mut sentences = vec![];
+        let mut current = String::new();
+        for c in self.value.chars() {
+            current.push(c);
+            if SENTENCE_TERMINATORS.contains(&c) {
+                sentences.push(std::mem::take(&mut current));
+            }
+        }
+        if !current.is_empty() {
+            sentences.push(current);
+        }
+        sentences
     }
 }
 
@@ -63,10 +75,19 @@ pub struct Diary {
     id: DiaryId,
     #[getset(get = "pub", set = "pub")]
     content: DiaryContent,
+    // 保存されている版数。まだ保存されていない日記は0
+    #[getset(get = "pub")]
+    version: i32,
 }
 
 impl Diary {
     pub fn new(id: DiaryId, content: DiaryContent) -> Result<Diary, DomainError> {
-        Ok(Diary { id, content })
+        Ok(Diary {
+            id,
+            content,
+            version: 0,
+        })
     }
+
+    pub fn with_version(self, version: i32) -> Self { Self { version, ..self } }
 }
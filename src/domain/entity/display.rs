@@ -0,0 +1,54 @@
+use chrono::{Duration, NaiveDateTime};
+use getset::Getters;
+
+use crate::domain::entity::user::UserId;
+
+// ペアリングコードは表示端末に出して書き手に入力してもらう。使えるのは発行から一定時間のみ
+const PAIRING_CODE_LIFETIME_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayId {
+    id: String,
+}
+
+impl DisplayId {
+    pub fn new(id: String) -> DisplayId { DisplayId { id } }
+    pub fn as_str(&self) -> &str { &self.id }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct DisplaySession {
+    #[getset(get = "pub")]
+    id: DisplayId,
+    #[getset(get = "pub")]
+    pairing_code: Option<String>,
+    #[getset(get = "pub")]
+    user_id: Option<UserId>,
+    #[getset(get = "pub")]
+    created_at: NaiveDateTime,
+}
+
+impl DisplaySession {
+    pub fn new(
+        id: DisplayId,
+        pairing_code: Option<String>,
+        user_id: Option<UserId>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id,
+            pairing_code,
+            user_id,
+            created_at,
+        }
+    }
+
+    pub fn is_pairing_expired(&self, now: NaiveDateTime) -> bool {
+        self.created_at <= Self::pairing_issued_before(now)
+    }
+
+    // now の時点で期限が切れているペアリングコードの発行時刻の上限
+    pub fn pairing_issued_before(now: NaiveDateTime) -> NaiveDateTime {
+        now - Duration::minutes(PAIRING_CODE_LIFETIME_MINUTES)
+    }
+}
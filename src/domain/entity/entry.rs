@@ -0,0 +1,21 @@
+use chrono::NaiveDate;
+use getset::Getters;
+
+use crate::domain::entity::diary::{Diary, DiaryId};
+
+// 1日分の日記。人間の日記 (id 0) と各ペルソナの日記をまとめたもの
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct DiaryEntry {
+    #[getset(get = "pub")]
+    date: NaiveDate,
+    #[getset(get = "pub")]
+    diaries: Vec<Diary>,
+}
+
+impl DiaryEntry {
+    pub fn new(date: NaiveDate, diaries: Vec<Diary>) -> Self { Self { date, diaries } }
+
+    pub fn find(&self, id: &DiaryId) -> Option<&Diary> {
+        self.diaries.iter().find(|diary| diary.id() == id)
+    }
+}
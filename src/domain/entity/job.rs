@@ -0,0 +1,176 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use getset::Getters;
+
+use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobId {
+    id: String,
+}
+
+impl JobId {
+    pub fn new(id: String) -> JobId { JobId { id } }
+    pub fn as_str(&self) -> &str { &self.id }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    // 書き換えを終えて、人間の日記とまとめて保存するのを待っている
+    Mutated,
+    Done,
+    Failed,
+    // 同じユーザーの新しいジョブに打ち切られて保存していない
+    Superseded,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Mutated => "mutated",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Superseded => "superseded",
+        }
+    }
+
+    pub fn parse(status: &str) -> Result<JobStatus, DomainError> {
+        match status {
+            "pending" => Ok(JobStatus::Pending),
+            "running" => Ok(JobStatus::Running),
+            "mutated" => Ok(JobStatus::Mutated),
+            "done" => Ok(JobStatus::Done),
+            "failed" => Ok(JobStatus::Failed),
+            "superseded" => Ok(JobStatus::Superseded),
+            _ => Err(DomainError::Validation(format!(
+                "unknown job status: {}",
+                status
+            ))),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Done | JobStatus::Failed | JobStatus::Superseded
+        )
+    }
+
+    // ペルソナの書き換えを終えている (保存待ちも含む)
+    pub fn is_settled(&self) -> bool { self.is_finished() || *self == JobStatus::Mutated }
+}
+
+// ジョブのうち1人のペルソナ分の処理
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct PersonaJob {
+    #[getset(get = "pub")]
+    persona_id: DiaryId,
+    #[getset(get = "pub")]
+    status: JobStatus,
+    #[getset(get = "pub")]
+    attempts: i32,
+    // 書き換えたがまだ保存していない日記と、差し込んだ位置
+    // ジョブの全ペルソナが終わってから人間の日記とまとめて保存する
+    #[getset(get = "pub")]
+    output: Option<(Diary, i32)>,
+}
+
+impl PersonaJob {
+    pub fn new(persona_id: DiaryId, status: JobStatus, attempts: i32) -> Self {
+        Self {
+            persona_id,
+            status,
+            attempts,
+            output: None,
+        }
+    }
+
+    pub fn with_output(self, output: Option<(Diary, i32)>) -> Self { Self { output, ..self } }
+}
+
+// 非同期モードの /mutate で登録する書き換えジョブ。ペルソナごとに別々に処理される
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct MutationJob {
+    #[getset(get = "pub")]
+    id: JobId,
+    #[getset(get = "pub")]
+    user_id: UserId,
+    #[getset(get = "pub")]
+    entry_date: NaiveDate,
+    #[getset(get = "pub")]
+    content: DiaryContent,
+    #[getset(get = "pub")]
+    created_at: NaiveDateTime,
+    #[getset(get = "pub")]
+    persona_jobs: Vec<PersonaJob>,
+}
+
+impl MutationJob {
+    pub fn new(
+        id: JobId,
+        user_id: UserId,
+        entry_date: NaiveDate,
+        content: DiaryContent,
+        created_at: NaiveDateTime,
+        persona_jobs: Vec<PersonaJob>,
+    ) -> Self {
+        Self {
+            id,
+            user_id,
+            entry_date,
+            content,
+            created_at,
+            persona_jobs,
+        }
+    }
+
+    pub fn find(&self, persona_id: &DiaryId) -> Option<&PersonaJob> {
+        self.persona_jobs
+            .iter()
+            .find(|persona_job| persona_job.persona_id() == persona_id)
+    }
+
+    // 全ペルソナの書き換えが終わっていれば、まだ保存していなくても true
+    pub fn is_settled(&self) -> bool {
+        self.persona_jobs
+            .iter()
+            .all(|persona_job| persona_job.status().is_settled())
+    }
+
+    // 全ペルソナの処理が終わり、保存待ちの mutated が残っている
+    pub fn is_unsaved(&self) -> bool {
+        self.is_settled()
+            && self
+                .persona_jobs
+                .iter()
+                .any(|persona_job| *persona_job.status() == JobStatus::Mutated)
+    }
+
+    // 全ペルソナが終われば done (1つでも失敗していれば failed、打ち切られていれば superseded)
+    // どれかに手が付いていれば running (保存待ちの mutated が残っている間も running)
+    pub fn status(&self) -> JobStatus {
+        let statuses: Vec<JobStatus> = self
+            .persona_jobs
+            .iter()
+            .map(|persona_job| *persona_job.status())
+            .collect();
+        if statuses.iter().all(JobStatus::is_finished) {
+            if statuses.contains(&JobStatus::Failed) {
+                JobStatus::Failed
+            } else if statuses.contains(&JobStatus::Superseded) {
+                JobStatus::Superseded
+            } else {
+                JobStatus::Done
+            }
+        } else if statuses.iter().all(|status| *status == JobStatus::Pending) {
+            JobStatus::Pending
+        } else {
+            JobStatus::Running
+        }
+    }
+}
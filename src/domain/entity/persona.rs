@@ -0,0 +1,27 @@
+use getset::Getters;
+
+use crate::domain::entity::diary::DiaryId;
+
+// AIの書き手。id はそのペルソナが書く日記の DiaryId と一致する (0 は人間の日記)
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct Persona {
+    #[getset(get = "pub")]
+    id: DiaryId,
+    #[getset(get = "pub")]
+    name: String,
+    #[getset(get = "pub")]
+    prompt: String,
+    #[getset(get = "pub")]
+    display_order: i32,
+}
+
+impl Persona {
+    pub fn new(id: DiaryId, name: String, prompt: String, display_order: i32) -> Self {
+        Self {
+            id,
+            name,
+            prompt,
+            display_order,
+        }
+    }
+}
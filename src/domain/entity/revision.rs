@@ -0,0 +1,34 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use getset::Getters;
+
+use crate::domain::entity::diary::Diary;
+
+// /mutate ごとに記録する日記の版。人間の入力と各ペルソナの出力をそれぞれ1件として残す
+// target_index は書き換えを始めた位置 (文字数)。全文を書き換えた場合は 0
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct DiaryRevision {
+    #[getset(get = "pub")]
+    entry_date: NaiveDate,
+    #[getset(get = "pub")]
+    diary: Diary,
+    #[getset(get = "pub")]
+    target_index: i32,
+    #[getset(get = "pub")]
+    created_at: NaiveDateTime,
+}
+
+impl DiaryRevision {
+    pub fn new(
+        entry_date: NaiveDate,
+        diary: Diary,
+        target_index: i32,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            entry_date,
+            diary,
+            target_index,
+            created_at,
+        }
+    }
+}
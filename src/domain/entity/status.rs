@@ -0,0 +1,70 @@
+use chrono::NaiveDateTime;
+use getset::Getters;
+
+use crate::domain::entity::diary::DiaryId;
+use crate::domain::error::DomainError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutationState {
+    Ok,
+    Failed,
+}
+
+impl MutationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MutationState::Ok => "ok",
+            MutationState::Failed => "failed",
+        }
+    }
+
+    pub fn parse(state: &str) -> Result<MutationState, DomainError> {
+        match state {
+            "ok" => Ok(MutationState::Ok),
+            "failed" => Ok(MutationState::Failed),
+            _ => Err(DomainError::Validation(format!(
+                "unknown mutation state: {}",
+                state
+            ))),
+        }
+    }
+}
+
+// ペルソナの直近の書き換えの結果。失敗しても日記は前回のまま残し、理由はここに記録する
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct DiaryStatus {
+    #[getset(get = "pub")]
+    diary_id: DiaryId,
+    #[getset(get = "pub")]
+    state: MutationState,
+    #[getset(get = "pub")]
+    reason: Option<String>,
+    #[getset(get = "pub")]
+    updated_at: NaiveDateTime,
+}
+
+impl DiaryStatus {
+    pub fn new(
+        diary_id: DiaryId,
+        state: MutationState,
+        reason: Option<String>,
+        updated_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            diary_id,
+            state,
+            reason,
+            updated_at,
+        }
+    }
+
+    pub fn ok(diary_id: DiaryId, updated_at: NaiveDateTime) -> Self {
+        Self::new(diary_id, MutationState::Ok, None, updated_at)
+    }
+
+    pub fn failed(diary_id: DiaryId, reason: String, updated_at: NaiveDateTime) -> Self {
+        Self::new(diary_id, MutationState::Failed, Some(reason), updated_at)
+    }
+
+    pub fn is_failed(&self) -> bool { self.state == MutationState::Failed }
+}
@@ -0,0 +1,47 @@
+use chrono::NaiveDateTime;
+use getset::Getters;
+use sha2::{Digest, Sha256};
+
+use crate::domain::entity::user::UserId;
+
+// リフレッシュトークンはハッシュ値だけを保存し、平文はクライアントにだけ渡す
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RefreshTokenHash(String);
+
+impl RefreshTokenHash {
+    pub fn from_plain(token: &str) -> Self { Self(hex::encode(Sha256::digest(token.as_bytes()))) }
+    pub fn from_hash(hash: String) -> Self { Self(hash) }
+    pub fn as_str(&self) -> &str { &self.0 }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct RefreshToken {
+    #[getset(get = "pub")]
+    hash: RefreshTokenHash,
+    #[getset(get = "pub")]
+    user_id: UserId,
+    #[getset(get = "pub")]
+    expires_at: NaiveDateTime,
+    #[getset(get = "pub")]
+    revoked_at: Option<NaiveDateTime>,
+}
+
+impl RefreshToken {
+    pub fn new(
+        hash: RefreshTokenHash,
+        user_id: UserId,
+        expires_at: NaiveDateTime,
+        revoked_at: Option<NaiveDateTime>,
+    ) -> Self {
+        Self {
+            hash,
+            user_id,
+            expires_at,
+            revoked_at,
+        }
+    }
+
+    pub fn is_revoked(&self) -> bool { self.revoked_at.is_some() }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool { self.expires_at <= now }
+}
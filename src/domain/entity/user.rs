@@ -1,7 +1,7 @@
 use chrono::NaiveDateTime;
 use getset::{Getters, Setters};
 
-use crate::domain::entity::diary::{Diary, DiaryId};
+use crate::domain::entity::diary::DiaryId;
 use crate::domain::error::DomainError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -19,16 +19,6 @@ pub struct User {
     #[getset(get = "pub")]
     pub id: UserId,
     #[getset(get = "pub", set = "pub")]
-    pub human_diary: Option<Diary>,
-    #[getset(get = "pub", set = "pub")]
-    pub ai_diary_1: Option<Diary>,
-    #[getset(get = "pub", set = "pub")]
-    pub ai_diary_2: Option<Diary>,
-    #[getset(get = "pub", set = "pub")]
-    pub ai_diary_3: Option<Diary>,
-    #[getset(get = "pub", set = "pub")]
-    pub ai_diary_4: Option<Diary>,
-    #[getset(get = "pub", set = "pub")]
     pub is_public: Option<bool>,
     #[getset(get = "pub", set = "pub")]
     pub favorite_id: Option<DiaryId>,
@@ -36,42 +26,27 @@ pub struct User {
     pub created_at: NaiveDateTime,
     #[getset(get = "pub", set = "pub")]
     pub updated_at: NaiveDateTime,
+    // 保存されている版数。update_result のたびに1つ増える
+    #[getset(get = "pub")]
+    pub version: i32,
 }
 
 impl User {
-    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: UserId,
-        human_diary: Option<Diary>,
-        ai_diary_1: Option<Diary>,
-        ai_diary_2: Option<Diary>,
-        ai_diary_3: Option<Diary>,
-        ai_diary_4: Option<Diary>,
         is_public: Option<bool>,
         favorite_id: Option<DiaryId>,
         created_at: NaiveDateTime,
         updated_at: NaiveDateTime,
+        version: i32,
     ) -> Self {
         Self {
             id,
-            human_diary,
-            ai_diary_1,
-            ai_diary_2,
-            ai_diary_3,
-            ai_diary_4,
             is_public,
             favorite_id,
             created_at,
             updated_at,
-        }
-    }
-    pub fn get_diary_by_id(self, id: &DiaryId) -> Option<Diary> {
-        match id.to_id() {
-            1 => self.ai_diary_1.clone(),
-            2 => self.ai_diary_2.clone(),
-            3 => self.ai_diary_3.clone(),
-            4 => self.ai_diary_4.clone(),
-            _ => None,
+            version,
         }
     }
 }
@@ -11,6 +11,9 @@ pub enum DomainError {
         entity_type: &'static str,
         user_id: String,
     },
+    // 読んだ後に他のリクエストが先に保存していた
+    #[error("{0}")]
+    Conflict(String),
     #[error(transparent)]
     InfrastructureError(anyhow::Error),
     #[error("{0}")]
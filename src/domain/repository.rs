@@ -1,2 +1,9 @@
+pub mod completion;
 pub mod diary;
+pub mod display;
+pub mod job;
+pub mod persona;
+pub mod revision;
+pub mod revocation;
+pub mod token;
 pub mod user;
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+use crate::domain::error::DomainError;
+
+// LLM の応答を、モデルとプロンプトから作ったキーで保存しておく
+#[async_trait]
+pub trait CompletionCacheRepository: Send + Sync + 'static {
+    // created_after 以降に保存したものだけを返す
+    async fn find(
+        &self,
+        cache_key: &str,
+        created_after: NaiveDateTime,
+    ) -> Result<Option<String>, DomainError>;
+    // 同じキーがあれば置き換える
+    async fn save(&self, cache_key: &str, model: &str, output: &str) -> Result<(), DomainError>;
+}
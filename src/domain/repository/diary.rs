@@ -1,17 +1,58 @@
 use async_trait::async_trait;
+use chrono::NaiveDate;
 
 use crate::domain::entity::diary::{Diary, DiaryId};
+use crate::domain::entity::entry::DiaryEntry;
+use crate::domain::entity::job::JobId;
+use crate::domain::entity::status::DiaryStatus;
 use crate::domain::entity::user::UserId;
 use crate::domain::error::DomainError;
 
 #[async_trait]
-#[allow(dead_code)]
 pub trait DiaryRepository: Send + Sync + 'static {
     async fn find_by_id(
         &self,
         user_id: &UserId,
+        entry_date: &NaiveDate,
         diary_id: &DiaryId,
     ) -> Result<Option<Diary>, DomainError>;
-    async fn find_all(&self, user_id: &UserId) -> Result<Vec<Diary>, DomainError>;
-    async fn update(&self, user_id: &UserId, diary: &Diary) -> Result<(), DomainError>;
+    async fn find_all(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+    ) -> Result<Vec<Diary>, DomainError>;
+    // 新しい日付順
+    async fn find_entries(&self, user_id: &UserId) -> Result<Vec<DiaryEntry>, DomainError>;
+    async fn find_latest_date(&self, user_id: &UserId) -> Result<Option<NaiveDate>, DomainError>;
+    // 同じ日付の複数の日記と書き換えの状態を1つのトランザクションで保存する
+    // 日記ごとに version が保存されている版数と一致するときだけ保存し、版数を1つ進める
+    // version が0なら新しく作る。一致しない (既にある) 日記が1つでもあれば Conflict を返し、どれも保存しない
+    async fn update_all(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        statuses: &[DiaryStatus],
+    ) -> Result<(), DomainError>;
+    // update_all と同じトランザクションで、ジョブの保存待ち (mutated) のペルソナの処理を done にする
+    async fn update_all_for_job(
+        &self,
+        job_id: &JobId,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        statuses: &[DiaryStatus],
+    ) -> Result<(), DomainError>;
+    async fn find_status(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary_id: &DiaryId,
+    ) -> Result<Option<DiaryStatus>, DomainError>;
+    async fn update_status(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        status: &DiaryStatus,
+    ) -> Result<(), DomainError>;
 }
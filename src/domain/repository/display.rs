@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+use crate::domain::entity::display::{DisplayId, DisplaySession};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+
+#[async_trait]
+pub trait DisplaySessionRepository: Send + Sync + 'static {
+    async fn create(&self, session: &DisplaySession) -> Result<(), DomainError>;
+    async fn find_by_id(&self, id: &DisplayId) -> Result<Option<DisplaySession>, DomainError>;
+    async fn find_by_pairing_code(
+        &self,
+        pairing_code: &str,
+    ) -> Result<Option<DisplaySession>, DomainError>;
+    // pairing_code がまだ使われていなければ user_id を紐付け、ペアリングコードは使えなくする
+    // 他のリクエストが先に紐付けていた場合は false を返す
+    async fn pair(
+        &self,
+        id: &DisplayId,
+        pairing_code: &str,
+        user_id: &UserId,
+    ) -> Result<bool, DomainError>;
+    // issued_before より前に発行したまま使われなかったペアリングコードを消し、同じコードを発行できるようにする
+    async fn release_expired_codes(&self, issued_before: NaiveDateTime) -> Result<(), DomainError>;
+}
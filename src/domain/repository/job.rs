@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+
+use crate::domain::entity::diary::{Diary, DiaryId};
+use crate::domain::entity::job::{JobId, JobStatus, MutationJob};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+
+#[async_trait]
+pub trait MutationJobRepository: Send + Sync + 'static {
+    // ジョブとペルソナごとの処理をまとめて登録する
+    async fn create(&self, job: &MutationJob) -> Result<(), DomainError>;
+    async fn find_by_id(&self, id: &JobId) -> Result<Option<MutationJob>, DomainError>;
+    // ユーザーが最後に登録したジョブ。これより古いジョブは打ち切られたものとして扱う
+    async fn find_latest_id(&self, user_id: &UserId) -> Result<Option<JobId>, DomainError>;
+    // 待っているペルソナの処理を古い順に1つ running にして返す。試行回数はここで数える
+    async fn claim_next(&self) -> Result<Option<(MutationJob, DiaryId)>, DomainError>;
+    async fn update_status(
+        &self,
+        id: &JobId,
+        persona_id: &DiaryId,
+        status: JobStatus,
+    ) -> Result<(), DomainError>;
+    // 書き換えた日記を残して mutated にする。日記はジョブが終わってからまとめて保存し、
+    // その同じトランザクションで done にする (DiaryRepository::update_all_for_job)
+    async fn save_output(
+        &self,
+        id: &JobId,
+        persona_id: &DiaryId,
+        output: &Diary,
+        target_index: i32,
+    ) -> Result<(), DomainError>;
+    // 全ペルソナの処理が終わり、書き換えた日記の保存を待っているジョブを登録順に返す
+    async fn find_unsaved(&self) -> Result<Vec<MutationJob>, DomainError>;
+    // 前のプロセスが running のまま落ちた処理を pending に戻す
+    async fn requeue_running(&self) -> Result<(), DomainError>;
+}
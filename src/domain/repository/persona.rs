@@ -0,0 +1,10 @@
+use async_trait::async_trait;
+
+use crate::domain::entity::persona::Persona;
+use crate::domain::error::DomainError;
+
+#[async_trait]
+pub trait PersonaRepository: Send + Sync + 'static {
+    // 有効なペルソナを display_order 順に返す
+    async fn find_enabled(&self) -> Result<Vec<Persona>, DomainError>;
+}
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+
+use crate::domain::entity::revision::DiaryRevision;
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+
+// 追記のみ。既存の版を書き換える操作は持たない
+#[async_trait]
+pub trait DiaryRevisionRepository: Send + Sync + 'static {
+    async fn append(&self, user_id: &UserId, revision: &DiaryRevision) -> Result<(), DomainError>;
+    // 記録した順に返す
+    async fn find_by_user(&self, user_id: &UserId) -> Result<Vec<DiaryRevision>, DomainError>;
+}
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+
+// ユーザーのアクセストークンをまとめて失効させた時刻。再起動しても失効が消えないよう保存しておく
+#[async_trait]
+pub trait AccessRevocationRepository: Send + Sync + 'static {
+    // 同じユーザーがあれば置き換える
+    async fn save(&self, user_id: &UserId, revoked_at: NaiveDateTime) -> Result<(), DomainError>;
+    // revoked_after 以降に失効させたものだけを返す
+    async fn find_after(
+        &self,
+        revoked_after: NaiveDateTime,
+    ) -> Result<Vec<(UserId, NaiveDateTime)>, DomainError>;
+}
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+
+use crate::domain::entity::token::{RefreshToken, RefreshTokenHash};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+
+#[async_trait]
+pub trait RefreshTokenRepository: Send + Sync + 'static {
+    async fn create(&self, token: &RefreshToken) -> Result<(), DomainError>;
+    async fn find_by_hash(
+        &self,
+        hash: &RefreshTokenHash,
+    ) -> Result<Option<RefreshToken>, DomainError>;
+    // まだ失効していなかったトークンをこの呼び出しで失効させた場合だけ true を返す
+    async fn revoke(&self, hash: &RefreshTokenHash) -> Result<bool, DomainError>;
+    async fn revoke_all(&self, user_id: &UserId) -> Result<(), DomainError>;
+}
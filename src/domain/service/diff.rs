@@ -0,0 +1,221 @@
+use crate::domain::entity::diary::DiaryContent;
+
+// 変更前後の文字列から先頭と末尾の一致部分を除いた範囲 (文字単位)
+// old[start..old_end] が new[start..new_end] に置き換わったことを表す
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedSpan {
+    pub start: usize,
+    pub old_end: usize,
+    pub new_end: usize,
+}
+
+pub fn changed_span(old: &[char], new: &[char]) -> ChangedSpan {
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    ChangedSpan {
+        start: prefix,
+        old_end: old.len() - suffix,
+        new_end: new.len() - suffix,
+    }
+}
+
+// 人間の日記の変更を文単位に広げたもの
+// 先頭 prefix 文と末尾 suffix 文は変更前後で同じ文字列になっている
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentenceDiff {
+    prefix: usize,
+    suffix: usize,
+    old_count: usize,
+    target_index: i32,
+    changed_text: String,
+}
+
+impl SentenceDiff {
+    pub fn between(old: &DiaryContent, new: &DiaryContent) -> Self {
+        let old_chars: Vec<char> = old.to_value().chars().collect();
+        let new_chars: Vec<char> = new.to_value().chars().collect();
+        let span = changed_span(&old_chars, &new_chars);
+        let old_sentences = old.sentences();
+        let new_sentences = new.sentences();
+
+        // 変更範囲の境界にちょうど区切りがあるかどうかは前後で異なりうるので、少ない方に合わせる
+        let prefix = count_ending_by(&old_sentences, span.start)
+            .min(count_ending_by(&new_sentences, span.start));
+        let suffix = count_starting_from(&old_sentences, span.old_end)
+            .min(count_starting_from(&new_sentences, span.new_end))
+            .min(old_sentences.len() - prefix)
+            .min(new_sentences.len() - prefix);
+
+        Self {
+            prefix,
+            suffix,
+            old_count: old_sentences.len(),
+            target_index: new_sentences[..prefix]
+                .iter()
+                .map(|sentence| sentence.chars().count() as i32)
+                .sum(),
+            changed_text: new_sentences[prefix..new_sentences.len() - suffix].concat(),
+        }
+    }
+
+    // 書き換えを始める位置 (先頭から変更されていない文の文字数)
+    pub fn target_index(&self) -> i32 { self.target_index }
+
+    // 書き換えが必要な新しい文 (連結済み)
+    pub fn changed_text(&self) -> &str { &self.changed_text }
+
+    // AIの日記が変更前の人間の日記と文単位で対応していなければ差し替えられない
+    pub fn is_aligned_with(&self, ai_content: &DiaryContent) -> bool {
+        ai_content.sentences().len() == self.old_count
+    }
+
+    // 変更されていない文は ai_content のまま残し、変更された文だけを mutated に置き換える
+    pub fn splice(&self, ai_content: &DiaryContent, mutated: &str) -> Option<String> {
+        let sentences = ai_content.sentences();
+        if sentences.len() != self.old_count {
+            return None;
+        }
+        Some(format!(
+            "{}{}{}",
+            sentences[..self.prefix].concat(),
+            mutated,
+            sentences[self.old_count - self.suffix..].concat()
+        ))
+    }
+}
+
+// pos 文字目までに終わっている先頭からの文の数
+fn count_ending_by(sentences: &[String], pos: usize) -> usize {
+    let mut end = 0;
+    sentences
+        .iter()
+        .take_while(|sentence| {
+            end += sentence.chars().count();
+            end <= pos
+        })
+        .count()
+}
+
+// pos 文字目以降から始まっている末尾からの文の数
+fn count_starting_from(sentences: &[String], pos: usize) -> usize {
+    let mut start: usize = sentences
+        .iter()
+        .map(|sentence| sentence.chars().count())
+        .sum();
+    sentences
+        .iter()
+        .rev()
+        .take_while(|sentence| {
+            start -= sentence.chars().count();
+            start >= pos
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(text: &str) -> DiaryContent { DiaryContent::new(text.to_string()).unwrap() }
+
+    fn chars(text: &str) -> Vec<char> { text.chars().collect() }
+
+    #[test]
+    fn test_changed_span() {
+        assert_eq!(
+            changed_span(&chars("今日は晴れた．"), &chars("今日は雨だった．")),
+            ChangedSpan {
+                start: 3,
+                old_end: 5,
+                new_end: 6,
+            }
+        );
+        assert_eq!(
+            changed_span(&chars("あいう"), &chars("あいう")),
+            ChangedSpan {
+                start: 3,
+                old_end: 3,
+                new_end: 3,
+            }
+        );
+        // 繰り返し文字で前後の一致部分が重ならない
+        assert_eq!(
+            changed_span(&chars("ああ"), &chars("あああ")),
+            ChangedSpan {
+                start: 2,
+                old_end: 2,
+                new_end: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_sentence_diff_for_mid_text_edit() {
+        let old = content("朝起きた．昼は晴れた．夜に寝た．");
+        let new = content("朝起きた．昼は雨だった．夜に寝た．");
+
+        let diff = SentenceDiff::between(&old, &new);
+
+        assert_eq!(diff.changed_text(), "昼は雨だった．");
+        assert_eq!(diff.target_index(), 5);
+        let ai = content("朝目覚めた．昼は快晴だった．夜に眠った．");
+        assert_eq!(
+            diff.splice(&ai, "昼は大雨だった．").unwrap(),
+            "朝目覚めた．昼は大雨だった．夜に眠った．"
+        );
+    }
+
+    #[test]
+    fn test_sentence_diff_for_append() {
+        let diff = SentenceDiff::between(&content("一文目．"), &content("一文目．二文目．"));
+
+        assert_eq!(diff.changed_text(), "二文目．");
+        assert_eq!(
+            diff.splice(&content("いち．"), "に．").unwrap(),
+            "いち．に．"
+        );
+    }
+
+    #[test]
+    fn test_sentence_diff_when_terminator_is_removed() {
+        let diff = SentenceDiff::between(&content("A．B．C．"), &content("A．BC．"));
+
+        assert_eq!(diff.changed_text(), "BC．");
+        assert_eq!(
+            diff.splice(&content("a．b．c．"), "bc．").unwrap(),
+            "a．bc．"
+        );
+    }
+
+    #[test]
+    fn test_sentence_diff_for_unchanged_text() {
+        let diff = SentenceDiff::between(&content("同じ．文．"), &content("同じ．文．"));
+
+        assert_eq!(diff.changed_text(), "");
+        assert_eq!(
+            diff.splice(&content("おなじ．ぶん．"), "").unwrap(),
+            "おなじ．ぶん．"
+        );
+    }
+
+    #[test]
+    fn test_splice_requires_aligned_sentences() {
+        let diff = SentenceDiff::between(&content("一．二．"), &content("一．三．"));
+        let merged = content("一と二．");
+
+        assert!(!diff.is_aligned_with(&merged));
+        assert!(diff.splice(&merged, "三．").is_none());
+    }
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use getset::Getters;
+
+use crate::domain::error::DomainError;
+
+// 同じモデル・同じプロンプトの応答をキャッシュから返してよいかどうか
+// Bypass でも新しい応答はキャッシュに書き込む
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheMode {
+    #[default]
+    Use,
+    Bypass,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Getters)]
+pub struct LlmRequest {
+    #[getset(get = "pub")]
+    model: String,
+    #[getset(get = "pub")]
+    prompt: String,
+    #[getset(get = "pub")]
+    cache_mode: CacheMode,
+}
+
+impl LlmRequest {
+    pub fn new(model: String, prompt: String) -> Self {
+        Self {
+            model,
+            prompt,
+            cache_mode: CacheMode::Use,
+        }
+    }
+
+    pub fn with_cache_mode(self, cache_mode: CacheMode) -> Self { Self { cache_mode, ..self } }
+
+    pub fn with_model(&self, model: String) -> Self {
+        Self {
+            model,
+            ..self.clone()
+        }
+    }
+}
+
+// 書き換え結果の本文だけを返す。通信失敗は InfrastructureError、応答形式の不備は Unexpected とする
+#[async_trait]
+pub trait LlmClient: Send + Sync + 'static {
+    async fn complete(&self, request: &LlmRequest) -> Result<String, DomainError>;
+
+    // 受け取った分ずつ on_progress に渡しながら書き換える。on_progress にはそれまでに届いた本文全体を渡す
+    // ストリーミングに対応しないクライアントは、完了時に1回だけ呼ぶ
+    async fn complete_stream(
+        &self,
+        request: &LlmRequest,
+        on_progress: &(dyn for<'s> Fn(&'s str) + Send + Sync),
+    ) -> Result<String, DomainError> {
+        let text = self.complete(request).await?;
+        on_progress(&text);
+        Ok(text)
+    }
+}
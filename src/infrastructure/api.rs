@@ -1 +1,13 @@
+pub mod anthropic;
+pub mod breaker;
+pub mod cache;
+pub mod fallback;
+pub mod http;
+#[cfg(test)]
+pub mod mock;
+pub mod ollama;
 pub mod openai;
+pub mod provider;
+pub mod retry;
+#[cfg(test)]
+pub mod stub;
@@ -0,0 +1,176 @@
+use async_trait::async_trait;
+
+use super::http::{receive_deltas, Delta, Framing, HttpTransport};
+use super::retry::RetryPolicy;
+use crate::domain::error::DomainError;
+use crate::domain::service::llm::{LlmClient, LlmRequest};
+
+pub const ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+// Messages API では応答の長さの上限が必須
+const MAX_TOKENS: u32 = 4096;
+
+// Anthropic の Messages API を呼ぶ
+#[derive(Clone)]
+pub struct AnthropicClient {
+    http: HttpTransport,
+    api_url: String,
+    api_key: String,
+}
+
+impl AnthropicClient {
+    pub fn with_policy(base_url: String, api_key: String, policy: RetryPolicy) -> Self {
+        Self {
+            http: HttpTransport::new(policy),
+            api_url: format!("{}/v1/messages", base_url.trim_end_matches('/')),
+            api_key,
+        }
+    }
+
+    async fn post(&self, body: &serde_json::Value) -> Result<reqwest::Response, DomainError> {
+        self.http
+            .post(|client| {
+                client
+                    .post(&self.api_url)
+                    .header("x-api-key", &self.api_key)
+                    .header("anthropic-version", ANTHROPIC_VERSION)
+                    .json(body)
+            })
+            .await
+    }
+}
+
+fn request_body(request: &LlmRequest, stream: bool) -> serde_json::Value {
+    serde_json::json!({
+        "model": request.model(),
+        "max_tokens": MAX_TOKENS,
+        "messages": [{"role": "user", "content": request.prompt()}],
+        "stream": stream
+    })
+}
+
+#[async_trait]
+impl LlmClient for AnthropicClient {
+    async fn complete(&self, request: &LlmRequest) -> Result<String, DomainError> {
+        self.http
+            .within_deadline(async {
+                let response = self.post(&request_body(request, false)).await?;
+                let res_json = response
+                    .json::<serde_json::Value>()
+                    .await
+                    .map_err(|err| DomainError::Unexpected(err.to_string()))?;
+                message_text(&res_json)
+            })
+            .await
+    }
+
+    async fn complete_stream(
+        &self,
+        request: &LlmRequest,
+        on_progress: &(dyn for<'s> Fn(&'s str) + Send + Sync),
+    ) -> Result<String, DomainError> {
+        self.http
+            .within_deadline(async {
+                let response = self.post(&request_body(request, true)).await?;
+                receive_deltas(response, Framing::Sse, event_content, on_progress).await
+            })
+            .await
+    }
+}
+
+// 応答の content はブロックの配列で、本文は text のブロックに入っている
+fn message_text(response: &serde_json::Value) -> Result<String, DomainError> {
+    let blocks = response["content"]
+        .as_array()
+        .ok_or_else(|| DomainError::Unexpected("missing content in message".to_string()))?;
+    Ok(blocks
+        .iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .collect())
+}
+
+// 本文は content_block_delta の text_delta で届き、message_stop で終わる
+// 途中で届く error (混雑など) は別の接続先で試せるよう通信の失敗として扱う
+fn event_content(data: &str) -> Result<Delta, DomainError> {
+    let event: serde_json::Value =
+        serde_json::from_str(data).map_err(|err| DomainError::Unexpected(err.to_string()))?;
+    Ok(match event["type"].as_str() {
+        Some("content_block_delta") => match event["delta"]["text"].as_str() {
+            Some(text) => Delta::Content(text.to_string()),
+            None => Delta::Empty,
+        },
+        Some("message_stop") => Delta::Done,
+        Some("error") => {
+            return Err(DomainError::InfrastructureError(anyhow::anyhow!(
+                "completion stream failed: {}",
+                event["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+            )))
+        },
+        _ => Delta::Empty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stub;
+    use super::*;
+
+    #[test]
+    fn test_reads_text_blocks_and_stream_events() {
+        let response = serde_json::json!({
+            "content": [
+                {"type": "text", "text": "書き"},
+                {"type": "text", "text": "換えた．"}
+            ]
+        });
+        assert_eq!(message_text(&response).unwrap(), "書き換えた．");
+        assert!(message_text(&serde_json::json!({"type": "error"})).is_err());
+
+        let delta = serde_json::json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": {"type": "text_delta", "text": "書き"}
+        })
+        .to_string();
+        assert!(matches!(event_content(&delta).unwrap(), Delta::Content(text) if text == "書き"));
+        assert!(matches!(
+            event_content(r#"{"type": "ping"}"#).unwrap(),
+            Delta::Empty
+        ));
+        assert!(matches!(
+            event_content(r#"{"type": "message_stop"}"#).unwrap(),
+            Delta::Done
+        ));
+        assert!(matches!(
+            event_content(r#"{"type": "error", "error": {"message": "Overloaded"}}"#),
+            Err(DomainError::InfrastructureError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sends_messages_request() {
+        let (url, captured) = stub::capture(serde_json::json!({
+            "content": [{"type": "text", "text": "書き換えた．"}]
+        }))
+        .await;
+        let client =
+            AnthropicClient::with_policy(url, "test-key".to_string(), RetryPolicy::default());
+        let request = LlmRequest::new(
+            "claude-3-5-haiku-latest".to_string(),
+            "書き換えて".to_string(),
+        );
+
+        assert_eq!(client.complete(&request).await.unwrap(), "書き換えた．");
+        let captured = captured.await.unwrap();
+        assert_eq!(captured.path, "/v1/messages");
+        assert_eq!(captured.headers["x-api-key"], "test-key");
+        assert_eq!(captured.headers["anthropic-version"], ANTHROPIC_VERSION);
+        assert_eq!(captured.body["model"], "claude-3-5-haiku-latest");
+        assert_eq!(captured.body["max_tokens"], MAX_TOKENS);
+        assert_eq!(captured.body["messages"][0]["content"], "書き換えて");
+        assert_eq!(captured.body["stream"], false);
+    }
+}
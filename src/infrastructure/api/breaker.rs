@@ -0,0 +1,143 @@
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_THRESHOLD: u32 = 5;
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open,
+    // 止めてから cooldown が過ぎ、試しの呼び出しを1つだけ通している
+    HalfOpen,
+}
+
+impl BreakerState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    // until を過ぎるまでは呼び出しを通さない。probing は試しの呼び出しを通した後かどうか
+    Open { until: Instant, probing: bool },
+}
+
+// 接続先ごとのサーキットブレーカー
+// threshold 回続けて失敗したら cooldown の間は呼び出しを止め、その後は1つずつ試して復旧を確かめる
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            state: Arc::new(Mutex::new(State::Closed { failures: 0 })),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let threshold = env::var("LLM_BREAKER_THRESHOLD")
+            .ok()
+            .and_then(|threshold| threshold.parse().ok())
+            .unwrap_or(DEFAULT_THRESHOLD);
+        let cooldown = env::var("LLM_BREAKER_COOLDOWN_SECS")
+            .ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_COOLDOWN);
+        Self::new(threshold, cooldown)
+    }
+
+    // 呼び出してよいかどうか。止めている間は false を返す
+    // 試しの呼び出しが打ち切られて結果が返らなくても、次の cooldown の後にはまた試せる
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { until, .. } if Instant::now() >= until => {
+                *state = State::Open {
+                    until: Instant::now() + self.cooldown,
+                    probing: true,
+                };
+                true
+            },
+            State::Open { .. } => false,
+        }
+    }
+
+    pub fn record_success(&self) { *self.state.lock().unwrap() = State::Closed { failures: 0 }; }
+
+    // 止めた (または止め直した) ときに true を返す
+    pub fn record_failure(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let failures = match *state {
+            State::Closed { failures } => failures + 1,
+            State::Open { probing: false, .. } => return false,
+            State::Open { probing: true, .. } => self.threshold,
+        };
+        *state = if failures >= self.threshold {
+            State::Open {
+                until: Instant::now() + self.cooldown,
+                probing: false,
+            }
+        } else {
+            State::Closed { failures }
+        };
+        failures >= self.threshold
+    }
+
+    pub fn state(&self) -> BreakerState {
+        match *self.state.lock().unwrap() {
+            State::Closed { .. } => BreakerState::Closed,
+            State::Open { probing: true, .. } => BreakerState::HalfOpen,
+            State::Open { until, .. } if Instant::now() >= until => BreakerState::HalfOpen,
+            State::Open { .. } => BreakerState::Open,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_consecutive_failures_and_probes_after_cooldown() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+
+        assert!(!breaker.record_failure());
+        breaker.record_success();
+        assert!(!breaker.record_failure());
+        assert!(breaker.record_failure());
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(!breaker.try_acquire());
+
+        std::thread::sleep(Duration::from_millis(30));
+        // cooldown の後は1つだけ通す
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+
+        // 試しの呼び出しが失敗したらすぐに止め直す
+        assert!(breaker.record_failure());
+        assert!(!breaker.try_acquire());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.try_acquire());
+        breaker.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.try_acquire());
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use log::warn;
+use sha2::{Digest, Sha256};
+
+use super::fallback::{FallbackLlmClient, RoutedCompletion};
+use crate::domain::error::DomainError;
+use crate::domain::repository::completion::CompletionCacheRepository;
+use crate::domain::service::llm::{CacheMode, LlmClient, LlmRequest};
+
+const DEFAULT_CAPACITY: usize = 1_000;
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// 最近使った順に capacity 件まで残す
+struct Lru {
+    capacity: usize,
+    // キー -> (応答, 保存した時刻, 最後に使った順番)
+    entries: HashMap<String, (String, Instant, u64)>,
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, key: &str, ttl: Duration) -> Option<String> {
+        let (output, saved_at, used) = self.entries.get_mut(key)?;
+        if saved_at.elapsed() > ttl {
+            let used = *used;
+            self.order.remove(&used);
+            self.entries.remove(key);
+            return None;
+        }
+        self.tick += 1;
+        self.order.remove(used);
+        *used = self.tick;
+        self.order.insert(self.tick, key.to_string());
+        Some(output.clone())
+    }
+
+    fn insert(&mut self, key: &str, output: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some((_, _, used)) = self.entries.remove(key) {
+            self.order.remove(&used);
+        }
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.entries.insert(
+            key.to_string(),
+            (output.to_string(), Instant::now(), self.tick),
+        );
+        self.order.insert(self.tick, key.to_string());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+// 応答した接続先が分かるクライアント
+// 代わりの接続先の応答を先頭の接続先の応答としてキャッシュしないために使う
+#[async_trait]
+pub trait RoutedLlmClient: Send + Sync + 'static {
+    // 先頭の接続先の名前と、そこで使うモデル
+    fn primary_route(&self, request: &LlmRequest) -> (String, String);
+
+    async fn complete_routed(
+        &self,
+        request: &LlmRequest,
+        on_progress: Option<&(dyn for<'s> Fn(&'s str) + Send + Sync)>,
+    ) -> Result<RoutedCompletion, DomainError>;
+}
+
+#[async_trait]
+impl RoutedLlmClient for FallbackLlmClient {
+    fn primary_route(&self, request: &LlmRequest) -> (String, String) {
+        FallbackLlmClient::primary_route(self, request)
+    }
+
+    async fn complete_routed(
+        &self,
+        request: &LlmRequest,
+        on_progress: Option<&(dyn for<'s> Fn(&'s str) + Send + Sync)>,
+    ) -> Result<RoutedCompletion, DomainError> {
+        FallbackLlmClient::complete_routed(self, request, on_progress).await
+    }
+}
+
+// 先頭の接続先での同じモデル・同じプロンプト (ペルソナの指示と入力文) への応答を再利用する
+// 代わりの接続先が返した応答はキャッシュしないので、先頭の接続先が戻れば次からはそちらの応答になる
+// メモリ上の LRU を先に引き、無ければ store (DB) を引く。どちらも ttl を過ぎたものは使わない
+// キャッシュの読み書きに失敗しても書き換えは止めない
+#[derive(Clone)]
+pub struct CachedLlmClient<C: RoutedLlmClient> {
+    client: Arc<C>,
+    memory: Arc<Mutex<Lru>>,
+    store: Option<Arc<dyn CompletionCacheRepository>>,
+    ttl: Duration,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl<C: RoutedLlmClient> CachedLlmClient<C> {
+    pub fn new(client: C, capacity: usize, ttl: Duration) -> Self {
+        Self {
+            client: Arc::new(client),
+            memory: Arc::new(Mutex::new(Lru::new(capacity))),
+            store: None,
+            ttl,
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn from_env(client: C) -> Self {
+        let capacity = env::var("LLM_CACHE_CAPACITY")
+            .ok()
+            .and_then(|capacity| capacity.parse().ok())
+            .unwrap_or(DEFAULT_CAPACITY);
+        let ttl = env::var("LLM_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+        Self::new(client, capacity, ttl)
+    }
+
+    pub fn with_store(self, store: Arc<dyn CompletionCacheRepository>) -> Self {
+        Self {
+            store: Some(store),
+            ..self
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.memory.lock().unwrap().entries.len(),
+        }
+    }
+
+    async fn lookup(&self, key: &str) -> Option<String> {
+        if let Some(output) = self.memory.lock().unwrap().get(key, self.ttl) {
+            return Some(output);
+        }
+        let store = self.store.as_ref()?;
+        let created_after = Utc::now().naive_utc() - chrono::Duration::from_std(self.ttl).ok()?;
+        match store.find(key, created_after).await {
+            Ok(Some(output)) => {
+                self.memory.lock().unwrap().insert(key, &output);
+                Some(output)
+            },
+            Ok(None) => None,
+            Err(err) => {
+                warn!("failed to read completion cache: {:?}", err);
+                None
+            },
+        }
+    }
+
+    async fn remember(&self, key: &str, request: &LlmRequest, output: &str) {
+        self.memory.lock().unwrap().insert(key, output);
+        if let Some(store) = &self.store {
+            if let Err(err) = store.save(key, request.model(), output).await {
+                warn!("failed to write completion cache: {:?}", err);
+            }
+        }
+    }
+
+    fn key(&self, request: &LlmRequest) -> String {
+        let (route, model) = self.client.primary_route(request);
+        cache_key(&route, &model, request.prompt())
+    }
+
+    // 先頭の接続先が応答した場合だけ保存する
+    async fn complete_and_remember(
+        &self,
+        key: &str,
+        request: &LlmRequest,
+        on_progress: Option<&(dyn for<'s> Fn(&'s str) + Send + Sync)>,
+    ) -> Result<String, DomainError> {
+        let completion = self.client.complete_routed(request, on_progress).await?;
+        if completion.primary {
+            self.remember(key, request, &completion.output).await;
+        }
+        Ok(completion.output)
+    }
+
+    async fn cached(&self, request: &LlmRequest, key: &str) -> Option<String> {
+        if *request.cache_mode() == CacheMode::Bypass {
+            return None;
+        }
+        let cached = self.lookup(key).await;
+        match cached {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        cached
+    }
+}
+
+// 接続先の名前も含めるので、接続先の設定を入れ替えても別の接続先の応答は使わない
+fn cache_key(route: &str, model: &str, prompt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(route.as_bytes());
+    hasher.update([0]);
+    hasher.update(model.as_bytes());
+    hasher.update([0]);
+    hasher.update(prompt.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[async_trait]
+impl<C: RoutedLlmClient> LlmClient for CachedLlmClient<C> {
+    async fn complete(&self, request: &LlmRequest) -> Result<String, DomainError> {
+        let key = self.key(request);
+        if let Some(output) = self.cached(request, &key).await {
+            return Ok(output);
+        }
+        self.complete_and_remember(&key, request, None).await
+    }
+
+    async fn complete_stream(
+        &self,
+        request: &LlmRequest,
+        on_progress: &(dyn for<'s> Fn(&'s str) + Send + Sync),
+    ) -> Result<String, DomainError> {
+        let key = self.key(request);
+        if let Some(output) = self.cached(request, &key).await {
+            on_progress(&output);
+            return Ok(output);
+        }
+        self.complete_and_remember(&key, request, Some(on_progress))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::api::breaker::CircuitBreaker;
+    use crate::infrastructure::api::fallback::LlmRoute;
+    use crate::infrastructure::api::mock::{MockLlmClient, MockReply};
+    use crate::infrastructure::database::memory::InMemoryCompletionCacheRepository;
+
+    fn route(name: &str, client: &MockLlmClient) -> LlmRoute {
+        LlmRoute::new(
+            name.to_string(),
+            None,
+            Arc::new(client.clone()),
+            CircuitBreaker::new(10, Duration::from_secs(60)),
+        )
+    }
+
+    fn single(client: &MockLlmClient) -> FallbackLlmClient {
+        FallbackLlmClient::new(vec![route("primary", client)])
+    }
+
+    fn request(prompt: &str) -> LlmRequest {
+        LlmRequest::new("gpt-4-turbo".to_string(), prompt.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_repeated_request_is_served_from_cache() {
+        let mock = MockLlmClient::new().with_fixed_response("書き換えた．");
+        let client = CachedLlmClient::new(single(&mock), 10, DEFAULT_TTL);
+
+        client.complete(&request("同じ入力")).await.unwrap();
+        client.complete(&request("同じ入力")).await.unwrap();
+        // モデルが違えば別の応答として扱う
+        client
+            .complete(&request("同じ入力").with_model("gpt-3.5-turbo".to_string()))
+            .await
+            .unwrap();
+        // Bypass は読まずに呼び出し、結果で上書きする
+        client
+            .complete(&request("同じ入力").with_cache_mode(CacheMode::Bypass))
+            .await
+            .unwrap();
+
+        assert_eq!(mock.requests().len(), 3);
+        assert_eq!(
+            client.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                entries: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_not_cached_and_old_entries_expire() {
+        let mock = MockLlmClient::new().with_fixed_response("書き換えた．");
+        mock.push_reply(MockReply::Unavailable("timeout".to_string()));
+        let client = CachedLlmClient::new(single(&mock), 10, Duration::from_millis(20));
+
+        assert!(client.complete(&request("入力")).await.is_err());
+        client.complete(&request("入力")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        client.complete(&request("入力")).await.unwrap();
+
+        assert_eq!(mock.requests().len(), 3);
+        assert_eq!(client.stats().hits, 0);
+    }
+
+    #[tokio::test]
+    async fn test_least_recently_used_entry_is_evicted() {
+        let mock = MockLlmClient::new()
+            .with_responder(|request| MockReply::Text(format!("{}の書き換え", request.prompt())));
+        let client = CachedLlmClient::new(single(&mock), 2, DEFAULT_TTL);
+
+        for prompt in ["一", "二", "一", "三", "一", "二"] {
+            client.complete(&request(prompt)).await.unwrap();
+        }
+
+        // 「三」を入れたときに最も使われていない「二」が追い出される
+        let prompts: Vec<String> = mock
+            .requests()
+            .iter()
+            .map(|request| request.prompt().clone())
+            .collect();
+        assert_eq!(prompts, vec!["一", "二", "三", "二"]);
+    }
+
+    #[tokio::test]
+    async fn test_store_survives_a_new_client() {
+        let store = Arc::new(InMemoryCompletionCacheRepository::new());
+        let mock = MockLlmClient::new().with_fixed_response("書き換えた．");
+
+        let client = CachedLlmClient::new(single(&mock), 10, DEFAULT_TTL).with_store(store.clone());
+        client.complete(&request("入力")).await.unwrap();
+        // 再起動してメモリ上のキャッシュが消えても DB から引ける
+        let restarted = CachedLlmClient::new(single(&mock), 10, DEFAULT_TTL).with_store(store);
+        let output = restarted.complete(&request("入力")).await.unwrap();
+
+        assert_eq!(output, "書き換えた．");
+        assert_eq!(mock.requests().len(), 1);
+        assert_eq!(restarted.stats().hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_answers_are_not_cached() {
+        let primary = MockLlmClient::new().with_fixed_response("先頭で書いた．");
+        primary.push_reply(MockReply::Unavailable("connection refused".to_string()));
+        let fallback = MockLlmClient::new().with_fixed_response("代わりに書いた．");
+        let client = CachedLlmClient::new(
+            FallbackLlmClient::new(vec![route("primary", &primary), route("cheap", &fallback)]),
+            10,
+            DEFAULT_TTL,
+        );
+
+        assert_eq!(
+            client.complete(&request("入力")).await.unwrap(),
+            "代わりに書いた．"
+        );
+        // 代わりの応答は残さないので、先頭の接続先が戻れば呼び直す
+        assert_eq!(
+            client.complete(&request("入力")).await.unwrap(),
+            "先頭で書いた．"
+        );
+        assert_eq!(
+            client.complete(&request("入力")).await.unwrap(),
+            "先頭で書いた．"
+        );
+
+        assert_eq!(primary.requests().len(), 2);
+        assert_eq!(fallback.requests().len(), 1);
+        assert_eq!(client.stats().entries, 1);
+    }
+}
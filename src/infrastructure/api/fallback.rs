@@ -0,0 +1,305 @@
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use log::{info, warn};
+
+use super::breaker::{BreakerState, CircuitBreaker};
+use super::provider::LlmConfig;
+use super::retry::RetryPolicy;
+use crate::domain::error::DomainError;
+use crate::domain::service::llm::{LlmClient, LlmRequest};
+
+// 接続先と、その接続先で使うモデルの組
+// model が無ければリクエストのモデルをそのまま使う
+pub struct LlmRoute {
+    name: String,
+    model: Option<String>,
+    client: Arc<dyn LlmClient>,
+    breaker: CircuitBreaker,
+    requests: AtomicU64,
+    failures: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl LlmRoute {
+    pub fn new(
+        name: String,
+        model: Option<String>,
+        client: Arc<dyn LlmClient>,
+        breaker: CircuitBreaker,
+    ) -> Self {
+        Self {
+            name,
+            model,
+            client,
+            breaker,
+            requests: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    fn request(&self, request: &LlmRequest) -> LlmRequest {
+        match &self.model {
+            Some(model) => request.with_model(model.clone()),
+            None => request.clone(),
+        }
+    }
+}
+
+// 応答と、それを返した接続先
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutedCompletion {
+    pub output: String,
+    pub route: String,
+    // 先頭の接続先が応答したかどうか
+    pub primary: bool,
+}
+
+// 管理画面に出す接続先ごとの状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteStats {
+    pub name: String,
+    pub state: BreakerState,
+    pub requests: u64,
+    pub failures: u64,
+    pub rejected: u64,
+    pub active: bool,
+}
+
+// 接続先を先頭から順に試すクライアント
+// 通信に失敗した (InfrastructureError) か、サーキットブレーカーが止めている接続先は飛ばして次へ進む
+// 応答が返ってきた上での失敗 (Unexpected) は別の接続先でも変わらないので、そのまま返す
+#[derive(Clone)]
+pub struct FallbackLlmClient {
+    routes: Arc<Vec<LlmRoute>>,
+    // 最後に応答を返した接続先
+    active: Arc<Mutex<Option<usize>>>,
+}
+
+impl FallbackLlmClient {
+    pub fn new(routes: Vec<LlmRoute>) -> Self {
+        assert!(!routes.is_empty(), "at least one LLM route is required");
+        Self {
+            routes: Arc::new(routes),
+            active: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // 先頭は config の接続先で、LLM_FALLBACKS にカンマ区切りで並べた順に代わりを試す
+    // 書き方は LlmConfig::parse_fallback を参照。接続先を省略したものは先頭と同じ接続先の別のモデルとして扱う
+    // 別の接続先の API キーは LLM_FALLBACK_<何番目か (1から)>_API_KEY で渡す
+    pub fn from_env(config: &LlmConfig) -> Self {
+        let primary = config.client(RetryPolicy::from_env());
+        let mut routes = vec![LlmRoute::new(
+            "primary".to_string(),
+            None,
+            primary.clone(),
+            CircuitBreaker::from_env(),
+        )];
+        let fallbacks = env::var("LLM_FALLBACKS").unwrap_or_default();
+        for (index, fallback) in fallbacks
+            .split(',')
+            .map(str::trim)
+            .filter(|fallback| !fallback.is_empty())
+            .enumerate()
+        {
+            let (model, fallback_config) =
+                LlmConfig::parse_fallback(fallback).expect("LLM_FALLBACKS is invalid");
+            let client = match fallback_config {
+                Some(fallback_config) => {
+                    let fallback_config =
+                        match env::var(format!("LLM_FALLBACK_{}_API_KEY", index + 1)) {
+                            Ok(api_key) => fallback_config.with_api_key(api_key),
+                            Err(_) => fallback_config,
+                        };
+                    fallback_config.client(RetryPolicy::from_env())
+                },
+                None => primary.clone(),
+            };
+            routes.push(LlmRoute::new(
+                fallback.to_string(),
+                Some(model),
+                client,
+                CircuitBreaker::from_env(),
+            ));
+        }
+        Self::new(routes)
+    }
+
+    pub fn stats(&self) -> Vec<RouteStats> {
+        let active = *self.active.lock().unwrap();
+        self.routes
+            .iter()
+            .enumerate()
+            .map(|(index, route)| RouteStats {
+                name: route.name.clone(),
+                state: route.breaker.state(),
+                requests: route.requests.load(Ordering::Relaxed),
+                failures: route.failures.load(Ordering::Relaxed),
+                rejected: route.rejected.load(Ordering::Relaxed),
+                active: active == Some(index),
+            })
+            .collect()
+    }
+
+    // 先頭の接続先の名前とモデル。応答キャッシュのキーに使う
+    pub fn primary_route(&self, request: &LlmRequest) -> (String, String) {
+        let route = &self.routes[0];
+        (route.name.clone(), route.request(request).model().clone())
+    }
+
+    // 応答した接続先も返す。代わりの接続先の応答をキャッシュしないために使う
+    pub async fn complete_routed(
+        &self,
+        request: &LlmRequest,
+        on_progress: Option<&(dyn for<'s> Fn(&'s str) + Send + Sync)>,
+    ) -> Result<RoutedCompletion, DomainError> {
+        let mut last_error = None;
+        for (index, route) in self.routes.iter().enumerate() {
+            if !route.breaker.try_acquire() {
+                route.rejected.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            route.requests.fetch_add(1, Ordering::Relaxed);
+
+            let route_request = route.request(request);
+            let result = match on_progress {
+                Some(on_progress) => {
+                    route
+                        .client
+                        .complete_stream(&route_request, on_progress)
+                        .await
+                },
+                None => route.client.complete(&route_request).await,
+            };
+            match result {
+                Err(DomainError::InfrastructureError(err)) => {
+                    route.failures.fetch_add(1, Ordering::Relaxed);
+                    if route.breaker.record_failure() {
+                        warn!("LLM route {} is unavailable, circuit opened", route.name);
+                    }
+                    warn!("LLM route {} failed: {}", route.name, err);
+                    last_error = Some(err);
+                },
+                result => {
+                    route.breaker.record_success();
+                    let previous = self.active.lock().unwrap().replace(index);
+                    if previous != Some(index) {
+                        info!("LLM route switched to {}", route.name);
+                    }
+                    return result.map(|output| RoutedCompletion {
+                        output,
+                        route: route.name.clone(),
+                        primary: index == 0,
+                    });
+                },
+            }
+        }
+
+        Err(DomainError::InfrastructureError(last_error.unwrap_or_else(
+            || anyhow::anyhow!("all LLM routes are unavailable"),
+        )))
+    }
+}
+
+#[async_trait]
+impl LlmClient for FallbackLlmClient {
+    async fn complete(&self, request: &LlmRequest) -> Result<String, DomainError> {
+        Ok(self.complete_routed(request, None).await?.output)
+    }
+
+    // 途中で失敗して次の接続先に移っても、on_progress には全文を渡すので表示は書き直される
+    async fn complete_stream(
+        &self,
+        request: &LlmRequest,
+        on_progress: &(dyn for<'s> Fn(&'s str) + Send + Sync),
+    ) -> Result<String, DomainError> {
+        Ok(self
+            .complete_routed(request, Some(on_progress))
+            .await?
+            .output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::infrastructure::api::mock::{MockLlmClient, MockReply};
+
+    fn route(name: &str, model: Option<&str>, client: &MockLlmClient) -> LlmRoute {
+        LlmRoute::new(
+            name.to_string(),
+            model.map(str::to_string),
+            Arc::new(client.clone()),
+            CircuitBreaker::new(2, Duration::from_secs(60)),
+        )
+    }
+
+    fn request() -> LlmRequest {
+        LlmRequest::new("gpt-4-turbo".to_string(), "書き換えて".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_and_stops_calling_unavailable_route() {
+        let primary = MockLlmClient::new()
+            .with_responder(|_| MockReply::Unavailable("connection refused".to_string()));
+        let fallback = MockLlmClient::new().with_fixed_response("代わりに書いた．");
+        let client = FallbackLlmClient::new(vec![
+            route("primary", None, &primary),
+            route("cheap", Some("gpt-3.5-turbo"), &fallback),
+        ]);
+
+        for _ in 0..3 {
+            assert_eq!(
+                client.complete(&request()).await.unwrap(),
+                "代わりに書いた．"
+            );
+        }
+
+        // 2回失敗した後は primary を呼ばない
+        assert_eq!(primary.requests().len(), 2);
+        assert_eq!(fallback.requests()[0].model(), "gpt-3.5-turbo");
+        let stats = client.stats();
+        assert_eq!(stats[0].state, BreakerState::Open);
+        assert_eq!(stats[0].rejected, 1);
+        assert!(stats[1].active);
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_response_is_not_retried_elsewhere() {
+        let primary = MockLlmClient::new()
+            .with_responder(|_| MockReply::Malformed("missing content".to_string()));
+        let fallback = MockLlmClient::new().with_fixed_response("代わりに書いた．");
+        let client = FallbackLlmClient::new(vec![
+            route("primary", None, &primary),
+            route("cheap", None, &fallback),
+        ]);
+
+        let result = client.complete(&request()).await;
+
+        assert!(matches!(result, Err(DomainError::Unexpected(_))));
+        assert!(fallback.requests().is_empty());
+        assert_eq!(client.stats()[0].state, BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_fails_fast_when_every_route_is_open() {
+        let primary =
+            MockLlmClient::new().with_responder(|_| MockReply::Unavailable("503".to_string()));
+        let client = FallbackLlmClient::new(vec![route("primary", None, &primary)]);
+
+        for _ in 0..3 {
+            assert!(matches!(
+                client.complete(&request()).await,
+                Err(DomainError::InfrastructureError(_))
+            ));
+        }
+
+        assert_eq!(primary.requests().len(), 2);
+    }
+}
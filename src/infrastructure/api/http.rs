@@ -0,0 +1,195 @@
+use std::future::Future;
+
+use log::warn;
+use reqwest::{Client, RequestBuilder, Response};
+
+use super::retry::{is_retryable_error, is_retryable_status, retry_after, RetryPolicy};
+use crate::domain::error::DomainError;
+
+// LLM の API を呼び出す HTTP クライアント。接続先ごとのリクエストの組み立てと応答の読み方は呼び出し側が持つ
+#[derive(Clone)]
+pub struct HttpTransport {
+    client: Client,
+    policy: RetryPolicy,
+}
+
+impl HttpTransport {
+    pub fn new(policy: RetryPolicy) -> Self {
+        let client = Client::builder()
+            .connect_timeout(policy.connect_timeout)
+            .read_timeout(policy.read_timeout)
+            .build()
+            .expect("failed to build HTTP client");
+        Self { client, policy }
+    }
+
+    // 混雑やサーバー側の障害、接続の失敗は待ってから送り直す。build は送るたびに呼ぶ
+    // ストリーミングは応答が始まった後には送り直さないので、ここでは応答のヘッダーまでを扱う
+    pub async fn post(
+        &self,
+        build: impl Fn(&Client) -> RequestBuilder,
+    ) -> Result<Response, DomainError> {
+        let mut attempt = 0;
+        loop {
+            let result = build(&self.client).send().await;
+            let can_retry = attempt < self.policy.max_retries;
+            let (retry_after, cause) = match result {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) if is_retryable_status(response.status()) && can_retry => {
+                    (retry_after(&response), response.status().to_string())
+                },
+                Ok(response) if is_retryable_status(response.status()) => {
+                    return Err(DomainError::InfrastructureError(anyhow::anyhow!(
+                        "completion failed with status {}",
+                        response.status()
+                    )))
+                },
+                Ok(response) => {
+                    return Err(DomainError::Unexpected(format!(
+                        "completion failed with status {}",
+                        response.status()
+                    )))
+                },
+                Err(err) if is_retryable_error(&err) && can_retry => (None, err.to_string()),
+                Err(err) => return Err(DomainError::InfrastructureError(anyhow::anyhow!(err))),
+            };
+
+            let delay = self.policy.backoff(attempt, retry_after);
+            warn!(
+                "completion request failed ({}), retrying in {:?}",
+                cause, delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    // 再試行とストリーミングの受信も含めて、1回の呼び出しを deadline までで打ち切る
+    pub async fn within_deadline<T>(
+        &self,
+        call: impl Future<Output = Result<T, DomainError>>,
+    ) -> Result<T, DomainError> {
+        tokio::time::timeout(self.policy.deadline, call)
+            .await
+            .map_err(|_| {
+                DomainError::InfrastructureError(anyhow::anyhow!(
+                    "completion did not finish within {:?}",
+                    self.policy.deadline
+                ))
+            })?
+    }
+}
+
+// ストリーミングの応答の区切り方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    // SSE。空行で区切られたイベントの data 行を取り出す
+    Sse,
+    // 1行に1つの JSON
+    Lines,
+}
+
+// チャンクの境界はイベントや UTF-8 の文字の途中にもなりうるので、区切りが届くまでバイト列のままためておく
+pub struct FrameBuffer {
+    framing: Framing,
+    buffer: Vec<u8>,
+}
+
+impl FrameBuffer {
+    pub fn new(framing: Framing) -> Self {
+        Self {
+            framing,
+            buffer: vec![],
+        }
+    }
+
+    // 区切りまで届いた分の中身を返す
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let separator: &[u8] = match self.framing {
+            Framing::Sse => b"\n\n",
+            Framing::Lines => b"\n",
+        };
+        let mut frames = vec![];
+        while let Some(end) = self
+            .buffer
+            .windows(separator.len())
+            .position(|window| window == separator)
+        {
+            let frame: Vec<u8> = self.buffer.drain(..end + separator.len()).collect();
+            let frame = String::from_utf8_lossy(&frame);
+            match self.framing {
+                Framing::Sse => {
+                    for line in frame.lines() {
+                        if let Some(payload) = line.trim_end_matches('\r').strip_prefix("data:") {
+                            frames.push(payload.trim_start().to_string());
+                        }
+                    }
+                },
+                Framing::Lines if !frame.trim().is_empty() => frames.push(frame.trim().to_string()),
+                Framing::Lines => {},
+            }
+        }
+        frames
+    }
+}
+
+pub enum Delta {
+    Content(String),
+    // role だけのチャンクなど本文を含まないもの
+    Empty,
+    Done,
+}
+
+// ストリーミングの応答を受け取り、parse で読んだ本文を on_progress に渡しながらつなげる
+pub async fn receive_deltas(
+    mut response: Response,
+    framing: Framing,
+    parse: impl Fn(&str) -> Result<Delta, DomainError>,
+    on_progress: &(dyn for<'s> Fn(&'s str) + Send + Sync),
+) -> Result<String, DomainError> {
+    let mut frames = FrameBuffer::new(framing);
+    let mut text = String::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?
+    {
+        for frame in frames.push(&chunk) {
+            match parse(&frame)? {
+                Delta::Content(content) => {
+                    text.push_str(&content);
+                    on_progress(&text);
+                },
+                Delta::Empty => {},
+                Delta::Done => return Ok(text),
+            }
+        }
+    }
+
+    // 終わりの合図が届く前に切れた場合
+    Err(DomainError::InfrastructureError(anyhow::anyhow!(
+        "completion stream ended unexpectedly"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_buffer_waits_for_boundary() {
+        let mut events = FrameBuffer::new(Framing::Sse);
+        let stream = "event: delta\ndata: 書き\n\ndata: [DONE]\n\n";
+        let bytes = stream.as_bytes();
+        // マルチバイト文字の途中で区切る
+        let split = stream.find("書").unwrap() + 1;
+
+        assert!(events.push(&bytes[..split]).is_empty());
+        assert_eq!(events.push(&bytes[split..]), vec!["書き", "[DONE]"]);
+
+        let mut lines = FrameBuffer::new(Framing::Lines);
+        assert_eq!(lines.push(b"{\"a\":1}\n\n{\"b\""), vec!["{\"a\":1}"]);
+        assert_eq!(lines.push(b":2}\n"), vec!["{\"b\":2}"]);
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::domain::error::DomainError;
+use crate::domain::service::llm::{LlmClient, LlmRequest};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockReply {
+    Text(String),
+    // 通信失敗 (InfrastructureError) を再現する
+    Unavailable(String),
+    // 応答形式の不備 (Unexpected) を再現する
+    Malformed(String),
+}
+
+type Responder = dyn Fn(&LlmRequest) -> MockReply + Send + Sync;
+
+struct MockState {
+    script: VecDeque<MockReply>,
+    responder: Arc<Responder>,
+    latency: Duration,
+    requests: Vec<LlmRequest>,
+}
+
+// テスト用の決定的なLLMクライアント
+// script に積んだ応答を順に返し、尽きたら responder の結果を返す
+#[derive(Clone)]
+pub struct MockLlmClient {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockLlmClient {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                script: VecDeque::new(),
+                responder: Arc::new(|_| MockReply::Text("mocked".to_string())),
+                latency: Duration::ZERO,
+                requests: vec![],
+            })),
+        }
+    }
+
+    pub fn with_responder<F>(self, responder: F) -> Self
+    where
+        F: Fn(&LlmRequest) -> MockReply + Send + Sync + 'static,
+    {
+        self.state.lock().unwrap().responder = Arc::new(responder);
+        self
+    }
+
+    pub fn with_fixed_response(self, text: &str) -> Self {
+        let text = text.to_string();
+        self.with_responder(move |_| MockReply::Text(text.clone()))
+    }
+
+    pub fn with_latency(self, latency: Duration) -> Self {
+        self.state.lock().unwrap().latency = latency;
+        self
+    }
+
+    pub fn push_reply(&self, reply: MockReply) {
+        self.state.lock().unwrap().script.push_back(reply);
+    }
+
+    pub fn requests(&self) -> Vec<LlmRequest> { self.state.lock().unwrap().requests.clone() }
+}
+
+#[async_trait]
+impl LlmClient for MockLlmClient {
+    async fn complete(&self, request: &LlmRequest) -> Result<String, DomainError> {
+        let (reply, latency) = {
+            let mut state = self.state.lock().unwrap();
+            state.requests.push(request.clone());
+            let reply = match state.script.pop_front() {
+                Some(reply) => reply,
+                None => (state.responder)(request),
+            };
+            (reply, state.latency)
+        };
+
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+
+        match reply {
+            MockReply::Text(text) => Ok(text),
+            MockReply::Unavailable(message) => {
+                Err(DomainError::InfrastructureError(anyhow::anyhow!(message)))
+            },
+            MockReply::Malformed(message) => Err(DomainError::Unexpected(message)),
+        }
+    }
+}
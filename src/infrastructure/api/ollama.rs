@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+
+use super::http::{receive_deltas, Delta, Framing, HttpTransport};
+use super::retry::RetryPolicy;
+use crate::domain::error::DomainError;
+use crate::domain::service::llm::{LlmClient, LlmRequest};
+
+pub const OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+// Ollama の /api/chat を呼ぶ。API キーは使わない
+#[derive(Clone)]
+pub struct OllamaClient {
+    http: HttpTransport,
+    api_url: String,
+}
+
+impl OllamaClient {
+    pub fn with_policy(base_url: String, policy: RetryPolicy) -> Self {
+        Self {
+            http: HttpTransport::new(policy),
+            api_url: format!("{}/api/chat", base_url.trim_end_matches('/')),
+        }
+    }
+
+    async fn post(
+        &self,
+        request: &LlmRequest,
+        stream: bool,
+    ) -> Result<reqwest::Response, DomainError> {
+        let body = serde_json::json!({
+            "model": request.model(),
+            "messages": [{"role": "user", "content": request.prompt()}],
+            "stream": stream
+        });
+        self.http
+            .post(|client| client.post(&self.api_url).json(&body))
+            .await
+    }
+}
+
+#[async_trait]
+impl LlmClient for OllamaClient {
+    async fn complete(&self, request: &LlmRequest) -> Result<String, DomainError> {
+        self.http
+            .within_deadline(async {
+                let response = self.post(request, false).await?;
+                let res_json = response
+                    .json::<serde_json::Value>()
+                    .await
+                    .map_err(|err| DomainError::Unexpected(err.to_string()))?;
+
+                res_json["message"]["content"]
+                    .as_str()
+                    .map(|content| content.to_string())
+                    .ok_or_else(|| {
+                        DomainError::Unexpected("missing content in completion".to_string())
+                    })
+            })
+            .await
+    }
+
+    async fn complete_stream(
+        &self,
+        request: &LlmRequest,
+        on_progress: &(dyn for<'s> Fn(&'s str) + Send + Sync),
+    ) -> Result<String, DomainError> {
+        self.http
+            .within_deadline(async {
+                let response = self.post(request, true).await?;
+                receive_deltas(response, Framing::Lines, line_content, on_progress).await
+            })
+            .await
+    }
+}
+
+// ストリーミングでは1行に1つの JSON が届き、最後の行は done が true になる
+// 途中で失敗すると error だけの行が届く
+fn line_content(line: &str) -> Result<Delta, DomainError> {
+    let chunk: serde_json::Value =
+        serde_json::from_str(line).map_err(|err| DomainError::Unexpected(err.to_string()))?;
+    if let Some(error) = chunk["error"].as_str() {
+        return Err(DomainError::InfrastructureError(anyhow::anyhow!(
+            "completion stream failed: {}",
+            error
+        )));
+    }
+    if chunk["done"].as_bool() == Some(true) {
+        return Ok(Delta::Done);
+    }
+    Ok(match chunk["message"]["content"].as_str() {
+        Some(content) if !content.is_empty() => Delta::Content(content.to_string()),
+        _ => Delta::Empty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::stub;
+    use super::*;
+
+    #[test]
+    fn test_reads_stream_lines() {
+        let line = serde_json::json!({
+            "model": "llama3",
+            "message": {"role": "assistant", "content": "書き"},
+            "done": false
+        })
+        .to_string();
+        assert!(matches!(line_content(&line).unwrap(), Delta::Content(text) if text == "書き"));
+        assert!(matches!(
+            line_content(r#"{"message": {"role": "assistant", "content": ""}, "done": true}"#)
+                .unwrap(),
+            Delta::Done
+        ));
+        assert!(matches!(
+            line_content(r#"{"error": "model not found"}"#),
+            Err(DomainError::InfrastructureError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sends_chat_request_without_api_key() {
+        let (url, captured) = stub::capture(serde_json::json!({
+            "message": {"role": "assistant", "content": "書き換えた．"},
+            "done": true
+        }))
+        .await;
+        let client = OllamaClient::with_policy(format!("{}/", url), RetryPolicy::default());
+        let request = LlmRequest::new("llama3:8b".to_string(), "書き換えて".to_string());
+
+        assert_eq!(client.complete(&request).await.unwrap(), "書き換えた．");
+        let captured = captured.await.unwrap();
+        assert_eq!(captured.path, "/api/chat");
+        assert!(!captured.headers.contains_key("authorization"));
+        assert!(!captured.headers.contains_key("x-api-key"));
+        assert_eq!(captured.body["model"], "llama3:8b");
+        assert_eq!(captured.body["messages"][0]["content"], "書き換えて");
+        assert_eq!(captured.body["stream"], false);
+    }
+}
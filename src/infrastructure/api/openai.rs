@@ -1,29 +1,218 @@
-use std::env;
+use async_trait::async_trait;
 
-use reqwest::Client;
+use super::http::{receive_deltas, Delta, Framing, HttpTransport};
+use super::retry::RetryPolicy;
+use crate::domain::error::DomainError;
+use crate::domain::service::llm::{LlmClient, LlmRequest};
 
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+// OpenAI の Chat Completions 形式の API を呼ぶ
+// base_url を変えれば llama.cpp や vLLM などの互換サーバーにも使える
 #[derive(Clone)]
 pub struct OpenAiClient {
-    client: Client,
+    http: HttpTransport,
+    api_url: String,
     api_key: String,
 }
 
 impl OpenAiClient {
-    pub fn new() -> Self {
-        let api_key = env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
+    pub fn with_policy(base_url: String, api_key: String, policy: RetryPolicy) -> Self {
         Self {
-            client: Client::new(),
+            http: HttpTransport::new(policy),
+            api_url: format!("{}/chat/completions", base_url.trim_end_matches('/')),
             api_key,
         }
     }
 
-    pub async fn post(&self, body: &serde_json::Value) -> reqwest::Result<reqwest::Response> {
-        let api_url = "https://api.openai.com/v1/chat/completions";
-        self.client
-            .post(api_url)
-            .bearer_auth(&self.api_key)
-            .json(body)
-            .send()
+    // 互換サーバーは API キーを要らないことが多いので、空なら Authorization を付けない
+    async fn post(&self, body: &serde_json::Value) -> Result<reqwest::Response, DomainError> {
+        self.http
+            .post(|client| {
+                let request = client.post(&self.api_url).json(body);
+                if self.api_key.is_empty() {
+                    request
+                } else {
+                    request.bearer_auth(&self.api_key)
+                }
+            })
             .await
     }
 }
+
+#[async_trait]
+impl LlmClient for OpenAiClient {
+    async fn complete(&self, request: &LlmRequest) -> Result<String, DomainError> {
+        self.http
+            .within_deadline(async {
+                let response = self
+                    .post(&serde_json::json!({
+                        "model": request.model(),
+                        "messages": [{"role": "user", "content": request.prompt()}]
+                    }))
+                    .await?;
+
+                let res_json = response
+                    .json::<serde_json::Value>()
+                    .await
+                    .map_err(|err| DomainError::Unexpected(err.to_string()))?;
+
+                res_json["choices"][0]["message"]["content"]
+                    .as_str()
+                    .map(|content| content.to_string())
+                    .ok_or_else(|| {
+                        DomainError::Unexpected("missing content in completion".to_string())
+                    })
+            })
+            .await
+    }
+
+    async fn complete_stream(
+        &self,
+        request: &LlmRequest,
+        on_progress: &(dyn for<'s> Fn(&'s str) + Send + Sync),
+    ) -> Result<String, DomainError> {
+        self.http
+            .within_deadline(async {
+                let response = self
+                    .post(&serde_json::json!({
+                        "model": request.model(),
+                        "messages": [{"role": "user", "content": request.prompt()}],
+                        "stream": true
+                    }))
+                    .await?;
+                receive_deltas(response, Framing::Sse, delta_content, on_progress).await
+            })
+            .await
+    }
+}
+
+fn delta_content(data: &str) -> Result<Delta, DomainError> {
+    if data == "[DONE]" {
+        return Ok(Delta::Done);
+    }
+    let chunk: serde_json::Value =
+        serde_json::from_str(data).map_err(|err| DomainError::Unexpected(err.to_string()))?;
+    Ok(match chunk["choices"][0]["delta"]["content"].as_str() {
+        Some(content) => Delta::Content(content.to_string()),
+        None => Delta::Empty,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    // 決められた順に応答を返すだけの HTTP サーバー (最後の応答はその後も繰り返す)
+    // 戻り値はサーバーの URL と受け取ったリクエストの数
+    async fn serve(replies: Vec<(u16, &'static str, Duration)>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let hit = counter.fetch_add(1, Ordering::SeqCst);
+                let (status, headers, delay) = replies[hit.min(replies.len() - 1)];
+                tokio::spawn(async move {
+                    let mut buf = vec![0; 8192];
+                    let _ = socket.read(&mut buf).await;
+                    tokio::time::sleep(delay).await;
+                    let body = serde_json::json!({
+                        "choices": [{"message": {"content": "書き換えた．"}}]
+                    })
+                    .to_string();
+                    let response = format!(
+                        "HTTP/1.1 {} X\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        headers,
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        (url, hits)
+    }
+
+    fn client(url: String, policy: RetryPolicy) -> OpenAiClient {
+        OpenAiClient::with_policy(url, "test".to_string(), policy)
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn request() -> LlmRequest {
+        LlmRequest::new("gpt-4-turbo".to_string(), "書き換えて".to_string())
+    }
+
+    #[test]
+    fn test_delta_without_content() {
+        let data = serde_json::json!({"choices": [{"delta": {"role": "assistant"}}]}).to_string();
+
+        assert!(matches!(delta_content(&data).unwrap(), Delta::Empty));
+        assert!(delta_content("not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limit_and_server_errors() {
+        let (url, hits) = serve(vec![
+            (429, "Retry-After: 0\r\n", Duration::ZERO),
+            (503, "", Duration::ZERO),
+            (200, "", Duration::ZERO),
+        ])
+        .await;
+
+        let content = client(url, fast_policy())
+            .complete(&request())
+            .await
+            .unwrap();
+
+        assert_eq!(content, "書き換えた．");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries_and_on_client_errors() {
+        let (url, hits) = serve(vec![(500, "", Duration::ZERO)]).await;
+        let result = client(url, fast_policy()).complete(&request()).await;
+        assert!(matches!(result, Err(DomainError::InfrastructureError(_))));
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+        // 400 は送り直しても結果が変わらない
+        let (url, hits) = serve(vec![(400, "", Duration::ZERO)]).await;
+        let result = client(url, fast_policy()).complete(&request()).await;
+        assert!(matches!(result, Err(DomainError::Unexpected(_))));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_slow_responses_are_cut_off_by_deadline() {
+        let (url, _) = serve(vec![(200, "", Duration::from_secs(5))]).await;
+        let policy = RetryPolicy {
+            read_timeout: Duration::from_secs(5),
+            deadline: Duration::from_millis(200),
+            ..fast_policy()
+        };
+
+        let started = std::time::Instant::now();
+        let result = client(url, policy).complete(&request()).await;
+
+        assert!(matches!(result, Err(DomainError::InfrastructureError(_))));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
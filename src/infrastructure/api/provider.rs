@@ -0,0 +1,173 @@
+use std::env;
+use std::sync::Arc;
+
+use super::anthropic::{AnthropicClient, ANTHROPIC_BASE_URL};
+use super::ollama::{OllamaClient, OLLAMA_BASE_URL};
+use super::openai::{OpenAiClient, OPENAI_BASE_URL};
+use super::retry::RetryPolicy;
+use crate::domain::error::DomainError;
+use crate::domain::service::llm::LlmClient;
+
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4-turbo";
+
+// 接続先の API の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmProvider {
+    // OpenAI と、llama.cpp や vLLM などの OpenAI 互換サーバー
+    OpenAi,
+    Anthropic,
+    Ollama,
+}
+
+impl LlmProvider {
+    pub fn parse(value: &str) -> Result<Self, DomainError> {
+        match value {
+            "openai" => Ok(LlmProvider::OpenAi),
+            "anthropic" => Ok(LlmProvider::Anthropic),
+            "ollama" => Ok(LlmProvider::Ollama),
+            _ => Err(DomainError::Validation(format!(
+                "unknown LLM provider: {}",
+                value
+            ))),
+        }
+    }
+
+    pub fn default_base_url(&self) -> &'static str {
+        match self {
+            LlmProvider::OpenAi => OPENAI_BASE_URL,
+            LlmProvider::Anthropic => ANTHROPIC_BASE_URL,
+            LlmProvider::Ollama => OLLAMA_BASE_URL,
+        }
+    }
+
+    // 形式ごとの API キーの環境変数。LLM_API_KEY が無い場合に使う
+    fn api_key_var(&self) -> Option<&'static str> {
+        match self {
+            LlmProvider::OpenAi => Some("OPENAI_API_KEY"),
+            LlmProvider::Anthropic => Some("ANTHROPIC_API_KEY"),
+            LlmProvider::Ollama => None,
+        }
+    }
+}
+
+// 書き換えに使う接続先とモデル
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmConfig {
+    pub provider: LlmProvider,
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+}
+
+impl LlmConfig {
+    // LLM_PROVIDER (省略時は openai) と LLM_BASE_URL、LLM_API_KEY、LLM_MODEL から読む
+    // OpenAI 以外はモデルの既定値が無いので LLM_MODEL が必須
+    pub fn from_env() -> Self {
+        let provider = env::var("LLM_PROVIDER")
+            .map(|provider| LlmProvider::parse(&provider).expect("LLM_PROVIDER is invalid"))
+            .unwrap_or(LlmProvider::OpenAi);
+        let model = match env::var("LLM_MODEL") {
+            Ok(model) => model,
+            Err(_) if provider == LlmProvider::OpenAi => DEFAULT_OPENAI_MODEL.to_string(),
+            Err(_) => panic!("LLM_MODEL must be set"),
+        };
+        let config = Self::new(provider, env::var("LLM_BASE_URL").ok(), model);
+        match env::var("LLM_API_KEY") {
+            Ok(api_key) => config.with_api_key(api_key),
+            Err(_) => config,
+        }
+    }
+
+    // 既定の接続先を使う場合だけ、API キーを形式ごとの環境変数から読む
+    // 別のサーバーを指定した場合に OpenAI などのキーを送ってしまわないよう、キーは空にしておく
+    pub fn new(provider: LlmProvider, base_url: Option<String>, model: String) -> Self {
+        let base_url = base_url.unwrap_or_else(|| provider.default_base_url().to_string());
+        let api_key = match provider.api_key_var() {
+            Some(name) if base_url == provider.default_base_url() => {
+                env::var(name).unwrap_or_default()
+            },
+            _ => String::new(),
+        };
+        Self {
+            provider,
+            base_url,
+            api_key,
+            model,
+        }
+    }
+
+    pub fn with_api_key(self, api_key: String) -> Self { Self { api_key, ..self } }
+
+    // LLM_FALLBACKS の1件を読む。`モデル@形式:URL` の形で、`@` 以降は省略できる
+    // `@` の後が形式の名前でなければ OpenAI 互換のサーバーの URL として扱う
+    // 接続先を省略した場合は primary と同じ接続先の別のモデルなので None を返す
+    pub fn parse_fallback(entry: &str) -> Result<(String, Option<Self>), DomainError> {
+        let (model, target) = match entry.split_once('@') {
+            Some((model, target)) => (model, target),
+            None => return Ok((entry.to_string(), None)),
+        };
+        let (provider, base_url) = match LlmProvider::parse(target) {
+            Ok(provider) => (provider, None),
+            Err(_) => match target.split_once(':') {
+                Some((name, base_url)) if LlmProvider::parse(name).is_ok() => {
+                    (LlmProvider::parse(name)?, Some(base_url.to_string()))
+                },
+                _ => (LlmProvider::OpenAi, Some(target.to_string())),
+            },
+        };
+        Ok((
+            model.to_string(),
+            Some(Self::new(provider, base_url, model.to_string())),
+        ))
+    }
+
+    pub fn client(&self, policy: RetryPolicy) -> Arc<dyn LlmClient> {
+        match self.provider {
+            LlmProvider::OpenAi => Arc::new(OpenAiClient::with_policy(
+                self.base_url.clone(),
+                self.api_key.clone(),
+                policy,
+            )),
+            LlmProvider::Anthropic => Arc::new(AnthropicClient::with_policy(
+                self.base_url.clone(),
+                self.api_key.clone(),
+                policy,
+            )),
+            LlmProvider::Ollama => {
+                Arc::new(OllamaClient::with_policy(self.base_url.clone(), policy))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fallback() {
+        assert_eq!(
+            LlmConfig::parse_fallback("gpt-3.5-turbo").unwrap(),
+            ("gpt-3.5-turbo".to_string(), None)
+        );
+
+        let (_, config) = LlmConfig::parse_fallback("llama3@http://localhost:8080/v1").unwrap();
+        let config = config.unwrap();
+        assert_eq!(config.provider, LlmProvider::OpenAi);
+        assert_eq!(config.base_url, "http://localhost:8080/v1");
+        // 別のサーバーには OPENAI_API_KEY を送らない
+        assert_eq!(config.api_key, "");
+
+        // Ollama のモデル名には `:` が入る
+        let (model, config) = LlmConfig::parse_fallback("llama3:8b@ollama").unwrap();
+        assert_eq!(model, "llama3:8b");
+        assert_eq!(config.unwrap().base_url, OLLAMA_BASE_URL);
+
+        let (_, config) =
+            LlmConfig::parse_fallback("claude-3-5-haiku-latest@anthropic:http://proxy:8080")
+                .unwrap();
+        let config = config.unwrap();
+        assert_eq!(config.provider, LlmProvider::Anthropic);
+        assert_eq!(config.base_url, "http://proxy:8080");
+    }
+}
@@ -0,0 +1,115 @@
+use std::env;
+use std::time::Duration;
+
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use uuid::Uuid;
+
+// 待ち時間は base_delay から倍々に伸ばし、max_delay で頭打ちにする
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(8);
+
+// LLM の API 呼び出しのタイムアウトと再試行の設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub connect_timeout: Duration,
+    // 応答の読み込みが途切れてから待つ時間。ストリーミング中もトークンの間隔に対して効く
+    pub read_timeout: Duration,
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // 再試行も含めた1回の呼び出し全体の期限
+    pub deadline: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(30),
+            max_retries: 3,
+            base_delay: BASE_DELAY,
+            max_delay: MAX_DELAY,
+            deadline: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // 設定されていない値は既定値を使う
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            connect_timeout: seconds_from_env("LLM_CONNECT_TIMEOUT_SECS")
+                .unwrap_or(default.connect_timeout),
+            read_timeout: seconds_from_env("LLM_READ_TIMEOUT_SECS").unwrap_or(default.read_timeout),
+            max_retries: env::var("LLM_MAX_RETRIES")
+                .ok()
+                .and_then(|retries| retries.parse().ok())
+                .unwrap_or(default.max_retries),
+            deadline: seconds_from_env("LLM_DEADLINE_SECS").unwrap_or(default.deadline),
+            ..default
+        }
+    }
+
+    // attempt 回目 (0 始まり) の失敗の後に待つ時間
+    // Retry-After があればそれに従い、なければ指数的に伸ばした時間の半分から全部の間でばらつかせる
+    pub fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after;
+        }
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let jitter = (Uuid::new_v4().as_u128() % 1_000) as u32;
+        ceiling / 2 + ceiling / 2 * jitter / 1_000
+    }
+}
+
+fn seconds_from_env(name: &str) -> Option<Duration> {
+    env::var(name)
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .map(Duration::from_secs)
+}
+
+// 混雑 (429) とサーバー側の障害 (5xx) は待てば通りうる
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+pub fn is_retryable_error(err: &reqwest::Error) -> bool { err.is_timeout() || err.is_connect() }
+
+// 秒数での指定だけを扱う。日付での指定は無視して通常の待ち時間にする
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_within_jitter_bounds() {
+        let policy = RetryPolicy::default();
+
+        for attempt in 0..6 {
+            let ceiling = (BASE_DELAY * 2u32.pow(attempt)).min(MAX_DELAY);
+            let delay = policy.backoff(attempt, None);
+            assert!(delay >= ceiling / 2 && delay <= ceiling, "{:?}", delay);
+        }
+        assert_eq!(
+            policy.backoff(0, Some(Duration::from_secs(3))),
+            Duration::from_secs(3)
+        );
+    }
+}
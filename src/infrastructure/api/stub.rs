@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+// スタブのサーバーが受け取ったリクエスト。ヘッダー名は小文字にそろえる
+pub struct CapturedRequest {
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: serde_json::Value,
+}
+
+// 1件だけリクエストを受け取り、body を JSON の応答として返す HTTP サーバー
+// 戻り値はサーバーの URL (パスなし) と受け取ったリクエスト
+pub async fn capture(body: serde_json::Value) -> (String, oneshot::Receiver<CapturedRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = oneshot::channel();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = vec![];
+        let mut chunk = vec![0; 8192];
+        // ヘッダーの終わりまで読んでから、Content-Length の分だけ本文を読む
+        let header_end = loop {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            if let Some(end) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end;
+            }
+        };
+        let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+        let mut lines = head.lines();
+        let path = lines
+            .next()
+            .and_then(|line| line.split(' ').nth(1))
+            .unwrap_or_default()
+            .to_string();
+        let headers: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+            .collect();
+        let length: usize = headers
+            .get("content-length")
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0);
+        while buf.len() < header_end + 4 + length {
+            let n = socket.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        let request_body =
+            serde_json::from_slice(&buf[header_end + 4..header_end + 4 + length]).unwrap();
+
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = socket.write_all(response.as_bytes()).await;
+        let _ = sender.send(CapturedRequest {
+            path,
+            headers,
+            body: request_body,
+        });
+    });
+    (url, receiver)
+}
@@ -1,3 +1,13 @@
+pub mod completion;
+pub mod diary;
+pub mod display;
 pub mod init;
+pub mod job;
+#[cfg(test)]
+pub mod memory;
 pub mod models;
+pub mod persona;
+pub mod revision;
+pub mod revocation;
+pub mod token;
 pub mod user;
@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::r2d2::ConnectionManager;
+use r2d2::PooledConnection;
+
+use crate::domain::error::DomainError;
+use crate::domain::repository::completion::CompletionCacheRepository;
+use crate::infrastructure::database::init::DbPool;
+use crate::infrastructure::database::models::NewCompletionCache;
+use crate::schema::completion_cache::{self as cache_schema};
+
+#[derive(Clone)]
+pub struct CompletionCacheRepositoryImpl {
+    pub pool: DbPool,
+}
+
+impl CompletionCacheRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self { Self { pool } }
+
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DomainError> {
+        self.pool
+            .get()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+}
+
+#[async_trait]
+impl CompletionCacheRepository for CompletionCacheRepositoryImpl {
+    async fn find(
+        &self,
+        cache_key: &str,
+        created_after: NaiveDateTime,
+    ) -> Result<Option<String>, DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalCompletionCacheRepository::find(cache_key, created_after, &mut connection).await
+    }
+
+    async fn save(&self, cache_key: &str, model: &str, output: &str) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalCompletionCacheRepository::save(cache_key, model, output, &mut connection).await
+    }
+}
+
+pub struct InternalCompletionCacheRepository;
+
+impl InternalCompletionCacheRepository {
+    pub async fn find(
+        cache_key: &str,
+        created_after: NaiveDateTime,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<String>, DomainError> {
+        cache_schema::table
+            .filter(cache_schema::cache_key.eq(cache_key))
+            .filter(cache_schema::created_at.ge(created_after))
+            .select(cache_schema::output)
+            .first::<String>(conn)
+            .optional()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+
+    pub async fn save(
+        cache_key: &str,
+        model: &str,
+        output: &str,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        let new_cache = NewCompletionCache::new(cache_key, model, output, Utc::now().naive_utc());
+        diesel::replace_into(cache_schema::table)
+            .values(new_cache)
+            .execute(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use chrono::Duration;
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use tokio;
+
+    use super::*;
+
+    // テスト用のデータベース接続プールを作成
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    #[tokio::test]
+    async fn test_save_and_find_completion() {
+        let pool = create_test_db_pool();
+        let repo = CompletionCacheRepositoryImpl::new(pool);
+        let cache_key = format!("{:0>64}", uuid::Uuid::new_v4().simple());
+        let an_hour_ago = Utc::now().naive_utc() - Duration::hours(1);
+
+        repo.save(&cache_key, "gpt-4-turbo", "一回目．")
+            .await
+            .unwrap();
+        repo.save(&cache_key, "gpt-4-turbo", "二回目．")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repo.find(&cache_key, an_hour_ago).await.unwrap(),
+            Some("二回目．".to_string())
+        );
+        // 期限より前に保存したものは返さない
+        let in_an_hour = Utc::now().naive_utc() + Duration::hours(1);
+        assert_eq!(repo.find(&cache_key, in_an_hour).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,619 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::r2d2::ConnectionManager;
+use diesel::result::DatabaseErrorKind;
+use diesel::result::Error::DatabaseError;
+use r2d2::PooledConnection;
+use serde_json;
+
+use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+use crate::domain::entity::entry::DiaryEntry;
+use crate::domain::entity::job::JobId;
+use crate::domain::entity::status::{DiaryStatus, MutationState};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+use crate::domain::repository::diary::DiaryRepository;
+use crate::infrastructure::database::init::DbPool;
+use crate::infrastructure::database::job::InternalMutationJobRepository;
+use crate::schema::diary::{self as diary_schema};
+use crate::schema::diary_status::{self as status_schema};
+
+#[derive(Clone)]
+pub struct DiaryRepositoryImpl {
+    pub pool: DbPool,
+}
+
+impl DiaryRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self { Self { pool } }
+
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DomainError> {
+        self.pool
+            .get()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+}
+
+#[async_trait]
+impl DiaryRepository for DiaryRepositoryImpl {
+    async fn find_by_id(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary_id: &DiaryId,
+    ) -> Result<Option<Diary>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let diary =
+            InternalDiaryRepository::find_by_id(user_id, entry_date, diary_id, &mut connection)
+                .await?;
+        Ok(diary)
+    }
+
+    async fn find_all(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+    ) -> Result<Vec<Diary>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let diaries =
+            InternalDiaryRepository::find_all(user_id, entry_date, &mut connection).await?;
+        Ok(diaries)
+    }
+
+    async fn find_entries(&self, user_id: &UserId) -> Result<Vec<DiaryEntry>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let entries = InternalDiaryRepository::find_entries(user_id, &mut connection).await?;
+        Ok(entries)
+    }
+
+    async fn find_latest_date(&self, user_id: &UserId) -> Result<Option<NaiveDate>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let latest = InternalDiaryRepository::find_latest_date(user_id, &mut connection).await?;
+        Ok(latest)
+    }
+
+    async fn update_all(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        statuses: &[DiaryStatus],
+    ) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalDiaryRepository::update_all(
+            None,
+            user_id,
+            entry_date,
+            diaries,
+            statuses,
+            &mut connection,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn update_all_for_job(
+        &self,
+        job_id: &JobId,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        statuses: &[DiaryStatus],
+    ) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalDiaryRepository::update_all(
+            Some(job_id),
+            user_id,
+            entry_date,
+            diaries,
+            statuses,
+            &mut connection,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn find_status(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary_id: &DiaryId,
+    ) -> Result<Option<DiaryStatus>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let status =
+            InternalDiaryRepository::find_status(user_id, entry_date, diary_id, &mut connection)
+                .await?;
+        Ok(status)
+    }
+
+    async fn update_status(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        status: &DiaryStatus,
+    ) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalDiaryRepository::update_status(user_id, entry_date, status, &mut connection)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Queryable)]
+struct DiaryRow {
+    diary_id: i32,
+    content: String,
+    version: i32,
+}
+
+impl DiaryRow {
+    // content には JSON 文字列として保存している
+    fn into_diary(self) -> Result<Diary, DomainError> {
+        let text: String = serde_json::from_str(&self.content)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(
+            Diary::new(DiaryId::new(self.diary_id)?, DiaryContent::new(text)?)?
+                .with_version(self.version),
+        )
+    }
+}
+
+#[derive(Debug, Queryable)]
+struct DiaryStatusRow {
+    diary_id: i32,
+    status: String,
+    reason: Option<String>,
+    updated_at: NaiveDateTime,
+}
+
+impl DiaryStatusRow {
+    fn into_status(self) -> Result<DiaryStatus, DomainError> {
+        Ok(DiaryStatus::new(
+            DiaryId::new(self.diary_id)?,
+            MutationState::parse(&self.status)?,
+            self.reason,
+            self.updated_at,
+        ))
+    }
+}
+
+pub struct InternalDiaryRepository;
+
+impl InternalDiaryRepository {
+    pub async fn find_by_id(
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary_id: &DiaryId,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<Diary>, DomainError> {
+        let diary_row: Option<DiaryRow> = diary_schema::table
+            .filter(diary_schema::user_id.eq(user_id.as_str()))
+            .filter(diary_schema::entry_date.eq(entry_date))
+            .filter(diary_schema::diary_id.eq(diary_id.to_id()))
+            .select((
+                diary_schema::diary_id,
+                diary_schema::content,
+                diary_schema::version,
+            ))
+            .first::<DiaryRow>(conn)
+            .optional()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        diary_row.map(DiaryRow::into_diary).transpose()
+    }
+
+    pub async fn find_all(
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        conn: &mut MysqlConnection,
+    ) -> Result<Vec<Diary>, DomainError> {
+        let diary_rows: Vec<DiaryRow> = diary_schema::table
+            .filter(diary_schema::user_id.eq(user_id.as_str()))
+            .filter(diary_schema::entry_date.eq(entry_date))
+            .order_by(diary_schema::diary_id.asc())
+            .select((
+                diary_schema::diary_id,
+                diary_schema::content,
+                diary_schema::version,
+            ))
+            .load::<DiaryRow>(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        diary_rows.into_iter().map(DiaryRow::into_diary).collect()
+    }
+
+    pub async fn find_entries(
+        user_id: &UserId,
+        conn: &mut MysqlConnection,
+    ) -> Result<Vec<DiaryEntry>, DomainError> {
+        let entry_rows: Vec<(NaiveDate, DiaryRow)> = diary_schema::table
+            .filter(diary_schema::user_id.eq(user_id.as_str()))
+            .order_by((
+                diary_schema::entry_date.desc(),
+                diary_schema::diary_id.asc(),
+            ))
+            .select((
+                diary_schema::entry_date,
+                (
+                    diary_schema::diary_id,
+                    diary_schema::content,
+                    diary_schema::version,
+                ),
+            ))
+            .load::<(NaiveDate, DiaryRow)>(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        // 日付順に並んでいるので、連続する同じ日付の行を1つのエントリにまとめる
+        let mut entries: Vec<(NaiveDate, Vec<Diary>)> = vec![];
+        for (entry_date, row) in entry_rows {
+            let diary = row.into_diary()?;
+            match entries.last_mut() {
+                Some((date, diaries)) if *date == entry_date => diaries.push(diary),
+                _ => entries.push((entry_date, vec![diary])),
+            }
+        }
+        Ok(entries
+            .into_iter()
+            .map(|(date, diaries)| DiaryEntry::new(date, diaries))
+            .collect())
+    }
+
+    pub async fn find_latest_date(
+        user_id: &UserId,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<NaiveDate>, DomainError> {
+        diary_schema::table
+            .filter(diary_schema::user_id.eq(user_id.as_str()))
+            .select(diesel::dsl::max(diary_schema::entry_date))
+            .first::<Option<NaiveDate>>(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+
+    // job_id があれば、同じトランザクションでそのジョブの保存待ちの処理を done にする
+    pub async fn update_all(
+        job_id: Option<&JobId>,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        statuses: &[DiaryStatus],
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        let mut failure = None;
+        conn.transaction(|conn| {
+            let written = diaries
+                .iter()
+                .try_for_each(|diary| write_diary(user_id, entry_date, diary, conn))
+                .and_then(|_| {
+                    statuses
+                        .iter()
+                        .try_for_each(|status| write_status(user_id, entry_date, status, conn))
+                })
+                .and_then(|_| match job_id {
+                    Some(job_id) => InternalMutationJobRepository::mark_saved(job_id, conn),
+                    None => Ok(()),
+                });
+            if let Err(err) = written {
+                // 1つでも保存できなければ、それまでに書いた分も取り消す
+                failure = Some(err);
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+            Ok(())
+        })
+        .map_err(|err: diesel::result::Error| {
+            failure
+                .take()
+                .unwrap_or_else(|| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+        })
+    }
+
+    pub async fn find_status(
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary_id: &DiaryId,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<DiaryStatus>, DomainError> {
+        let status_row: Option<DiaryStatusRow> = status_schema::table
+            .filter(status_schema::user_id.eq(user_id.as_str()))
+            .filter(status_schema::entry_date.eq(entry_date))
+            .filter(status_schema::diary_id.eq(diary_id.to_id()))
+            .select((
+                status_schema::diary_id,
+                status_schema::status,
+                status_schema::reason,
+                status_schema::updated_at,
+            ))
+            .first::<DiaryStatusRow>(conn)
+            .optional()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        status_row.map(DiaryStatusRow::into_status).transpose()
+    }
+
+    pub async fn update_status(
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        status: &DiaryStatus,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        write_status(user_id, entry_date, status, conn)
+    }
+}
+
+fn write_status(
+    user_id: &UserId,
+    entry_date: &NaiveDate,
+    status: &DiaryStatus,
+    conn: &mut MysqlConnection,
+) -> Result<(), DomainError> {
+    diesel::sql_query(
+        "INSERT INTO diary_status (user_id, entry_date, diary_id, status, reason, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?) \
+             ON DUPLICATE KEY UPDATE status = VALUES(status), reason = VALUES(reason), \
+             updated_at = VALUES(updated_at)",
+    )
+    .bind::<diesel::sql_types::Text, _>(user_id.as_str())
+    .bind::<diesel::sql_types::Date, _>(entry_date)
+    .bind::<diesel::sql_types::Integer, _>(status.diary_id().to_id())
+    .bind::<diesel::sql_types::Text, _>(status.state().as_str())
+    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(status.reason())
+    .bind::<diesel::sql_types::Timestamp, _>(status.updated_at())
+    .execute(conn)
+    .map_err(|e| DomainError::InfrastructureError(anyhow::anyhow!(e)))?;
+
+    Ok(())
+}
+
+// version が0なら新しく作り、それ以外は保存されている版数と一致するときだけ上書きする
+fn write_diary(
+    user_id: &UserId,
+    entry_date: &NaiveDate,
+    diary: &Diary,
+    conn: &mut MysqlConnection,
+) -> Result<(), DomainError> {
+    let conflict = || {
+        DomainError::Conflict(format!(
+            "diary {} was updated by another request",
+            diary.id().to_id()
+        ))
+    };
+    if *diary.version() == 0 {
+        return diesel::insert_into(diary_schema::table)
+            .values((
+                diary_schema::user_id.eq(user_id.as_str()),
+                diary_schema::entry_date.eq(entry_date),
+                diary_schema::diary_id.eq(diary.id().to_id()),
+                diary_schema::content.eq(diary.content().to_json()),
+                diary_schema::version.eq(1),
+            ))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(|err| match err {
+                DatabaseError(DatabaseErrorKind::UniqueViolation, _) => conflict(),
+                err => DomainError::InfrastructureError(anyhow::anyhow!(err)),
+            });
+    }
+
+    let updated = diesel::update(
+        diary_schema::table
+            .filter(diary_schema::user_id.eq(user_id.as_str()))
+            .filter(diary_schema::entry_date.eq(entry_date))
+            .filter(diary_schema::diary_id.eq(diary.id().to_id()))
+            .filter(diary_schema::version.eq(diary.version())),
+    )
+    .set((
+        diary_schema::content.eq(diary.content().to_json()),
+        diary_schema::version.eq(diary_schema::version + 1),
+    ))
+    .execute(conn)
+    .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+    if updated == 0 {
+        return Err(conflict());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use tokio;
+
+    use super::*;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::user::UserRepositoryImpl;
+
+    // テスト用のデータベース接続プールを作成
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    #[tokio::test]
+    async fn test_update_diary() {
+        let pool = create_test_db_pool();
+        let repo = DiaryRepositoryImpl::new(pool);
+
+        let user_id = UserId::new("test_user_id".to_string()).unwrap();
+        let entry_date = NaiveDate::from_ymd_opt(2024, 7, 11).unwrap();
+
+        let diary_id = DiaryId::new(1).unwrap();
+        let diary_content = DiaryContent::new("Test diary entry".to_string()).unwrap();
+        let version = repo
+            .find_by_id(&user_id, &entry_date, &diary_id)
+            .await
+            .unwrap()
+            .map_or(0, |diary| *diary.version());
+        let diary = Diary::new(diary_id.clone(), diary_content)
+            .unwrap()
+            .with_version(version);
+
+        let result = repo
+            .update_all(&user_id, &entry_date, std::slice::from_ref(&diary), &[])
+            .await;
+
+        assert!(result.is_ok(), "Failed to update diary: {:?}", result);
+        let found = repo
+            .find_by_id(&user_id, &entry_date, &diary_id)
+            .await
+            .unwrap();
+        assert_eq!(found, Some(diary.clone().with_version(version + 1)));
+        // 古い版数のままでは保存できない
+        assert!(matches!(
+            repo.update_all(&user_id, &entry_date, &[diary], &[]).await,
+            Err(DomainError::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_all_rolls_back_on_conflict() {
+        let pool = create_test_db_pool();
+        let user_repository = UserRepositoryImpl::new(pool.clone());
+        let repo = DiaryRepositoryImpl::new(pool);
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        let entry_date = NaiveDate::from_ymd_opt(2024, 7, 11).unwrap();
+        let diary = |id: i32, text: &str| {
+            Diary::new(
+                DiaryId::new(id).unwrap(),
+                DiaryContent::new(text.to_string()).unwrap(),
+            )
+            .unwrap()
+        };
+        repo.update_all(&user_id, &entry_date, &[diary(1, "保存済み")], &[])
+            .await
+            .unwrap();
+
+        // 人間の日記は新しく作れるが、ペルソナ1の版数が古いので全体を取り消す
+        let result = repo
+            .update_all(
+                &user_id,
+                &entry_date,
+                &[diary(0, "人間"), diary(1, "書き換え")],
+                &[],
+            )
+            .await;
+
+        assert!(matches!(result, Err(DomainError::Conflict(_))));
+        let diaries = repo.find_all(&user_id, &entry_date).await.unwrap();
+        assert_eq!(diaries, vec![diary(1, "保存済み").with_version(1)]);
+
+        repo.update_all(
+            &user_id,
+            &entry_date,
+            &[diary(0, "人間"), diary(1, "書き換え").with_version(1)],
+            &[],
+        )
+        .await
+        .unwrap();
+        let diaries = repo.find_all(&user_id, &entry_date).await.unwrap();
+        assert_eq!(
+            diaries,
+            vec![
+                diary(0, "人間").with_version(1),
+                diary(1, "書き換え").with_version(2)
+            ]
+        );
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_find_all_diaries() {
+        let pool = create_test_db_pool();
+        let repo = DiaryRepositoryImpl::new(pool);
+
+        let user_id = UserId::new("test_user_id".to_string()).unwrap();
+        let entry_date = NaiveDate::from_ymd_opt(2024, 7, 11).unwrap();
+
+        let diaries = repo.find_all(&user_id, &entry_date).await;
+
+        assert!(diaries.is_ok(), "Failed to find diaries: {:?}", diaries);
+        assert!(diaries
+            .unwrap()
+            .windows(2)
+            .all(|pair| pair[0].id().to_id() < pair[1].id().to_id()));
+    }
+
+    #[tokio::test]
+    async fn test_find_entries_groups_by_date() {
+        let pool = create_test_db_pool();
+        let user_repository = UserRepositoryImpl::new(pool.clone());
+        let repo = DiaryRepositoryImpl::new(pool);
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+
+        let first = NaiveDate::from_ymd_opt(2024, 7, 11).unwrap();
+        let second = NaiveDate::from_ymd_opt(2024, 7, 12).unwrap();
+        for (date, id) in [(first, 0), (first, 1), (second, 0)] {
+            let diary = Diary::new(
+                DiaryId::new(id).unwrap(),
+                DiaryContent::new(format!("{} {}", date, id)).unwrap(),
+            )
+            .unwrap();
+            repo.update_all(&user_id, &date, &[diary], &[])
+                .await
+                .unwrap();
+        }
+
+        let entries = repo.find_entries(&user_id).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(*entries[0].date(), second);
+        assert_eq!(entries[1].diaries().len(), 2);
+        assert_eq!(repo.find_latest_date(&user_id).await.unwrap(), Some(second));
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_status_overwrites_previous_result() {
+        let pool = create_test_db_pool();
+        let user_repository = UserRepositoryImpl::new(pool.clone());
+        let repo = DiaryRepositoryImpl::new(pool);
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        let entry_date = NaiveDate::from_ymd_opt(2024, 7, 11).unwrap();
+        let diary_id = DiaryId::new(1).unwrap();
+        let updated_at = chrono::Utc::now().naive_utc();
+
+        let failed = DiaryStatus::failed(diary_id.clone(), "timeout".to_string(), updated_at);
+        repo.update_status(&user_id, &entry_date, &failed)
+            .await
+            .unwrap();
+        let found = repo
+            .find_status(&user_id, &entry_date, &diary_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(found.is_failed());
+        assert_eq!(found.reason().as_deref(), Some("timeout"));
+
+        repo.update_status(
+            &user_id,
+            &entry_date,
+            &DiaryStatus::ok(diary_id.clone(), updated_at),
+        )
+        .await
+        .unwrap();
+        let found = repo
+            .find_status(&user_id, &entry_date, &diary_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*found.state(), MutationState::Ok);
+        assert_eq!(found.reason(), &None);
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::r2d2::ConnectionManager;
+use r2d2::PooledConnection;
+
+use crate::domain::entity::display::{DisplayId, DisplaySession};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+use crate::domain::repository::display::DisplaySessionRepository;
+use crate::infrastructure::database::init::DbPool;
+use crate::infrastructure::database::models::NewDisplaySession;
+use crate::schema::display_session::{self as display_schema};
+
+#[derive(Clone)]
+pub struct DisplaySessionRepositoryImpl {
+    pub pool: DbPool,
+}
+
+impl DisplaySessionRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self { Self { pool } }
+
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DomainError> {
+        self.pool
+            .get()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+}
+
+#[async_trait]
+impl DisplaySessionRepository for DisplaySessionRepositoryImpl {
+    async fn create(&self, session: &DisplaySession) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalDisplaySessionRepository::create(session, &mut connection).await?;
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &DisplayId) -> Result<Option<DisplaySession>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let session = InternalDisplaySessionRepository::find_by_id(id, &mut connection).await?;
+        Ok(session)
+    }
+
+    async fn find_by_pairing_code(
+        &self,
+        pairing_code: &str,
+    ) -> Result<Option<DisplaySession>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let session =
+            InternalDisplaySessionRepository::find_by_pairing_code(pairing_code, &mut connection)
+                .await?;
+        Ok(session)
+    }
+
+    async fn pair(
+        &self,
+        id: &DisplayId,
+        pairing_code: &str,
+        user_id: &UserId,
+    ) -> Result<bool, DomainError> {
+        let mut connection = self.get_connection()?;
+        let paired =
+            InternalDisplaySessionRepository::pair(id, pairing_code, user_id, &mut connection)
+                .await?;
+        Ok(paired)
+    }
+
+    async fn release_expired_codes(&self, issued_before: NaiveDateTime) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalDisplaySessionRepository::release_expired_codes(issued_before, &mut connection)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Queryable)]
+struct DisplaySessionRow {
+    display_id: String,
+    pairing_code: Option<String>,
+    user_id: Option<String>,
+    created_at: NaiveDateTime,
+}
+
+impl DisplaySessionRow {
+    fn into_session(self) -> Result<DisplaySession, DomainError> {
+        Ok(DisplaySession::new(
+            DisplayId::new(self.display_id),
+            self.pairing_code,
+            self.user_id.map(UserId::new).transpose()?,
+            self.created_at,
+        ))
+    }
+}
+
+pub struct InternalDisplaySessionRepository;
+
+impl InternalDisplaySessionRepository {
+    pub async fn create(
+        session: &DisplaySession,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        let new_session = NewDisplaySession::new(
+            session.id().as_str(),
+            session.pairing_code().as_deref(),
+            *session.created_at(),
+        );
+        diesel::insert_into(display_schema::table)
+            .values(new_session)
+            .execute(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+
+    pub async fn find_by_id(
+        id: &DisplayId,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<DisplaySession>, DomainError> {
+        let session_row: Option<DisplaySessionRow> = display_schema::table
+            .filter(display_schema::display_id.eq(id.as_str()))
+            .select((
+                display_schema::display_id,
+                display_schema::pairing_code,
+                display_schema::user_id,
+                display_schema::created_at,
+            ))
+            .first::<DisplaySessionRow>(conn)
+            .optional()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        session_row.map(DisplaySessionRow::into_session).transpose()
+    }
+
+    pub async fn find_by_pairing_code(
+        pairing_code: &str,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<DisplaySession>, DomainError> {
+        let session_row: Option<DisplaySessionRow> = display_schema::table
+            .filter(display_schema::pairing_code.eq(pairing_code))
+            .select((
+                display_schema::display_id,
+                display_schema::pairing_code,
+                display_schema::user_id,
+                display_schema::created_at,
+            ))
+            .first::<DisplaySessionRow>(conn)
+            .optional()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        session_row.map(DisplaySessionRow::into_session).transpose()
+    }
+
+    pub async fn pair(
+        id: &DisplayId,
+        pairing_code: &str,
+        user_id: &UserId,
+        conn: &mut MysqlConnection,
+    ) -> Result<bool, DomainError> {
+        // 同じコードで同時に紐付けようとしても、コードを消せた1件だけが成功する
+        let updated = diesel::update(
+            display_schema::table
+                .find(id.as_str())
+                .filter(display_schema::pairing_code.eq(pairing_code)),
+        )
+        .set((
+            display_schema::user_id.eq(user_id.as_str()),
+            display_schema::pairing_code.eq(None::<String>),
+            display_schema::paired_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)
+        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(updated == 1)
+    }
+
+    pub async fn release_expired_codes(
+        issued_before: NaiveDateTime,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        diesel::update(
+            display_schema::table
+                .filter(display_schema::pairing_code.is_not_null())
+                .filter(display_schema::created_at.lt(issued_before)),
+        )
+        .set(display_schema::pairing_code.eq(None::<String>))
+        .execute(conn)
+        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use tokio;
+
+    use super::*;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::user::UserRepositoryImpl;
+
+    // テスト用のデータベース接続プールを作成
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    #[tokio::test]
+    async fn test_pair_display_session() {
+        let pool = create_test_db_pool();
+        let user_repository = UserRepositoryImpl::new(pool.clone());
+        let repo = DisplaySessionRepositoryImpl::new(pool);
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        let display_id = DisplayId::new(uuid::Uuid::new_v4().to_string());
+        // 他のテストと衝突しないよう、display_id からコードを作る
+        let pairing_code = display_id.as_str()[..6].to_string();
+        let session = DisplaySession::new(
+            display_id.clone(),
+            Some(pairing_code.clone()),
+            None,
+            Utc::now().naive_utc(),
+        );
+        repo.create(&session).await.unwrap();
+
+        let found = repo.find_by_pairing_code(&pairing_code).await.unwrap();
+        assert_eq!(
+            found.map(|session| session.id().clone()),
+            Some(display_id.clone())
+        );
+
+        assert!(repo
+            .pair(&display_id, &pairing_code, &user_id)
+            .await
+            .unwrap());
+        // 使い終わったコードでは紐付け直せない
+        assert!(!repo
+            .pair(&display_id, &pairing_code, &user_id)
+            .await
+            .unwrap());
+
+        let paired = repo.find_by_id(&display_id).await.unwrap().unwrap();
+        assert_eq!(paired.user_id(), &Some(user_id.clone()));
+        assert!(paired.pairing_code().is_none());
+        assert!(repo
+            .find_by_pairing_code(&pairing_code)
+            .await
+            .unwrap()
+            .is_none());
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+}
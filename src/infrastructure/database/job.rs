@@ -0,0 +1,473 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::r2d2::ConnectionManager;
+use r2d2::PooledConnection;
+use serde_json;
+
+use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+use crate::domain::entity::job::{JobId, JobStatus, MutationJob, PersonaJob};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+use crate::domain::repository::job::MutationJobRepository;
+use crate::infrastructure::database::init::DbPool;
+use crate::infrastructure::database::models::{NewMutationJob, NewMutationJobTask};
+use crate::schema::mutation_job::{self as job_schema};
+use crate::schema::mutation_job_task::{self as task_schema};
+
+#[derive(Clone)]
+pub struct MutationJobRepositoryImpl {
+    pub pool: DbPool,
+}
+
+impl MutationJobRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self { Self { pool } }
+
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DomainError> {
+        self.pool
+            .get()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+}
+
+#[async_trait]
+impl MutationJobRepository for MutationJobRepositoryImpl {
+    async fn create(&self, job: &MutationJob) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalMutationJobRepository::create(job, &mut connection).await?;
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &JobId) -> Result<Option<MutationJob>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let job = InternalMutationJobRepository::find_by_id(id, &mut connection).await?;
+        Ok(job)
+    }
+
+    async fn find_latest_id(&self, user_id: &UserId) -> Result<Option<JobId>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let id = InternalMutationJobRepository::find_latest_id(user_id, &mut connection).await?;
+        Ok(id)
+    }
+
+    async fn claim_next(&self) -> Result<Option<(MutationJob, DiaryId)>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let claimed = InternalMutationJobRepository::claim_next(&mut connection).await?;
+        Ok(claimed)
+    }
+
+    async fn update_status(
+        &self,
+        id: &JobId,
+        persona_id: &DiaryId,
+        status: JobStatus,
+    ) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalMutationJobRepository::update_status(id, persona_id, status, &mut connection)
+            .await?;
+        Ok(())
+    }
+
+    async fn save_output(
+        &self,
+        id: &JobId,
+        persona_id: &DiaryId,
+        output: &Diary,
+        target_index: i32,
+    ) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalMutationJobRepository::save_output(
+            id,
+            persona_id,
+            output,
+            target_index,
+            &mut connection,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn find_unsaved(&self) -> Result<Vec<MutationJob>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let jobs = InternalMutationJobRepository::find_unsaved(&mut connection).await?;
+        Ok(jobs)
+    }
+
+    async fn requeue_running(&self) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalMutationJobRepository::requeue_running(&mut connection).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Queryable)]
+struct MutationJobRow {
+    job_id: String,
+    user_id: String,
+    entry_date: NaiveDate,
+    content: String,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Queryable)]
+struct MutationJobTaskRow {
+    persona_id: i32,
+    status: String,
+    attempts: i32,
+    output: Option<String>,
+    output_version: Option<i32>,
+    target_index: Option<i32>,
+}
+
+impl MutationJobTaskRow {
+    fn into_persona_job(self) -> Result<PersonaJob, DomainError> {
+        let persona_id = DiaryId::new(self.persona_id)?;
+        let output = match (self.output, self.output_version, self.target_index) {
+            (Some(output), Some(version), Some(target_index)) => {
+                let text: String = serde_json::from_str(&output)
+                    .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+                let diary =
+                    Diary::new(persona_id.clone(), DiaryContent::new(text)?)?.with_version(version);
+                Some((diary, target_index))
+            },
+            _ => None,
+        };
+        Ok(
+            PersonaJob::new(persona_id, JobStatus::parse(&self.status)?, self.attempts)
+                .with_output(output),
+        )
+    }
+}
+
+pub struct InternalMutationJobRepository;
+
+impl InternalMutationJobRepository {
+    pub async fn create(job: &MutationJob, conn: &mut MysqlConnection) -> Result<(), DomainError> {
+        let new_job = NewMutationJob::new(
+            job.id().as_str(),
+            job.user_id().as_str(),
+            *job.entry_date(),
+            job.content().to_json(),
+            *job.created_at(),
+        );
+        let new_tasks: Vec<NewMutationJobTask> = job
+            .persona_jobs()
+            .iter()
+            .map(|persona_job| {
+                NewMutationJobTask::new(
+                    job.id().as_str(),
+                    persona_job.persona_id().to_id(),
+                    persona_job.status().as_str(),
+                    *persona_job.attempts(),
+                    *job.created_at(),
+                )
+            })
+            .collect();
+
+        conn.transaction(|conn| {
+            diesel::insert_into(job_schema::table)
+                .values(new_job)
+                .execute(conn)?;
+            diesel::insert_into(task_schema::table)
+                .values(new_tasks)
+                .execute(conn)
+        })
+        .map_err(|err: diesel::result::Error| {
+            DomainError::InfrastructureError(anyhow::anyhow!(err))
+        })?;
+        Ok(())
+    }
+
+    pub async fn find_by_id(
+        id: &JobId,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<MutationJob>, DomainError> {
+        let job_row: Option<MutationJobRow> = job_schema::table
+            .filter(job_schema::job_id.eq(id.as_str()))
+            .select((
+                job_schema::job_id,
+                job_schema::user_id,
+                job_schema::entry_date,
+                job_schema::content,
+                job_schema::created_at,
+            ))
+            .first::<MutationJobRow>(conn)
+            .optional()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        let job_row = match job_row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let task_rows: Vec<MutationJobTaskRow> = task_schema::table
+            .filter(task_schema::job_id.eq(id.as_str()))
+            .order_by(task_schema::persona_id.asc())
+            .select((
+                task_schema::persona_id,
+                task_schema::status,
+                task_schema::attempts,
+                task_schema::output,
+                task_schema::output_version,
+                task_schema::target_index,
+            ))
+            .load::<MutationJobTaskRow>(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        let persona_jobs = task_rows
+            .into_iter()
+            .map(MutationJobTaskRow::into_persona_job)
+            .collect::<Result<Vec<PersonaJob>, DomainError>>()?;
+
+        // content には JSON 文字列として保存している
+        let text: String = serde_json::from_str(&job_row.content)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(Some(MutationJob::new(
+            JobId::new(job_row.job_id),
+            UserId::new(job_row.user_id)?,
+            job_row.entry_date,
+            DiaryContent::new(text)?,
+            job_row.created_at,
+            persona_jobs,
+        )))
+    }
+
+    pub async fn find_latest_id(
+        user_id: &UserId,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<JobId>, DomainError> {
+        let job_id: Option<String> = job_schema::table
+            .filter(job_schema::user_id.eq(user_id.as_str()))
+            .order_by((job_schema::created_at.desc(), job_schema::job_id.desc()))
+            .select(job_schema::job_id)
+            .first(conn)
+            .optional()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(job_id.map(JobId::new))
+    }
+
+    pub async fn claim_next(
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<(MutationJob, DiaryId)>, DomainError> {
+        // 複数のワーカーが同じ処理を取らないよう、行ロックを取ってから running にする
+        let claimed: Option<(String, i32)> = conn
+            .transaction(|conn| {
+                let task: Option<(String, i32)> = task_schema::table
+                    .filter(task_schema::status.eq(JobStatus::Pending.as_str()))
+                    .order_by((
+                        task_schema::updated_at.asc(),
+                        task_schema::job_id.asc(),
+                        task_schema::persona_id.asc(),
+                    ))
+                    .select((task_schema::job_id, task_schema::persona_id))
+                    .for_update()
+                    .skip_locked()
+                    .first(conn)
+                    .optional()?;
+                if let Some((job_id, persona_id)) = &task {
+                    diesel::update(task_schema::table.find((job_id.as_str(), *persona_id)))
+                        .set((
+                            task_schema::status.eq(JobStatus::Running.as_str()),
+                            task_schema::attempts.eq(task_schema::attempts + 1),
+                            task_schema::updated_at.eq(Utc::now().naive_utc()),
+                        ))
+                        .execute(conn)?;
+                }
+                Ok(task)
+            })
+            .map_err(|err: diesel::result::Error| {
+                DomainError::InfrastructureError(anyhow::anyhow!(err))
+            })?;
+
+        let (job_id, persona_id) = match claimed {
+            Some(claimed) => claimed,
+            None => return Ok(None),
+        };
+        match Self::find_by_id(&JobId::new(job_id), conn).await? {
+            Some(job) => Ok(Some((job, DiaryId::new(persona_id)?))),
+            None => Err(DomainError::Unexpected(
+                "claimed job was not found".to_string(),
+            )),
+        }
+    }
+
+    pub async fn update_status(
+        id: &JobId,
+        persona_id: &DiaryId,
+        status: JobStatus,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        diesel::update(task_schema::table.find((id.as_str(), persona_id.to_id())))
+            .set((
+                task_schema::status.eq(status.as_str()),
+                task_schema::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+
+    pub async fn save_output(
+        id: &JobId,
+        persona_id: &DiaryId,
+        output: &Diary,
+        target_index: i32,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        diesel::update(task_schema::table.find((id.as_str(), persona_id.to_id())))
+            .set((
+                task_schema::status.eq(JobStatus::Mutated.as_str()),
+                task_schema::output.eq(output.content().to_json()),
+                task_schema::output_version.eq(output.version()),
+                task_schema::target_index.eq(target_index),
+                task_schema::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+
+    // 日記を保存するトランザクションの中から呼ぶ
+    pub fn mark_saved(id: &JobId, conn: &mut MysqlConnection) -> Result<(), DomainError> {
+        diesel::update(
+            task_schema::table
+                .filter(task_schema::job_id.eq(id.as_str()))
+                .filter(task_schema::status.eq(JobStatus::Mutated.as_str())),
+        )
+        .set((
+            task_schema::status.eq(JobStatus::Done.as_str()),
+            task_schema::updated_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)
+        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+
+    pub async fn find_unsaved(conn: &mut MysqlConnection) -> Result<Vec<MutationJob>, DomainError> {
+        let mutated: Vec<String> = task_schema::table
+            .filter(task_schema::status.eq(JobStatus::Mutated.as_str()))
+            .select(task_schema::job_id)
+            .distinct()
+            .load(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        let job_ids: Vec<String> = job_schema::table
+            .filter(job_schema::job_id.eq_any(&mutated))
+            .order_by((job_schema::created_at.asc(), job_schema::job_id.asc()))
+            .select(job_schema::job_id)
+            .load(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        // 他のペルソナがまだ処理中のジョブは、そのペルソナが終わったときに保存する
+        let mut jobs = vec![];
+        for job_id in job_ids {
+            if let Some(job) = Self::find_by_id(&JobId::new(job_id), conn).await? {
+                if job.is_unsaved() {
+                    jobs.push(job);
+                }
+            }
+        }
+        Ok(jobs)
+    }
+
+    pub async fn requeue_running(conn: &mut MysqlConnection) -> Result<(), DomainError> {
+        diesel::update(
+            task_schema::table.filter(task_schema::status.eq(JobStatus::Running.as_str())),
+        )
+        .set(task_schema::status.eq(JobStatus::Pending.as_str()))
+        .execute(conn)
+        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use tokio;
+
+    use super::*;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::user::UserRepositoryImpl;
+
+    // テスト用のデータベース接続プールを作成
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    #[tokio::test]
+    async fn test_claim_and_finish_persona_jobs() {
+        let pool = create_test_db_pool();
+        let user_repository = UserRepositoryImpl::new(pool.clone());
+        let repo = MutationJobRepositoryImpl::new(pool);
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        let job_id = JobId::new(uuid::Uuid::new_v4().to_string());
+        let job = MutationJob::new(
+            job_id.clone(),
+            user_id.clone(),
+            Utc::now().date_naive(),
+            DiaryContent::new("一文目．".to_string()).unwrap(),
+            Utc::now().naive_utc(),
+            vec![PersonaJob::new(
+                DiaryId::new(1).unwrap(),
+                JobStatus::Pending,
+                0,
+            )],
+        );
+        repo.create(&job).await.unwrap();
+
+        // 他のテストのジョブが残っていることもあるので、自分のジョブを取るまで進める
+        let persona_id = loop {
+            let (claimed, persona_id) = repo.claim_next().await.unwrap().unwrap();
+            if claimed.id() == &job_id {
+                assert_eq!(claimed.content().to_value(), "一文目．");
+                break persona_id;
+            }
+        };
+        let running = repo.find_by_id(&job_id).await.unwrap().unwrap();
+        assert_eq!(running.status(), JobStatus::Running);
+        assert_eq!(*running.find(&persona_id).unwrap().attempts(), 1);
+
+        // 再起動後は running のまま残った処理をもう一度取れる
+        repo.requeue_running().await.unwrap();
+        let requeued = repo.find_by_id(&job_id).await.unwrap().unwrap();
+        assert_eq!(requeued.status(), JobStatus::Pending);
+
+        let output = Diary::new(
+            persona_id.clone(),
+            DiaryContent::new("書き換えた．".to_string()).unwrap(),
+        )
+        .unwrap()
+        .with_version(2);
+        repo.save_output(&job_id, &persona_id, &output, 1)
+            .await
+            .unwrap();
+        // 日記を保存するまでは done にしない
+        let mutated = repo.find_by_id(&job_id).await.unwrap().unwrap();
+        assert_eq!(mutated.status(), JobStatus::Running);
+        assert!(mutated.is_settled());
+        assert_eq!(
+            mutated.find(&persona_id).unwrap().output(),
+            &Some((output, 1))
+        );
+        let unsaved = repo.find_unsaved().await.unwrap();
+        assert!(unsaved.iter().any(|job| job.id() == &job_id));
+        let mut connection = repo.get_connection().unwrap();
+        InternalMutationJobRepository::mark_saved(&job_id, &mut connection).unwrap();
+        let done = repo.find_by_id(&job_id).await.unwrap().unwrap();
+        assert_eq!(done.status(), JobStatus::Done);
+        let unsaved = repo.find_unsaved().await.unwrap();
+        assert!(!unsaved.iter().any(|job| job.id() == &job_id));
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+}
@@ -0,0 +1,698 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+
+use crate::domain::entity::diary::{Diary, DiaryId};
+use crate::domain::entity::display::{DisplayId, DisplaySession};
+use crate::domain::entity::entry::DiaryEntry;
+use crate::domain::entity::job::{JobId, JobStatus, MutationJob, PersonaJob};
+use crate::domain::entity::persona::Persona;
+use crate::domain::entity::revision::DiaryRevision;
+use crate::domain::entity::status::DiaryStatus;
+use crate::domain::entity::token::{RefreshToken, RefreshTokenHash};
+use crate::domain::entity::user::{User, UserId};
+use crate::domain::error::DomainError;
+use crate::domain::repository::completion::CompletionCacheRepository;
+use crate::domain::repository::diary::DiaryRepository;
+use crate::domain::repository::display::DisplaySessionRepository;
+use crate::domain::repository::job::MutationJobRepository;
+use crate::domain::repository::persona::PersonaRepository;
+use crate::domain::repository::revision::DiaryRevisionRepository;
+use crate::domain::repository::revocation::AccessRevocationRepository;
+use crate::domain::repository::token::RefreshTokenRepository;
+use crate::domain::repository::user::UserRepository;
+
+// テスト用のインメモリ実装。DBなしでユースケースを検証するために使う
+#[derive(Clone, Default)]
+pub struct InMemoryUserRepository {
+    users: Arc<Mutex<HashMap<String, User>>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self { Self::default() }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn create(&self, user_id: &UserId) -> Result<(), DomainError> {
+        let current_time = Utc::now().naive_utc();
+        let user = User::new(user_id.clone(), None, None, current_time, current_time, 1);
+        self.users
+            .lock()
+            .unwrap()
+            .insert(user_id.as_str().to_string(), user);
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, DomainError> {
+        Ok(self.users.lock().unwrap().get(id.as_str()).cloned())
+    }
+
+    async fn update_result(
+        &self,
+        user_id: &UserId,
+        is_public: bool,
+        favorite_id: &DiaryId,
+        version: i32,
+    ) -> Result<(), DomainError> {
+        match self.users.lock().unwrap().get_mut(user_id.as_str()) {
+            Some(user) if user.version == version => {
+                user.is_public = Some(is_public);
+                user.favorite_id = Some(favorite_id.clone());
+                user.version += 1;
+                Ok(())
+            },
+            _ => Err(DomainError::Conflict(format!(
+                "user {} was updated by another request",
+                user_id.as_str()
+            ))),
+        }
+    }
+
+    async fn delete_user(&self, id: &UserId) -> Result<(), DomainError> {
+        self.users.lock().unwrap().remove(id.as_str());
+        Ok(())
+    }
+}
+
+// (user_id, entry_date, diary_id)
+type DiaryKey = (String, NaiveDate, i32);
+
+#[derive(Clone, Default)]
+pub struct InMemoryDiaryRepository {
+    diaries: Arc<Mutex<BTreeMap<DiaryKey, Diary>>>,
+    statuses: Arc<Mutex<BTreeMap<DiaryKey, DiaryStatus>>>,
+    // update_all_for_job で保存待ちの処理を done にするジョブのリポジトリ
+    jobs: Option<InMemoryMutationJobRepository>,
+    // 残りの回数だけ日記の保存を失敗させる
+    failures: Arc<AtomicUsize>,
+}
+
+impl InMemoryDiaryRepository {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_jobs(self, jobs: InMemoryMutationJobRepository) -> Self {
+        Self {
+            jobs: Some(jobs),
+            ..self
+        }
+    }
+
+    // 保存の失敗からの立ち直りを確かめるため、次の count 回の保存を失敗させる
+    pub fn fail_next_writes(&self, count: usize) { self.failures.store(count, Ordering::SeqCst); }
+
+    // 全て確かめてから書くので、Conflict の場合は何も変わらない
+    fn write_entry(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        statuses: &[DiaryStatus],
+    ) -> Result<(), DomainError> {
+        if self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                count.checked_sub(1)
+            })
+            .is_ok()
+        {
+            return Err(DomainError::InfrastructureError(anyhow::anyhow!(
+                "injected write failure"
+            )));
+        }
+        let mut stored = self.diaries.lock().unwrap();
+        let key = |diary: &Diary| {
+            (
+                user_id.as_str().to_string(),
+                *entry_date,
+                diary.id().to_id(),
+            )
+        };
+        for diary in diaries {
+            let stored_version = stored
+                .get(&key(diary))
+                .map_or(0, |stored| *stored.version());
+            if stored_version != *diary.version() {
+                return Err(DomainError::Conflict(format!(
+                    "diary {} was updated by another request",
+                    diary.id().to_id()
+                )));
+            }
+        }
+        for diary in diaries {
+            stored.insert(key(diary), diary.clone().with_version(diary.version() + 1));
+        }
+        let mut stored_statuses = self.statuses.lock().unwrap();
+        for status in statuses {
+            stored_statuses.insert(
+                (
+                    user_id.as_str().to_string(),
+                    *entry_date,
+                    status.diary_id().to_id(),
+                ),
+                status.clone(),
+            );
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DiaryRepository for InMemoryDiaryRepository {
+    async fn find_by_id(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary_id: &DiaryId,
+    ) -> Result<Option<Diary>, DomainError> {
+        Ok(self
+            .diaries
+            .lock()
+            .unwrap()
+            .get(&(user_id.as_str().to_string(), *entry_date, diary_id.to_id()))
+            .cloned())
+    }
+
+    async fn find_all(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+    ) -> Result<Vec<Diary>, DomainError> {
+        Ok(self
+            .diaries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((owner, date, _), _)| owner == user_id.as_str() && date == entry_date)
+            .map(|(_, diary)| diary.clone())
+            .collect())
+    }
+
+    async fn find_entries(&self, user_id: &UserId) -> Result<Vec<DiaryEntry>, DomainError> {
+        let mut entries: BTreeMap<NaiveDate, Vec<Diary>> = BTreeMap::new();
+        for ((owner, date, _), diary) in self.diaries.lock().unwrap().iter() {
+            if owner == user_id.as_str() {
+                entries.entry(*date).or_default().push(diary.clone());
+            }
+        }
+        Ok(entries
+            .into_iter()
+            .rev()
+            .map(|(date, diaries)| DiaryEntry::new(date, diaries))
+            .collect())
+    }
+
+    async fn find_latest_date(&self, user_id: &UserId) -> Result<Option<NaiveDate>, DomainError> {
+        Ok(self
+            .diaries
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|(owner, _, _)| owner == user_id.as_str())
+            .map(|(_, date, _)| *date)
+            .max())
+    }
+
+    async fn update_all(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        statuses: &[DiaryStatus],
+    ) -> Result<(), DomainError> {
+        self.write_entry(user_id, entry_date, diaries, statuses)
+    }
+
+    async fn update_all_for_job(
+        &self,
+        job_id: &JobId,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diaries: &[Diary],
+        statuses: &[DiaryStatus],
+    ) -> Result<(), DomainError> {
+        let jobs = self
+            .jobs
+            .as_ref()
+            .ok_or_else(|| DomainError::Unexpected("job repository is not attached".to_string()))?;
+        self.write_entry(user_id, entry_date, diaries, statuses)?;
+        jobs.mark_saved(job_id);
+        Ok(())
+    }
+
+    async fn find_status(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        diary_id: &DiaryId,
+    ) -> Result<Option<DiaryStatus>, DomainError> {
+        Ok(self
+            .statuses
+            .lock()
+            .unwrap()
+            .get(&(user_id.as_str().to_string(), *entry_date, diary_id.to_id()))
+            .cloned())
+    }
+
+    async fn update_status(
+        &self,
+        user_id: &UserId,
+        entry_date: &NaiveDate,
+        status: &DiaryStatus,
+    ) -> Result<(), DomainError> {
+        self.statuses.lock().unwrap().insert(
+            (
+                user_id.as_str().to_string(),
+                *entry_date,
+                status.diary_id().to_id(),
+            ),
+            status.clone(),
+        );
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryDiaryRevisionRepository {
+    revisions: Arc<Mutex<Vec<(String, DiaryRevision)>>>,
+}
+
+impl InMemoryDiaryRevisionRepository {
+    pub fn new() -> Self { Self::default() }
+}
+
+#[async_trait]
+impl DiaryRevisionRepository for InMemoryDiaryRevisionRepository {
+    async fn append(&self, user_id: &UserId, revision: &DiaryRevision) -> Result<(), DomainError> {
+        self.revisions
+            .lock()
+            .unwrap()
+            .push((user_id.as_str().to_string(), revision.clone()));
+        Ok(())
+    }
+
+    async fn find_by_user(&self, user_id: &UserId) -> Result<Vec<DiaryRevision>, DomainError> {
+        Ok(self
+            .revisions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(owner, _)| owner == user_id.as_str())
+            .map(|(_, revision)| revision.clone())
+            .collect())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryCompletionCacheRepository {
+    completions: Arc<Mutex<HashMap<String, (String, NaiveDateTime)>>>,
+}
+
+impl InMemoryCompletionCacheRepository {
+    pub fn new() -> Self { Self::default() }
+}
+
+#[async_trait]
+impl CompletionCacheRepository for InMemoryCompletionCacheRepository {
+    async fn find(
+        &self,
+        cache_key: &str,
+        created_after: NaiveDateTime,
+    ) -> Result<Option<String>, DomainError> {
+        Ok(self
+            .completions
+            .lock()
+            .unwrap()
+            .get(cache_key)
+            .filter(|(_, created_at)| *created_at >= created_after)
+            .map(|(output, _)| output.clone()))
+    }
+
+    async fn save(&self, cache_key: &str, _model: &str, output: &str) -> Result<(), DomainError> {
+        self.completions.lock().unwrap().insert(
+            cache_key.to_string(),
+            (output.to_string(), Utc::now().naive_utc()),
+        );
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryAccessRevocationRepository {
+    revocations: Arc<Mutex<HashMap<String, NaiveDateTime>>>,
+}
+
+impl InMemoryAccessRevocationRepository {
+    pub fn new() -> Self { Self::default() }
+}
+
+#[async_trait]
+impl AccessRevocationRepository for InMemoryAccessRevocationRepository {
+    async fn save(&self, user_id: &UserId, revoked_at: NaiveDateTime) -> Result<(), DomainError> {
+        self.revocations
+            .lock()
+            .unwrap()
+            .insert(user_id.as_str().to_string(), revoked_at);
+        Ok(())
+    }
+
+    async fn find_after(
+        &self,
+        revoked_after: NaiveDateTime,
+    ) -> Result<Vec<(UserId, NaiveDateTime)>, DomainError> {
+        self.revocations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, revoked_at)| **revoked_at >= revoked_after)
+            .map(|(user_id, revoked_at)| Ok((UserId::new(user_id.clone())?, *revoked_at)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryDisplaySessionRepository {
+    sessions: Arc<Mutex<HashMap<String, DisplaySession>>>,
+}
+
+impl InMemoryDisplaySessionRepository {
+    pub fn new() -> Self { Self::default() }
+}
+
+#[async_trait]
+impl DisplaySessionRepository for InMemoryDisplaySessionRepository {
+    async fn create(&self, session: &DisplaySession) -> Result<(), DomainError> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session.id().as_str().to_string(), session.clone());
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &DisplayId) -> Result<Option<DisplaySession>, DomainError> {
+        Ok(self.sessions.lock().unwrap().get(id.as_str()).cloned())
+    }
+
+    async fn find_by_pairing_code(
+        &self,
+        pairing_code: &str,
+    ) -> Result<Option<DisplaySession>, DomainError> {
+        Ok(self
+            .sessions
+            .lock()
+            .unwrap()
+            .values()
+            .find(|session| session.pairing_code().as_deref() == Some(pairing_code))
+            .cloned())
+    }
+
+    async fn pair(
+        &self,
+        id: &DisplayId,
+        pairing_code: &str,
+        user_id: &UserId,
+    ) -> Result<bool, DomainError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        match sessions.get(id.as_str()).cloned() {
+            Some(session) if session.pairing_code().as_deref() == Some(pairing_code) => {
+                sessions.insert(
+                    id.as_str().to_string(),
+                    DisplaySession::new(
+                        session.id().clone(),
+                        None,
+                        Some(user_id.clone()),
+                        *session.created_at(),
+                    ),
+                );
+                Ok(true)
+            },
+            _ => Ok(false),
+        }
+    }
+
+    async fn release_expired_codes(&self, issued_before: NaiveDateTime) -> Result<(), DomainError> {
+        for session in self.sessions.lock().unwrap().values_mut() {
+            if session.pairing_code().is_some() && *session.created_at() < issued_before {
+                *session = DisplaySession::new(
+                    session.id().clone(),
+                    None,
+                    session.user_id().clone(),
+                    *session.created_at(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+// ジョブは登録順に並べ、ペルソナごとの処理の状態は別に持つ (DB と同じ形)
+#[derive(Clone, Default)]
+pub struct InMemoryMutationJobRepository {
+    jobs: Arc<Mutex<Vec<MutationJob>>>,
+    tasks: Arc<Mutex<HashMap<(String, i32), PersonaJob>>>,
+}
+
+impl InMemoryMutationJobRepository {
+    pub fn new() -> Self { Self::default() }
+
+    fn with_tasks(&self, job: &MutationJob) -> MutationJob {
+        let tasks = self.tasks.lock().unwrap();
+        let persona_jobs = job
+            .persona_jobs()
+            .iter()
+            .map(|persona_job| {
+                tasks[&(
+                    job.id().as_str().to_string(),
+                    persona_job.persona_id().to_id(),
+                )]
+                    .clone()
+            })
+            .collect();
+        MutationJob::new(
+            job.id().clone(),
+            job.user_id().clone(),
+            *job.entry_date(),
+            job.content().clone(),
+            *job.created_at(),
+            persona_jobs,
+        )
+    }
+
+    // 日記の保存と同時に、保存待ちの処理を done にする
+    pub fn mark_saved(&self, id: &JobId) {
+        for ((job_id, _), task) in self.tasks.lock().unwrap().iter_mut() {
+            if job_id == id.as_str() && *task.status() == JobStatus::Mutated {
+                *task =
+                    PersonaJob::new(task.persona_id().clone(), JobStatus::Done, *task.attempts())
+                        .with_output(task.output().clone());
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MutationJobRepository for InMemoryMutationJobRepository {
+    async fn create(&self, job: &MutationJob) -> Result<(), DomainError> {
+        let mut tasks = self.tasks.lock().unwrap();
+        for persona_job in job.persona_jobs() {
+            tasks.insert(
+                (
+                    job.id().as_str().to_string(),
+                    persona_job.persona_id().to_id(),
+                ),
+                persona_job.clone(),
+            );
+        }
+        self.jobs.lock().unwrap().push(job.clone());
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &JobId) -> Result<Option<MutationJob>, DomainError> {
+        let job = self
+            .jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|job| job.id() == id)
+            .cloned();
+        Ok(job.map(|job| self.with_tasks(&job)))
+    }
+
+    async fn find_latest_id(&self, user_id: &UserId) -> Result<Option<JobId>, DomainError> {
+        Ok(self
+            .jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|job| job.user_id() == user_id)
+            .map(|job| job.id().clone()))
+    }
+
+    async fn claim_next(&self) -> Result<Option<(MutationJob, DiaryId)>, DomainError> {
+        let jobs = self.jobs.lock().unwrap().clone();
+        for job in jobs {
+            let mut tasks = self.tasks.lock().unwrap();
+            let claimed = job.persona_jobs().iter().find_map(|persona_job| {
+                let key = (
+                    job.id().as_str().to_string(),
+                    persona_job.persona_id().to_id(),
+                );
+                let task = &tasks[&key];
+                (*task.status() == JobStatus::Pending).then(|| (key, task.clone()))
+            });
+            if let Some((key, task)) = claimed {
+                tasks.insert(
+                    key,
+                    PersonaJob::new(
+                        task.persona_id().clone(),
+                        JobStatus::Running,
+                        task.attempts() + 1,
+                    )
+                    .with_output(task.output().clone()),
+                );
+                drop(tasks);
+                return Ok(Some((self.with_tasks(&job), task.persona_id().clone())));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn update_status(
+        &self,
+        id: &JobId,
+        persona_id: &DiaryId,
+        status: JobStatus,
+    ) -> Result<(), DomainError> {
+        let mut tasks = self.tasks.lock().unwrap();
+        if let Some(task) = tasks.get_mut(&(id.as_str().to_string(), persona_id.to_id())) {
+            *task = PersonaJob::new(task.persona_id().clone(), status, *task.attempts())
+                .with_output(task.output().clone());
+        }
+        Ok(())
+    }
+
+    async fn save_output(
+        &self,
+        id: &JobId,
+        persona_id: &DiaryId,
+        output: &Diary,
+        target_index: i32,
+    ) -> Result<(), DomainError> {
+        let mut tasks = self.tasks.lock().unwrap();
+        if let Some(task) = tasks.get_mut(&(id.as_str().to_string(), persona_id.to_id())) {
+            *task = PersonaJob::new(
+                task.persona_id().clone(),
+                JobStatus::Mutated,
+                *task.attempts(),
+            )
+            .with_output(Some((output.clone(), target_index)));
+        }
+        Ok(())
+    }
+
+    async fn find_unsaved(&self) -> Result<Vec<MutationJob>, DomainError> {
+        let jobs = self.jobs.lock().unwrap().clone();
+        Ok(jobs
+            .iter()
+            .map(|job| self.with_tasks(job))
+            .filter(MutationJob::is_unsaved)
+            .collect())
+    }
+
+    async fn requeue_running(&self) -> Result<(), DomainError> {
+        for task in self.tasks.lock().unwrap().values_mut() {
+            if *task.status() == JobStatus::Running {
+                *task = PersonaJob::new(
+                    task.persona_id().clone(),
+                    JobStatus::Pending,
+                    *task.attempts(),
+                )
+                .with_output(task.output().clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryPersonaRepository {
+    personas: Arc<Vec<Persona>>,
+}
+
+impl InMemoryPersonaRepository {
+    pub fn new(personas: Vec<Persona>) -> Self {
+        Self {
+            personas: Arc::new(personas),
+        }
+    }
+}
+
+#[async_trait]
+impl PersonaRepository for InMemoryPersonaRepository {
+    async fn find_enabled(&self) -> Result<Vec<Persona>, DomainError> {
+        let mut personas = self.personas.as_ref().clone();
+        personas.sort_by_key(|persona| *persona.display_order());
+        Ok(personas)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryRefreshTokenRepository {
+    tokens: Arc<Mutex<HashMap<RefreshTokenHash, RefreshToken>>>,
+}
+
+impl InMemoryRefreshTokenRepository {
+    pub fn new() -> Self { Self::default() }
+}
+
+#[async_trait]
+impl RefreshTokenRepository for InMemoryRefreshTokenRepository {
+    async fn create(&self, token: &RefreshToken) -> Result<(), DomainError> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(token.hash().clone(), token.clone());
+        Ok(())
+    }
+
+    async fn find_by_hash(
+        &self,
+        hash: &RefreshTokenHash,
+    ) -> Result<Option<RefreshToken>, DomainError> {
+        Ok(self.tokens.lock().unwrap().get(hash).cloned())
+    }
+
+    async fn revoke(&self, hash: &RefreshTokenHash) -> Result<bool, DomainError> {
+        let mut tokens = self.tokens.lock().unwrap();
+        match tokens.get(hash).cloned() {
+            Some(token) if !token.is_revoked() => {
+                tokens.insert(hash.clone(), revoked(token));
+                Ok(true)
+            },
+            _ => Ok(false),
+        }
+    }
+
+    async fn revoke_all(&self, user_id: &UserId) -> Result<(), DomainError> {
+        let mut tokens = self.tokens.lock().unwrap();
+        for token in tokens.values_mut() {
+            if token.user_id() == user_id && !token.is_revoked() {
+                *token = revoked(token.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn revoked(token: RefreshToken) -> RefreshToken {
+    RefreshToken::new(
+        token.hash().clone(),
+        token.user_id().clone(),
+        *token.expires_at(),
+        Some(Utc::now().naive_utc()),
+    )
+}
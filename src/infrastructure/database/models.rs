@@ -1,7 +1,10 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
 
-use crate::schema::user;
+use crate::schema::{
+    access_revocation, completion_cache, diary_revision, display_session, mutation_job,
+    mutation_job_task, refresh_token, user,
+};
 
 #[derive(Insertable)]
 #[table_name = "user"]
@@ -20,3 +23,178 @@ impl<'a> NewUser<'a> {
         }
     }
 }
+
+#[derive(Insertable)]
+#[diesel(table_name = refresh_token)]
+pub struct NewRefreshToken<'a> {
+    pub token_hash: &'a str,
+    pub user_id: &'a str,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+impl<'a> NewRefreshToken<'a> {
+    pub fn new(
+        token_hash: &'a str,
+        user_id: &'a str,
+        expires_at: NaiveDateTime,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        NewRefreshToken {
+            token_hash,
+            user_id,
+            expires_at,
+            created_at,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = diary_revision)]
+pub struct NewDiaryRevision<'a> {
+    pub user_id: &'a str,
+    pub entry_date: NaiveDate,
+    pub diary_id: i32,
+    pub content: String,
+    pub target_index: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl<'a> NewDiaryRevision<'a> {
+    pub fn new(
+        user_id: &'a str,
+        entry_date: NaiveDate,
+        diary_id: i32,
+        content: String,
+        target_index: i32,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        NewDiaryRevision {
+            user_id,
+            entry_date,
+            diary_id,
+            content,
+            target_index,
+            created_at,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = display_session)]
+pub struct NewDisplaySession<'a> {
+    pub display_id: &'a str,
+    pub pairing_code: Option<&'a str>,
+    pub created_at: NaiveDateTime,
+}
+
+impl<'a> NewDisplaySession<'a> {
+    pub fn new(
+        display_id: &'a str,
+        pairing_code: Option<&'a str>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        NewDisplaySession {
+            display_id,
+            pairing_code,
+            created_at,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = mutation_job)]
+pub struct NewMutationJob<'a> {
+    pub job_id: &'a str,
+    pub user_id: &'a str,
+    pub entry_date: NaiveDate,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl<'a> NewMutationJob<'a> {
+    pub fn new(
+        job_id: &'a str,
+        user_id: &'a str,
+        entry_date: NaiveDate,
+        content: String,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        NewMutationJob {
+            job_id,
+            user_id,
+            entry_date,
+            content,
+            created_at,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = mutation_job_task)]
+pub struct NewMutationJobTask<'a> {
+    pub job_id: &'a str,
+    pub persona_id: i32,
+    pub status: &'a str,
+    pub attempts: i32,
+    pub updated_at: NaiveDateTime,
+}
+
+impl<'a> NewMutationJobTask<'a> {
+    pub fn new(
+        job_id: &'a str,
+        persona_id: i32,
+        status: &'a str,
+        attempts: i32,
+        updated_at: NaiveDateTime,
+    ) -> Self {
+        NewMutationJobTask {
+            job_id,
+            persona_id,
+            status,
+            attempts,
+            updated_at,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = completion_cache)]
+pub struct NewCompletionCache<'a> {
+    pub cache_key: &'a str,
+    pub model: &'a str,
+    pub output: &'a str,
+    pub created_at: NaiveDateTime,
+}
+
+impl<'a> NewCompletionCache<'a> {
+    pub fn new(
+        cache_key: &'a str,
+        model: &'a str,
+        output: &'a str,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        NewCompletionCache {
+            cache_key,
+            model,
+            output,
+            created_at,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = access_revocation)]
+pub struct NewAccessRevocation<'a> {
+    pub user_id: &'a str,
+    pub revoked_at: NaiveDateTime,
+}
+
+impl<'a> NewAccessRevocation<'a> {
+    pub fn new(user_id: &'a str, revoked_at: NaiveDateTime) -> Self {
+        NewAccessRevocation {
+            user_id,
+            revoked_at,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::r2d2::ConnectionManager;
+use r2d2::PooledConnection;
+
+use crate::domain::entity::diary::DiaryId;
+use crate::domain::entity::persona::Persona;
+use crate::domain::error::DomainError;
+use crate::domain::repository::persona::PersonaRepository;
+use crate::infrastructure::database::init::DbPool;
+use crate::schema::persona::{self as persona_schema};
+
+#[derive(Clone)]
+pub struct PersonaRepositoryImpl {
+    pub pool: DbPool,
+}
+
+impl PersonaRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self { Self { pool } }
+
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DomainError> {
+        self.pool
+            .get()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+}
+
+#[async_trait]
+impl PersonaRepository for PersonaRepositoryImpl {
+    async fn find_enabled(&self) -> Result<Vec<Persona>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let personas = InternalPersonaRepository::find_enabled(&mut connection).await?;
+        Ok(personas)
+    }
+}
+
+#[derive(Debug, Queryable)]
+struct PersonaRow {
+    persona_id: i32,
+    name: String,
+    prompt: String,
+    display_order: i32,
+}
+
+pub struct InternalPersonaRepository;
+
+impl InternalPersonaRepository {
+    pub async fn find_enabled(conn: &mut MysqlConnection) -> Result<Vec<Persona>, DomainError> {
+        let persona_rows: Vec<PersonaRow> = persona_schema::table
+            .filter(persona_schema::is_enabled.eq(true))
+            .order_by((
+                persona_schema::display_order.asc(),
+                persona_schema::persona_id.asc(),
+            ))
+            .select((
+                persona_schema::persona_id,
+                persona_schema::name,
+                persona_schema::prompt,
+                persona_schema::display_order,
+            ))
+            .load::<PersonaRow>(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        persona_rows
+            .into_iter()
+            .map(|row| {
+                Ok(Persona::new(
+                    DiaryId::new(row.persona_id)?,
+                    row.name,
+                    row.prompt,
+                    row.display_order,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use tokio;
+
+    use super::*;
+
+    // テスト用のデータベース接続プールを作成
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    #[tokio::test]
+    async fn test_find_enabled_personas() {
+        let pool = create_test_db_pool();
+        let repo = PersonaRepositoryImpl::new(pool);
+
+        let personas = repo.find_enabled().await;
+
+        assert!(personas.is_ok(), "Failed to find personas: {:?}", personas);
+        let personas = personas.unwrap();
+        assert!(personas
+            .windows(2)
+            .all(|pair| pair[0].display_order() <= pair[1].display_order()));
+    }
+}
@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::r2d2::ConnectionManager;
+use r2d2::PooledConnection;
+use serde_json;
+
+use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+use crate::domain::entity::revision::DiaryRevision;
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+use crate::domain::repository::revision::DiaryRevisionRepository;
+use crate::infrastructure::database::init::DbPool;
+use crate::infrastructure::database::models::NewDiaryRevision;
+use crate::schema::diary_revision::{self as revision_schema};
+
+#[derive(Clone)]
+pub struct DiaryRevisionRepositoryImpl {
+    pub pool: DbPool,
+}
+
+impl DiaryRevisionRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self { Self { pool } }
+
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DomainError> {
+        self.pool
+            .get()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+}
+
+#[async_trait]
+impl DiaryRevisionRepository for DiaryRevisionRepositoryImpl {
+    async fn append(&self, user_id: &UserId, revision: &DiaryRevision) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalDiaryRevisionRepository::append(user_id, revision, &mut connection).await?;
+        Ok(())
+    }
+
+    async fn find_by_user(&self, user_id: &UserId) -> Result<Vec<DiaryRevision>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let revisions =
+            InternalDiaryRevisionRepository::find_by_user(user_id, &mut connection).await?;
+        Ok(revisions)
+    }
+}
+
+#[derive(Debug, Queryable)]
+struct DiaryRevisionRow {
+    entry_date: NaiveDate,
+    diary_id: i32,
+    content: String,
+    target_index: i32,
+    created_at: NaiveDateTime,
+}
+
+pub struct InternalDiaryRevisionRepository;
+
+impl InternalDiaryRevisionRepository {
+    pub async fn append(
+        user_id: &UserId,
+        revision: &DiaryRevision,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        let new_revision = NewDiaryRevision::new(
+            user_id.as_str(),
+            *revision.entry_date(),
+            revision.diary().id().to_id(),
+            revision.diary().content().to_json(),
+            *revision.target_index(),
+            *revision.created_at(),
+        );
+        diesel::insert_into(revision_schema::table)
+            .values(new_revision)
+            .execute(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+
+    pub async fn find_by_user(
+        user_id: &UserId,
+        conn: &mut MysqlConnection,
+    ) -> Result<Vec<DiaryRevision>, DomainError> {
+        let revision_rows: Vec<DiaryRevisionRow> = revision_schema::table
+            .filter(revision_schema::user_id.eq(user_id.as_str()))
+            .order_by(revision_schema::revision_id.asc())
+            .select((
+                revision_schema::entry_date,
+                revision_schema::diary_id,
+                revision_schema::content,
+                revision_schema::target_index,
+                revision_schema::created_at,
+            ))
+            .load::<DiaryRevisionRow>(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        revision_rows
+            .into_iter()
+            .map(|row| {
+                // content には JSON 文字列として保存している
+                let text: String = serde_json::from_str(&row.content)
+                    .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+                let diary = Diary::new(DiaryId::new(row.diary_id)?, DiaryContent::new(text)?)?;
+                Ok(DiaryRevision::new(
+                    row.entry_date,
+                    diary,
+                    row.target_index,
+                    row.created_at,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use chrono::Utc;
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use tokio;
+
+    use super::*;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::user::UserRepositoryImpl;
+
+    // テスト用のデータベース接続プールを作成
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    #[tokio::test]
+    async fn test_append_and_list_revisions() {
+        let pool = create_test_db_pool();
+        let user_repository = UserRepositoryImpl::new(pool.clone());
+        let repo = DiaryRevisionRepositoryImpl::new(pool);
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+
+        for (id, text) in [(0, "一文目．"), (1, "いちぶんめ．")] {
+            let diary = Diary::new(
+                DiaryId::new(id).unwrap(),
+                DiaryContent::new(text.to_string()).unwrap(),
+            )
+            .unwrap();
+            let revision =
+                DiaryRevision::new(Utc::now().date_naive(), diary, 0, Utc::now().naive_utc());
+            repo.append(&user_id, &revision).await.unwrap();
+        }
+
+        let revisions = repo.find_by_user(&user_id).await.unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].diary().id().to_id(), 0);
+        assert_eq!(revisions[1].diary().content().to_value(), "いちぶんめ．");
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+}
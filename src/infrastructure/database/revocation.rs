@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::r2d2::ConnectionManager;
+use r2d2::PooledConnection;
+
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+use crate::domain::repository::revocation::AccessRevocationRepository;
+use crate::infrastructure::database::init::DbPool;
+use crate::infrastructure::database::models::NewAccessRevocation;
+use crate::schema::access_revocation::{self as revocation_schema};
+
+#[derive(Clone)]
+pub struct AccessRevocationRepositoryImpl {
+    pub pool: DbPool,
+}
+
+impl AccessRevocationRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self { Self { pool } }
+
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DomainError> {
+        self.pool
+            .get()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+}
+
+#[async_trait]
+impl AccessRevocationRepository for AccessRevocationRepositoryImpl {
+    async fn save(&self, user_id: &UserId, revoked_at: NaiveDateTime) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalAccessRevocationRepository::save(user_id, revoked_at, &mut connection).await
+    }
+
+    async fn find_after(
+        &self,
+        revoked_after: NaiveDateTime,
+    ) -> Result<Vec<(UserId, NaiveDateTime)>, DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalAccessRevocationRepository::find_after(revoked_after, &mut connection).await
+    }
+}
+
+pub struct InternalAccessRevocationRepository;
+
+impl InternalAccessRevocationRepository {
+    pub async fn save(
+        user_id: &UserId,
+        revoked_at: NaiveDateTime,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        diesel::replace_into(revocation_schema::table)
+            .values(NewAccessRevocation::new(user_id.as_str(), revoked_at))
+            .execute(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+
+    pub async fn find_after(
+        revoked_after: NaiveDateTime,
+        conn: &mut MysqlConnection,
+    ) -> Result<Vec<(UserId, NaiveDateTime)>, DomainError> {
+        let rows = revocation_schema::table
+            .filter(revocation_schema::revoked_at.ge(revoked_after))
+            .select((revocation_schema::user_id, revocation_schema::revoked_at))
+            .load::<(String, NaiveDateTime)>(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        rows.into_iter()
+            .map(|(user_id, revoked_at)| Ok((UserId::new(user_id)?, revoked_at)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use chrono::{Duration, Utc};
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use tokio;
+
+    use super::*;
+
+    // テスト用のデータベース接続プールを作成
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    #[tokio::test]
+    async fn test_save_and_find_revocation() {
+        let pool = create_test_db_pool();
+        let repo = AccessRevocationRepositoryImpl::new(pool);
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        let an_hour_ago = Utc::now().naive_utc() - Duration::hours(1);
+        let revoked_at = Utc::now().naive_utc() - Duration::minutes(1);
+
+        repo.save(&user_id, an_hour_ago - Duration::hours(1))
+            .await
+            .unwrap();
+        assert!(!repo
+            .find_after(an_hour_ago)
+            .await
+            .unwrap()
+            .iter()
+            .any(|(found, _)| found == &user_id));
+
+        // 同じユーザーを失効させ直すと時刻を置き換える
+        repo.save(&user_id, revoked_at).await.unwrap();
+        assert!(repo
+            .find_after(an_hour_ago)
+            .await
+            .unwrap()
+            .iter()
+            .any(|(found, _)| found == &user_id));
+    }
+}
@@ -0,0 +1,204 @@
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::r2d2::ConnectionManager;
+use r2d2::PooledConnection;
+
+use crate::domain::entity::token::{RefreshToken, RefreshTokenHash};
+use crate::domain::entity::user::UserId;
+use crate::domain::error::DomainError;
+use crate::domain::repository::token::RefreshTokenRepository;
+use crate::infrastructure::database::init::DbPool;
+use crate::infrastructure::database::models::NewRefreshToken;
+use crate::schema::refresh_token::{self as token_schema};
+
+#[derive(Clone)]
+pub struct RefreshTokenRepositoryImpl {
+    pub pool: DbPool,
+}
+
+impl RefreshTokenRepositoryImpl {
+    pub fn new(pool: DbPool) -> Self { Self { pool } }
+
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, DomainError> {
+        self.pool
+            .get()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepository for RefreshTokenRepositoryImpl {
+    async fn create(&self, token: &RefreshToken) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalRefreshTokenRepository::create(token, &mut connection).await?;
+        Ok(())
+    }
+
+    async fn find_by_hash(
+        &self,
+        hash: &RefreshTokenHash,
+    ) -> Result<Option<RefreshToken>, DomainError> {
+        let mut connection = self.get_connection()?;
+        let token = InternalRefreshTokenRepository::find_by_hash(hash, &mut connection).await?;
+        Ok(token)
+    }
+
+    async fn revoke(&self, hash: &RefreshTokenHash) -> Result<bool, DomainError> {
+        let mut connection = self.get_connection()?;
+        let revoked = InternalRefreshTokenRepository::revoke(hash, &mut connection).await?;
+        Ok(revoked)
+    }
+
+    async fn revoke_all(&self, user_id: &UserId) -> Result<(), DomainError> {
+        let mut connection = self.get_connection()?;
+        InternalRefreshTokenRepository::revoke_all(user_id, &mut connection).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Queryable)]
+struct RefreshTokenRow {
+    token_hash: String,
+    user_id: String,
+    expires_at: NaiveDateTime,
+    revoked_at: Option<NaiveDateTime>,
+}
+
+pub struct InternalRefreshTokenRepository;
+
+impl InternalRefreshTokenRepository {
+    pub async fn create(
+        token: &RefreshToken,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        let current_time = Utc::now().naive_utc();
+        let new_token = NewRefreshToken::new(
+            token.hash().as_str(),
+            token.user_id().as_str(),
+            *token.expires_at(),
+            current_time,
+        );
+        diesel::insert_into(token_schema::table)
+            .values(new_token)
+            .execute(conn)
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+
+    pub async fn find_by_hash(
+        hash: &RefreshTokenHash,
+        conn: &mut MysqlConnection,
+    ) -> Result<Option<RefreshToken>, DomainError> {
+        let token_row: Option<RefreshTokenRow> = token_schema::table
+            .find(hash.as_str())
+            .select((
+                token_schema::token_hash,
+                token_schema::user_id,
+                token_schema::expires_at,
+                token_schema::revoked_at,
+            ))
+            .first::<RefreshTokenRow>(conn)
+            .optional()
+            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+
+        let token = match token_row {
+            Some(row) => Some(RefreshToken::new(
+                RefreshTokenHash::from_hash(row.token_hash),
+                UserId::new(row.user_id)?,
+                row.expires_at,
+                row.revoked_at,
+            )),
+            None => None,
+        };
+        Ok(token)
+    }
+
+    // 同時に失効させようとしても、revoked_at が NULL の行を更新できるのは1つだけ
+    pub async fn revoke(
+        hash: &RefreshTokenHash,
+        conn: &mut MysqlConnection,
+    ) -> Result<bool, DomainError> {
+        let current_time = Utc::now().naive_utc();
+        let updated = diesel::update(
+            token_schema::table
+                .find(hash.as_str())
+                .filter(token_schema::revoked_at.is_null()),
+        )
+        .set(token_schema::revoked_at.eq(current_time))
+        .execute(conn)
+        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(updated == 1)
+    }
+
+    pub async fn revoke_all(
+        user_id: &UserId,
+        conn: &mut MysqlConnection,
+    ) -> Result<(), DomainError> {
+        let current_time = Utc::now().naive_utc();
+        diesel::update(
+            token_schema::table
+                .filter(token_schema::user_id.eq(user_id.as_str()))
+                .filter(token_schema::revoked_at.is_null()),
+        )
+        .set(token_schema::revoked_at.eq(current_time))
+        .execute(conn)
+        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use chrono::Duration;
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use tokio;
+
+    use super::*;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::user::UserRepositoryImpl;
+
+    // テスト用のデータベース接続プールを作成
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    #[tokio::test]
+    async fn test_create_and_revoke_refresh_token() {
+        let pool = create_test_db_pool();
+        let user_repository = UserRepositoryImpl::new(pool.clone());
+        let repo = RefreshTokenRepositoryImpl::new(pool);
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+
+        let hash = RefreshTokenHash::from_plain(&uuid::Uuid::new_v4().to_string());
+        let expires_at = (Utc::now() + Duration::hours(1)).naive_utc();
+        let token = RefreshToken::new(hash.clone(), user_id.clone(), expires_at, None);
+        repo.create(&token).await.unwrap();
+
+        let found = repo.find_by_hash(&hash).await.unwrap().unwrap();
+        assert_eq!(found.user_id(), &user_id);
+        assert!(!found.is_revoked());
+
+        // 2回目の失効は何も変えない
+        assert!(repo.revoke(&hash).await.unwrap());
+        assert!(!repo.revoke(&hash).await.unwrap());
+        repo.revoke_all(&user_id).await.unwrap();
+
+        let found = repo.find_by_hash(&hash).await.unwrap().unwrap();
+        assert!(found.is_revoked());
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+}
@@ -3,9 +3,8 @@ use chrono::{NaiveDateTime, Utc};
 use diesel::prelude::*;
 use diesel::r2d2::ConnectionManager;
 use r2d2::PooledConnection;
-use serde_json;
 
-use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+use crate::domain::entity::diary::DiaryId;
 use crate::domain::entity::user::{User, UserId};
 use crate::domain::error::DomainError;
 use crate::domain::repository::user::UserRepository;
@@ -44,27 +43,22 @@ impl UserRepository for UserRepositoryImpl {
         Ok(user)
     }
 
-    async fn find_current_user(&self) -> Result<Option<User>, DomainError> {
-        let mut connection = self.get_connection()?;
-        let user = InternalUserRepository::find_current_user(&mut connection).await?;
-        Ok(user)
-    }
-
-    async fn update_diary(&self, user_id: &UserId, diary: &Diary) -> Result<(), DomainError> {
-        let mut connection = self.get_connection()?;
-        InternalUserRepository::update_diary(user_id, diary, &mut connection).await?;
-        Ok(())
-    }
-
     async fn update_result(
         &self,
         user_id: &UserId,
         is_public: bool,
         favorite_id: &DiaryId,
+        version: i32,
     ) -> Result<(), DomainError> {
         let mut connection = self.get_connection()?;
-        InternalUserRepository::update_result(user_id, is_public, favorite_id, &mut connection)
-            .await?;
+        InternalUserRepository::update_result(
+            user_id,
+            is_public,
+            favorite_id,
+            version,
+            &mut connection,
+        )
+        .await?;
         Ok(())
     }
 
@@ -78,15 +72,11 @@ impl UserRepository for UserRepositoryImpl {
 #[derive(Debug, Queryable)]
 struct UserRow {
     user_id: String,
-    human_diary: Option<String>,
-    ai_diary_1: Option<String>,
-    ai_diary_2: Option<String>,
-    ai_diary_3: Option<String>,
-    ai_diary_4: Option<String>,
     is_public: Option<bool>,
     favorite_id: Option<i32>,
     created_at: NaiveDateTime,
     updated_at: NaiveDateTime,
+    version: i32,
 }
 
 pub struct InternalUserRepository;
@@ -112,214 +102,48 @@ impl InternalUserRepository {
             .optional()
             .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
 
-        let user: Option<User> = user_row.map(|row: UserRow| {
-            let user_id = UserId::new(row.user_id).unwrap();
-            let human_id = DiaryId::new(0).unwrap();
-
-            let human_diary: Option<Diary> = match row.human_diary {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-
-            let ai_diary_1: Option<Diary> = match row.ai_diary_1 {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-
-            let ai_diary_2: Option<Diary> = match row.ai_diary_2 {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-
-            let ai_diary_3: Option<Diary> = match row.ai_diary_3 {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-
-            let ai_diary_4: Option<Diary> = match row.ai_diary_4 {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-
-            let is_public = row.is_public;
-            let favorite_id = row.favorite_id.map(|id| DiaryId::new(id).unwrap());
-            let created_at = row.created_at;
-            let updated_at = row.updated_at;
-
-            User::new(
-                user_id,
-                human_diary,
-                ai_diary_1,
-                ai_diary_2,
-                ai_diary_3,
-                ai_diary_4,
-                is_public,
-                favorite_id,
-                created_at,
-                updated_at,
-            )
-        });
-        Ok(user)
+        match user_row {
+            Some(row) => Ok(Some(Self::to_user(row)?)),
+            None => Ok(None),
+        }
     }
 
-    pub async fn find_current_user(
-        conn: &mut MysqlConnection,
-    ) -> Result<Option<User>, DomainError> {
-        let user_row: Option<UserRow> = user_schema::dsl::user
-            .order_by(user_schema::created_at.desc())
-            .first::<UserRow>(conn)
-            .optional()
-            .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))?;
-
-        let user: Option<User> = user_row.map(|row: UserRow| {
-            let user_id = UserId::new(row.user_id).unwrap();
-            let human_id = DiaryId::new(0).unwrap();
-            let human_diary: Option<Diary> = match row.human_diary {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-            let ai_diary_1: Option<Diary> = match row.ai_diary_1 {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-            let ai_diary_2: Option<Diary> = match row.ai_diary_2 {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-            let ai_diary_3: Option<Diary> = match row.ai_diary_3 {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-            let ai_diary_4: Option<Diary> = match row.ai_diary_4 {
-                Some(json_str) => {
-                    let text: String = serde_json::from_str(&json_str)
-                        .map_err(|err| DomainError::InfrastructureError(anyhow::anyhow!(err)))
-                        .unwrap();
-                    let content = DiaryContent::new(text).unwrap();
-                    Some(Diary::new(human_id.clone(), content).unwrap())
-                },
-                None => None,
-            };
-            let is_public = row.is_public;
-            let favorite_id = row.favorite_id.map(|id| DiaryId::new(id).unwrap());
-            let created_at = row.created_at;
-            let updated_at = row.updated_at;
-
-            User::new(
-                user_id,
-                human_diary,
-                ai_diary_1,
-                ai_diary_2,
-                ai_diary_3,
-                ai_diary_4,
-                is_public,
-                favorite_id,
-                created_at,
-                updated_at,
-            )
-        });
-        Ok(user)
-    }
-
-    pub async fn update_diary(
-        user_id: &UserId,
-        diary: &Diary,
-        conn: &mut MysqlConnection,
-    ) -> Result<(), DomainError> {
-        let target_diary_column = match diary.id().to_id() {
-            0 => "human_diary",
-            1 => "ai_diary_1",
-            2 => "ai_diary_2",
-            3 => "ai_diary_3",
-            4 => "ai_diary_4",
-            _ => {
-                return Err(DomainError::InfrastructureError(anyhow::anyhow!(
-                    "invalid target id"
-                )))
-            },
-        };
-
-        let query = format!(
-            "UPDATE user SET {} = ? WHERE user_id = ?",
-            target_diary_column
-        );
-
-        diesel::sql_query(query)
-            .bind::<diesel::sql_types::Text, _>(&diary.content().to_json())
-            .bind::<diesel::sql_types::Text, _>(user_id.as_str())
-            .execute(conn)
-            .map_err(|e| DomainError::InfrastructureError(anyhow::anyhow!(e)))?;
-
-        Ok(())
+    fn to_user(row: UserRow) -> Result<User, DomainError> {
+        Ok(User::new(
+            UserId::new(row.user_id)?,
+            row.is_public,
+            row.favorite_id.map(DiaryId::new).transpose()?,
+            row.created_at,
+            row.updated_at,
+            row.version,
+        ))
     }
 
     pub async fn update_result(
         user_id: &UserId,
         is_public: bool,
         favorite_id: &DiaryId,
+        version: i32,
         conn: &mut MysqlConnection,
     ) -> Result<(), DomainError> {
-        diesel::update(user_schema::table.filter(user_schema::user_id.eq(user_id.as_str())))
-            .set((
-                user_schema::is_public.eq(is_public),
-                user_schema::favorite_id.eq(favorite_id.to_id()),
-            ))
-            .execute(conn)
-            .map_err(|error| DomainError::InfrastructureError(anyhow::anyhow!(error)))?;
+        let updated = diesel::update(
+            user_schema::table
+                .filter(user_schema::user_id.eq(user_id.as_str()))
+                .filter(user_schema::version.eq(version)),
+        )
+        .set((
+            user_schema::is_public.eq(is_public),
+            user_schema::favorite_id.eq(favorite_id.to_id()),
+            user_schema::version.eq(user_schema::version + 1),
+        ))
+        .execute(conn)
+        .map_err(|error| DomainError::InfrastructureError(anyhow::anyhow!(error)))?;
+        if updated == 0 {
+            return Err(DomainError::Conflict(format!(
+                "user {} was updated by another request",
+                user_id.as_str()
+            )));
+        }
         Ok(())
     }
 
@@ -380,34 +204,6 @@ mod tests {
         assert_eq!(found_user.unwrap().unwrap().id, user_id);
     }
 
-    #[tokio::test]
-    async fn test_find_current() {
-        let pool = create_test_db_pool();
-        let repo = UserRepositoryImpl::new(pool);
-
-        let found_user = repo.find_current_user().await;
-
-        println!("{:?}", found_user);
-        assert!(found_user.is_ok(), "Failed to find user: {:?}", found_user);
-    }
-
-    #[tokio::test]
-    async fn test_update_diary() {
-        let pool = create_test_db_pool();
-        let repo = UserRepositoryImpl::new(pool);
-
-        let user_id = UserId::new("test_user_id".to_string()).unwrap();
-        // repo.create(&user_id).await.unwrap();
-
-        let diary_id = DiaryId::new(1).unwrap();
-        let diary_content = DiaryContent::new("Test diary entry".to_string()).unwrap();
-        let diary = Diary::new(diary_id, diary_content).unwrap();
-
-        let result = repo.update_diary(&user_id, &diary).await;
-
-        assert!(result.is_ok(), "Failed to update diary: {:?}", result);
-    }
-
     #[tokio::test]
     async fn test_update_result() {
         let pool = create_test_db_pool();
@@ -417,8 +213,15 @@ mod tests {
         // repo.create(&user_id).await.unwrap();
 
         let favorite_id = DiaryId::new(1).unwrap();
-        let result = repo.update_result(&user_id, true, &favorite_id).await;
+        let version = repo.find_by_id(&user_id).await.unwrap().unwrap().version;
+        let result = repo
+            .update_result(&user_id, true, &favorite_id, version)
+            .await;
 
         assert!(result.is_ok(), "Failed to update result: {:?}", result);
+        let stale = repo
+            .update_result(&user_id, false, &favorite_id, version)
+            .await;
+        assert!(matches!(stale, Err(DomainError::Conflict(_))));
     }
 }
@@ -1,8 +1,12 @@
+use std::env;
+use std::sync::Arc;
+
 use actix_cors::Cors;
 use actix_web::{middleware as actix_middleware, App, HttpServer};
 use dotenv::dotenv;
 use env_logger::Env;
 use infrastructure::database::init::create_pool;
+use log::warn;
 
 mod application;
 mod auth;
@@ -19,27 +23,112 @@ async fn main() -> std::io::Result<()> {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
     let pool = create_pool();
+    let jwt_keys = auth::keys::JwtKeys::from_env().expect("JWT signing key must be configured");
+    // ユーザー単位の失効は DB に保存し、再起動しても削除したユーザーのトークンを受け付けない
+    let revocation_store = auth::revocation::RevocationStore::new().with_store(Arc::new(
+        infrastructure::database::revocation::AccessRevocationRepositoryImpl::new(pool.clone()),
+    ));
+    revocation_store
+        .load()
+        .await
+        .expect("failed to load token revocations");
+    let admin_credential = auth::admin::AdminCredential::from_env();
+    if !admin_credential.is_enabled() {
+        warn!("ADMIN_CREDENTIAL is not set, admin tokens cannot be issued");
+    }
 
     let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
-    let openai_client = infrastructure::api::openai::OpenAiClient::new();
-    let mutate_service =
-        application::usecase::mutate::MutateUsecase::new(openai_client, user_repository.clone());
+    let token_repository =
+        infrastructure::database::token::RefreshTokenRepositoryImpl::new(pool.clone());
+    let diary_repository = infrastructure::database::diary::DiaryRepositoryImpl::new(pool.clone());
+    let revision_repository =
+        infrastructure::database::revision::DiaryRevisionRepositoryImpl::new(pool.clone());
+    let persona_repository =
+        infrastructure::database::persona::PersonaRepositoryImpl::new(pool.clone());
+    let display_repository =
+        infrastructure::database::display::DisplaySessionRepositoryImpl::new(pool.clone());
+    let job_repository =
+        infrastructure::database::job::MutationJobRepositoryImpl::new(pool.clone());
+    let diary_broadcaster = application::broadcast::DiaryBroadcaster::new();
+    let llm_config = infrastructure::api::provider::LlmConfig::from_env();
+    let llm_client = infrastructure::api::fallback::FallbackLlmClient::from_env(&llm_config);
+    let mut cached_llm_client =
+        infrastructure::api::cache::CachedLlmClient::from_env(llm_client.clone());
+    // LLM_CACHE_DB を指定すると、再起動してもキャッシュした応答を使えるように DB にも保存する
+    if env::var("LLM_CACHE_DB").is_ok_and(|enabled| enabled == "true") {
+        cached_llm_client = cached_llm_client.with_store(Arc::new(
+            infrastructure::database::completion::CompletionCacheRepositoryImpl::new(pool.clone()),
+        ));
+    }
+    let mutate_service = application::usecase::mutate::MutateUsecase::new(
+        cached_llm_client.clone(),
+        llm_config.model.clone(),
+        user_repository.clone(),
+        diary_repository.clone(),
+        revision_repository.clone(),
+        persona_repository.clone(),
+        diary_broadcaster.clone(),
+    );
+    let mutation_job_use_case = application::usecase::job::MutationJobUseCase::new(
+        job_repository.clone(),
+        user_repository.clone(),
+        persona_repository.clone(),
+        mutate_service.clone(),
+    );
+    // 前回のプロセスで処理中だったジョブを戻してから、キューのワーカーを起動する
+    mutation_job_use_case
+        .recover()
+        .await
+        .expect("failed to recover mutation jobs");
+    let mutation_workers = env::var("MUTATION_WORKERS")
+        .ok()
+        .and_then(|workers| workers.parse().ok())
+        .unwrap_or(application::usecase::job::DEFAULT_WORKERS);
+    Arc::new(mutation_job_use_case.clone()).spawn_workers(mutation_workers);
     let update_result_use_case =
         application::usecase::result::UpdateResultUseCase::new(user_repository.clone());
     let create_user_use_case =
         application::usecase::init::CreateUserUseCase::new(user_repository.clone());
-    let get_diary_use_case =
-        application::usecase::diary::GetDiaryUseCase::new(user_repository.clone());
-    let delete_user_use_case =
-        application::usecase::delete::DeleteUsecase::new(user_repository.clone());
+    let get_diary_use_case = application::usecase::diary::GetDiaryUseCase::new(
+        display_repository.clone(),
+        diary_repository.clone(),
+    );
+    let display_use_case =
+        application::usecase::display::DisplayUseCase::new(display_repository.clone());
+    let delete_user_use_case = application::usecase::delete::DeleteUsecase::new(
+        user_repository.clone(),
+        token_repository.clone(),
+    )
+    .with_sentence_cache(mutate_service.sentence_cache());
+    let get_personas_use_case =
+        application::usecase::persona::GetPersonasUseCase::new(persona_repository.clone());
+    let get_entries_use_case =
+        application::usecase::entry::GetEntriesUseCase::new(diary_repository.clone());
+    let get_revisions_use_case =
+        application::usecase::revision::GetRevisionsUseCase::new(revision_repository.clone());
+    let token_use_case = application::usecase::token::TokenUsecase::new(token_repository.clone());
+    let idempotency_store = presentation::idempotency::IdempotencyStore::from_env();
 
     HttpServer::new(move || {
         App::new()
             .app_data(actix_web::web::Data::new(mutate_service.clone()))
+            .app_data(actix_web::web::Data::new(mutation_job_use_case.clone()))
             .app_data(actix_web::web::Data::new(update_result_use_case.clone()))
             .app_data(actix_web::web::Data::new(create_user_use_case.clone()))
             .app_data(actix_web::web::Data::new(get_diary_use_case.clone()))
+            .app_data(actix_web::web::Data::new(display_use_case.clone()))
+            .app_data(actix_web::web::Data::new(diary_broadcaster.clone()))
             .app_data(actix_web::web::Data::new(delete_user_use_case.clone()))
+            .app_data(actix_web::web::Data::new(get_personas_use_case.clone()))
+            .app_data(actix_web::web::Data::new(get_entries_use_case.clone()))
+            .app_data(actix_web::web::Data::new(get_revisions_use_case.clone()))
+            .app_data(actix_web::web::Data::new(token_use_case.clone()))
+            .app_data(actix_web::web::Data::new(jwt_keys.clone()))
+            .app_data(actix_web::web::Data::new(revocation_store.clone()))
+            .app_data(actix_web::web::Data::new(admin_credential.clone()))
+            .app_data(actix_web::web::Data::new(idempotency_store.clone()))
+            .app_data(actix_web::web::Data::new(llm_client.clone()))
+            .app_data(actix_web::web::Data::new(cached_llm_client.clone()))
             .wrap(actix_middleware::Logger::default())
             .wrap(middleware::Logging)
             .wrap(
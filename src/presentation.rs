@@ -1,6 +1,13 @@
+pub mod admin;
 pub mod delete;
 pub mod diary;
+pub mod display;
+pub mod entry;
+pub mod idempotency;
 pub mod init;
 pub mod mutate;
+pub mod persona;
 pub mod result;
+pub mod revision;
 pub mod routes;
+pub mod token;
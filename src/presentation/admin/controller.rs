@@ -0,0 +1,60 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::request::AdminUserPath;
+use super::response::{LlmCacheSummary, LlmRouteSummary, LlmRoutesResponse, LlmRoutesResult};
+use crate::application::usecase::delete::DeleteUsecase;
+use crate::auth::revocation::RevocationStore;
+use crate::domain::entity::user::UserId;
+use crate::infrastructure::api::cache::CachedLlmClient;
+use crate::infrastructure::api::fallback::FallbackLlmClient;
+use crate::infrastructure::database::token::RefreshTokenRepositoryImpl;
+use crate::infrastructure::database::user::UserRepositoryImpl;
+
+// スタッフが来場者のセッションを強制的に終了させる
+pub async fn admin_delete_user_handler(
+    request_path: web::Path<AdminUserPath>,
+    delete_usecase: web::Data<DeleteUsecase<UserRepositoryImpl, RefreshTokenRepositoryImpl>>,
+    revocations: web::Data<RevocationStore>,
+) -> impl Responder {
+    let user_id = UserId::new(request_path.into_inner().user_id).unwrap();
+
+    match delete_usecase.delete_user(&user_id).await {
+        Ok(_) => match revocations.revoke_user(&user_id).await {
+            Ok(_) => HttpResponse::Ok().finish(),
+            Err(_) => HttpResponse::InternalServerError().json("Error Revoke Token"),
+        },
+        Err(_) => HttpResponse::InternalServerError().json("Error Delete User"),
+    }
+}
+
+// LLM の接続先ごとのサーキットブレーカーの状態と呼び出し回数、応答キャッシュのヒット数
+pub async fn admin_llm_routes_handler(
+    client: web::Data<FallbackLlmClient>,
+    cache: web::Data<CachedLlmClient<FallbackLlmClient>>,
+) -> impl Responder {
+    let routes = client
+        .stats()
+        .into_iter()
+        .map(|route| LlmRouteSummary {
+            name: route.name,
+            state: route.state.as_str().to_string(),
+            requests: route.requests,
+            failures: route.failures,
+            rejected: route.rejected,
+            active: route.active,
+        })
+        .collect();
+
+    let cache = cache.stats();
+
+    HttpResponse::Ok().json(LlmRoutesResponse {
+        result: LlmRoutesResult {
+            routes,
+            cache: LlmCacheSummary {
+                hits: cache.hits,
+                misses: cache.misses,
+                entries: cache.entries,
+            },
+        },
+    })
+}
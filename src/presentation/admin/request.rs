@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+pub struct AdminUserPath {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LlmRoutesResponse {
+    pub result: LlmRoutesResult,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LlmRoutesResult {
+    pub routes: Vec<LlmRouteSummary>,
+    pub cache: LlmCacheSummary,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LlmRouteSummary {
+    pub name: String,
+    pub state: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub rejected: u64,
+    pub active: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LlmCacheSummary {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
@@ -1,20 +1,25 @@
-use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_web::{web, HttpResponse, Responder};
 
 use crate::application::usecase::delete::DeleteUsecase;
-use crate::auth::jwt::get_user_id_from_req;
+use crate::auth::extractor::AuthenticatedUser;
+use crate::auth::revocation::RevocationStore;
+use crate::infrastructure::database::token::RefreshTokenRepositoryImpl;
 use crate::infrastructure::database::user::UserRepositoryImpl;
 
 pub async fn delete_handler(
-    req: HttpRequest,
-    delete_usecase: web::Data<DeleteUsecase<UserRepositoryImpl>>,
+    AuthenticatedUser { user_id, claims }: AuthenticatedUser,
+    delete_usecase: web::Data<DeleteUsecase<UserRepositoryImpl, RefreshTokenRepositoryImpl>>,
+    revocations: web::Data<RevocationStore>,
 ) -> impl Responder {
-    let user_id = match get_user_id_from_req(req) {
-        Ok(user_id) => user_id,
-        Err(_) => return HttpResponse::Unauthorized().finish(),
-    };
-
     match delete_usecase.delete_user(&user_id).await {
-        Ok(_) => HttpResponse::Ok().into(),
+        Ok(_) => {
+            // 削除後に手元のトークンで呼ばれても 401 を返せるよう失効させる
+            revocations.revoke_token(&claims);
+            match revocations.revoke_user(&user_id).await {
+                Ok(_) => HttpResponse::Ok().into(),
+                Err(_) => HttpResponse::InternalServerError().json("Error Revoke Token"),
+            }
+        },
         Err(_) => HttpResponse::InternalServerError().json("Error Delete User"),
     }
 }
@@ -25,12 +30,16 @@ mod tests {
 
     use actix_web::body::MessageBody;
     use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
-    use actix_web::{test, web, App};
-    use chrono::{Duration, Utc};
+    use actix_web::{http, test, web, App};
+    use chrono::Duration;
     use diesel::r2d2::ConnectionManager;
     use diesel::MysqlConnection;
 
     use super::delete_handler;
+    use crate::auth::jwt::Claims;
+    use crate::auth::keys::JwtKeys;
+    use crate::auth::revocation::RevocationStore;
+    use crate::domain::entity::user::UserId;
     use crate::infrastructure::database::init::DbPool;
     use crate::{application, infrastructure};
 
@@ -57,47 +66,35 @@ mod tests {
 
         // リポジトリとユースケースの設定
         let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
-        let delete_user_use_case =
-            application::usecase::delete::DeleteUsecase::new(user_repository.clone());
+        let token_repository =
+            infrastructure::database::token::RefreshTokenRepositoryImpl::new(pool.clone());
+        let delete_user_use_case = application::usecase::delete::DeleteUsecase::new(
+            user_repository.clone(),
+            token_repository,
+        );
 
         App::new()
             .app_data(web::Data::new(delete_user_use_case))
+            .app_data(web::Data::new(test_keys()))
+            .app_data(web::Data::new(RevocationStore::new()))
             .service(web::resource("/delete").route(web::post().to(delete_handler)))
     }
 
-    use jsonwebtoken::{encode, EncodingKey, Header};
-    use serde::{Deserialize, Serialize};
-
-    #[derive(Debug, Serialize, Deserialize)]
-    struct Claims {
-        sub: String,
-        exp: usize,
+    fn test_keys() -> JwtKeys {
+        JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap()
     }
 
-    fn generate_test_jwt(user_id: &str, secret: &[u8]) -> String {
-        let expiration = Utc::now()
-            .checked_add_signed(Duration::hours(1))
-            .expect("valid timestamp")
-            .timestamp() as usize;
-
-        let claims = Claims {
-            sub: user_id.to_owned(),
-            exp: expiration,
-        };
-
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(secret),
-        )
-        .expect("token creation failed")
+    fn generate_test_jwt(user_id: &str, keys: &JwtKeys) -> String {
+        let user_id = UserId::new(user_id.to_string()).unwrap();
+        keys.encode(&Claims::new(&user_id, Duration::hours(1)))
+            .expect("token creation failed")
     }
 
     #[actix_rt::test]
     async fn test_delete_handler() {
         let app = test::init_service(setup_test_app()).await;
 
-        let token = generate_test_jwt("4ac32454-0c8d-4c3a-abef-a4dddd60415a", b"your_secret_key");
+        let token = generate_test_jwt("4ac32454-0c8d-4c3a-abef-a4dddd60415a", &test_keys());
 
         let request = test::TestRequest::post()
             .uri("/delete")
@@ -107,5 +104,14 @@ mod tests {
         let response = test::call_service(&app, request).await;
         println!("result:{}", response.status());
         assert!(response.status().is_success());
+
+        // 削除済みユーザーのトークンは失効している
+        let request = test::TestRequest::post()
+            .uri("/delete")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
     }
 }
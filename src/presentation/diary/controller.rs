@@ -1,44 +1,332 @@
-use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+use std::time::Duration;
 
-use super::request::DiaryRequestPath;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_ws::{Message, MessageStream, Session};
+use chrono::NaiveDate;
+use futures_util::{stream, StreamExt};
+
+use super::request::{DiaryRequestPath, DiaryRequestQuery};
 use super::response::{DiaryResponse, DiaryResult, MutatedLength};
+use crate::application::broadcast::{DiaryBroadcaster, DiaryReceiver, DiaryUpdate};
+use crate::application::error::ApplicationError;
 use crate::application::usecase::diary::GetDiaryUseCase;
-use crate::domain::entity::diary::DiaryId;
-use crate::infrastructure::database::user::UserRepositoryImpl;
+use crate::domain::entity::diary::{DiaryContent, DiaryId};
+use crate::domain::entity::display::DisplayId;
+use crate::domain::entity::status::{DiaryStatus, MutationState};
+use crate::domain::entity::user::UserId;
+use crate::domain::repository::diary::DiaryRepository;
+use crate::domain::repository::display::DisplaySessionRepository;
+
+const SSE_KEEP_ALIVE_SECONDS: u64 = 15;
 
-pub async fn diary_handler(
+// 表示端末は登録時に受け取った displayId をクエリで渡す
+pub async fn diary_handler<S: DisplaySessionRepository, D: DiaryRepository>(
     request_path: web::Path<DiaryRequestPath>,
-    diary_usecase: web::Data<GetDiaryUseCase<UserRepositoryImpl>>,
+    request_query: web::Query<DiaryRequestQuery>,
+    diary_usecase: web::Data<GetDiaryUseCase<S, D>>,
 ) -> impl Responder {
     let diary_id = DiaryId::new(request_path.into_inner().client_id).unwrap();
+    let display_id = DisplayId::new(request_query.into_inner().display_id);
+
+    match diary_usecase
+        .get_display_diary(&display_id, &diary_id)
+        .await
+    {
+        Ok((ai_content, human_content, status)) => HttpResponse::Ok().json(diary_response(
+            &ai_content,
+            human_content.to_length(),
+            status.as_ref(),
+        )),
+        Err(ApplicationError::NotFound { .. }) => HttpResponse::NotFound().json("Diary Not Found"),
+        Err(_) => HttpResponse::InternalServerError().json("Get Diary Error"),
+    }
+}
 
-    match diary_usecase.get_current_user_diary(&diary_id).await {
-        Ok((ai_content, human_content)) => HttpResponse::Ok().json(DiaryResponse {
-            result: DiaryResult {
-                diary: ai_content.to_value().clone(),
-                mutated_length: MutatedLength {
-                    ai: ai_content.to_length(),
-                    human: human_content.to_length(),
+// ポーリングの代わりに WebSocket で受け取る。送る内容は GET /diary/{clientId} と同じ形
+// 接続時に現在の日記を1回送り、その後はペルソナの書き換えが終わるたびに送る
+pub async fn diary_ws_handler<
+    S: DisplaySessionRepository + 'static,
+    D: DiaryRepository + 'static,
+>(
+    req: HttpRequest,
+    body: web::Payload,
+    request_path: web::Path<DiaryRequestPath>,
+    request_query: web::Query<DiaryRequestQuery>,
+    diary_usecase: web::Data<GetDiaryUseCase<S, D>>,
+    broadcaster: web::Data<DiaryBroadcaster>,
+) -> actix_web::Result<HttpResponse> {
+    // 接続を切り替える前に確かめ、不正な clientId には通常の応答を返す
+    let diary_id = match DiaryId::new(request_path.into_inner().client_id) {
+        Ok(diary_id) => diary_id,
+        Err(_) => return Ok(HttpResponse::BadRequest().json("Invalid clientId")),
+    };
+    let subscription = DisplaySubscription::new(
+        &broadcaster,
+        diary_usecase.into_inner(),
+        DisplayId::new(request_query.into_inner().display_id),
+        diary_id,
+    );
+
+    let (response, session, msg_stream) = actix_ws::handle(&req, body)?;
+    actix_web::rt::spawn(push_diary_updates(session, msg_stream, subscription));
+
+    Ok(response)
+}
+
+async fn push_diary_updates<S: DisplaySessionRepository, D: DiaryRepository>(
+    mut session: Session,
+    mut msg_stream: MessageStream,
+    mut subscription: DisplaySubscription<S, D>,
+) {
+    if let Some(response) = subscription.current().await {
+        if session
+            .text(serde_json::to_string(&response).unwrap())
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            message = msg_stream.next() => match message {
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                },
+                Some(Ok(Message::Close(reason))) => {
+                    let _ = session.close(reason).await;
+                    return;
                 },
+                Some(Ok(_)) => {},
+                _ => return,
             },
-        }),
-        Err(_) => HttpResponse::InternalServerError().json("Get Diary Error"),
+            update = subscription.next() => match update {
+                // 書き換え途中の更新は SSE でのみ流す
+                Some(update) if !*update.is_complete() => continue,
+                Some(update) => {
+                    let response = diary_response(update.diary().content(), *update.human_length(), None);
+                    if session.text(serde_json::to_string(&response).unwrap()).await.is_err() {
+                        return;
+                    }
+                },
+                None => {
+                    let _ = session.close(None).await;
+                    return;
+                },
+            },
+        }
+    }
+}
+
+// AIが書いている途中の文章を Server-Sent Events で流す
+// 書き換え途中は event: progress、保存した結果は event: complete で、data は GET /diary/{clientId} と同じ形
+pub async fn diary_stream_handler<
+    S: DisplaySessionRepository + 'static,
+    D: DiaryRepository + 'static,
+>(
+    request_path: web::Path<DiaryRequestPath>,
+    request_query: web::Query<DiaryRequestQuery>,
+    diary_usecase: web::Data<GetDiaryUseCase<S, D>>,
+    broadcaster: web::Data<DiaryBroadcaster>,
+) -> impl Responder {
+    let diary_id = match DiaryId::new(request_path.into_inner().client_id) {
+        Ok(diary_id) => diary_id,
+        Err(_) => return HttpResponse::BadRequest().json("Invalid clientId"),
+    };
+    let subscription = DisplaySubscription::new(
+        &broadcaster,
+        diary_usecase.into_inner(),
+        DisplayId::new(request_query.into_inner().display_id),
+        diary_id,
+    );
+
+    let events = stream::unfold(
+        (subscription, true),
+        |(mut subscription, is_first)| async move {
+            if is_first {
+                if let Some(response) = subscription.current().await {
+                    return Some((sse_event("complete", &response), (subscription, false)));
+                }
+            }
+            // 切断を検知できるよう、更新がなくても定期的にコメント行を送る
+            match tokio::time::timeout(
+                Duration::from_secs(SSE_KEEP_ALIVE_SECONDS),
+                subscription.next(),
+            )
+            .await
+            {
+                Ok(Some(update)) => {
+                    let event = if *update.is_complete() {
+                        "complete"
+                    } else {
+                        "progress"
+                    };
+                    let response =
+                        diary_response(update.diary().content(), *update.human_length(), None);
+                    Some((sse_event(event, &response), (subscription, false)))
+                },
+                Ok(None) => None,
+                Err(_) => Some((
+                    Ok(web::Bytes::from_static(b": keep-alive\n\n")),
+                    (subscription, false),
+                )),
+            }
+        },
+    );
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+fn sse_event(event: &str, response: &DiaryResponse) -> Result<web::Bytes, actix_web::Error> {
+    Ok(web::Bytes::from(format!(
+        "event: {}\ndata: {}\n\n",
+        event,
+        serde_json::to_string(response).unwrap()
+    )))
+}
+
+// 表示端末に紐付いた書き手の、指定したペルソナの更新だけを取り出す
+struct DisplaySubscription<S: DisplaySessionRepository, D: DiaryRepository> {
+    updates: DiaryReceiver,
+    diary_usecase: Arc<GetDiaryUseCase<S, D>>,
+    display_id: DisplayId,
+    diary_id: DiaryId,
+    paired_user: Option<UserId>,
+    showing_date: Option<NaiveDate>,
+}
+
+impl<S: DisplaySessionRepository, D: DiaryRepository> DisplaySubscription<S, D> {
+    // 現在の日記を送るまでの間の更新も取りこぼさないよう、先に購読しておく
+    fn new(
+        broadcaster: &DiaryBroadcaster,
+        diary_usecase: Arc<GetDiaryUseCase<S, D>>,
+        display_id: DisplayId,
+        diary_id: DiaryId,
+    ) -> Self {
+        Self {
+            updates: broadcaster.subscribe(),
+            diary_usecase,
+            display_id,
+            diary_id,
+            paired_user: None,
+            showing_date: None,
+        }
+    }
+
+    // 接続時点の日記。ペアリング前や日記がまだない場合は None
+    async fn current(&self) -> Option<DiaryResponse> {
+        self.diary_usecase
+            .get_display_diary(&self.display_id, &self.diary_id)
+            .await
+            .ok()
+            .map(|(ai_content, human_content, status)| {
+                diary_response(&ai_content, human_content.to_length(), status.as_ref())
+            })
+    }
+
+    // 配信が終わった (サーバーの停止) 場合は None
+    async fn next(&mut self) -> Option<DiaryUpdate> {
+        loop {
+            let update = self.updates.recv().await?;
+            if update.diary().id() != &self.diary_id {
+                continue;
+            }
+            // ペアリング前に接続した端末もあるので、紐付くまでは更新のたびに確かめる
+            if self.paired_user.is_none() {
+                self.paired_user = self
+                    .diary_usecase
+                    .find_paired_user(&self.display_id)
+                    .await
+                    .ok()
+                    .flatten();
+            }
+            if self.paired_user.as_ref() != Some(update.user_id()) {
+                continue;
+            }
+            // 表示中の日付より前のエントリを書き換えても、表示は変えない
+            // 新しい日付の保存が届いたら、以降はその日付を表示中とする
+            if self.showing_date.is_none() {
+                self.showing_date = self
+                    .diary_usecase
+                    .find_display_date(update.user_id())
+                    .await
+                    .ok()
+                    .flatten();
+            }
+            if self
+                .showing_date
+                .is_some_and(|showing_date| *update.entry_date() < showing_date)
+            {
+                continue;
+            }
+            if *update.is_complete() {
+                self.showing_date = Some(*update.entry_date());
+            }
+            return Some(update);
+        }
+    }
+}
+
+// 配信される更新は保存に成功したものだけなので、status がなければ ok とする
+fn diary_response(
+    ai_content: &DiaryContent,
+    human_length: i32,
+    status: Option<&DiaryStatus>,
+) -> DiaryResponse {
+    DiaryResponse {
+        result: DiaryResult {
+            diary: ai_content.to_value().clone(),
+            mutated_length: MutatedLength {
+                ai: ai_content.to_length(),
+                human: human_length,
+            },
+            status: status
+                .map_or(MutationState::Ok, |status| *status.state())
+                .as_str()
+                .to_string(),
+            reason: status.and_then(|status| status.reason().clone()),
+        },
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::env;
+    use std::future::poll_fn;
+    use std::pin::Pin;
 
     use actix_web::body::MessageBody;
-    use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
-    use actix_web::{test, web, App};
+    use actix_web::dev::{Payload, ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::error::PayloadError;
+    use actix_web::{http, test, web, App};
+    use chrono::{Days, Local, Utc};
     use diesel::r2d2::ConnectionManager;
     use diesel::MysqlConnection;
+    use futures_util::{stream, Stream};
     use serde_json::from_slice;
 
-    use super::diary_handler;
+    use super::{diary_handler, diary_stream_handler, diary_ws_handler};
+    use crate::application::broadcast::{DiaryBroadcaster, DiaryUpdate};
+    use crate::application::usecase::diary::GetDiaryUseCase;
+    use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+    use crate::domain::entity::display::{DisplayId, DisplaySession};
+    use crate::domain::entity::user::UserId;
+    use crate::domain::repository::diary::DiaryRepository;
+    use crate::domain::repository::display::DisplaySessionRepository;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::diary::DiaryRepositoryImpl;
+    use crate::infrastructure::database::display::DisplaySessionRepositoryImpl;
     use crate::infrastructure::database::init::DbPool;
+    use crate::infrastructure::database::memory::{
+        InMemoryDiaryRepository, InMemoryDisplaySessionRepository,
+    };
     use crate::presentation::diary::response::DiaryResponse;
     use crate::{application, infrastructure};
 
@@ -51,7 +339,9 @@ mod tests {
             .expect("Failed to create test pool.")
     }
 
-    fn setup_test_app() -> App<
+    fn setup_test_app(
+        pool: DbPool,
+    ) -> App<
         impl ServiceFactory<
             ServiceRequest,
             Response = ServiceResponse<impl MessageBody>,
@@ -60,24 +350,64 @@ mod tests {
             InitError = (),
         >,
     > {
-        // テスト用のデータベース接続プールの作成
-        let pool = create_test_db_pool();
-
         // リポジトリとユースケースの設定
-        let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
+        let display_repository =
+            infrastructure::database::display::DisplaySessionRepositoryImpl::new(pool.clone());
+        let diary_repository =
+            infrastructure::database::diary::DiaryRepositoryImpl::new(pool.clone());
         let get_diary_use_case =
-            application::usecase::diary::GetDiaryUseCase::new(user_repository.clone());
+            application::usecase::diary::GetDiaryUseCase::new(display_repository, diary_repository);
 
         App::new()
             .app_data(web::Data::new(get_diary_use_case))
-            .service(web::resource("/diary/{clientId}").route(web::get().to(diary_handler)))
+            .service(web::resource("/diary/{clientId}").route(
+                web::get().to(diary_handler::<DisplaySessionRepositoryImpl, DiaryRepositoryImpl>),
+            ))
     }
 
     #[actix_rt::test]
     async fn test_get_diary_handler() {
-        let app = test::init_service(setup_test_app()).await;
+        let pool = create_test_db_pool();
+        let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
+        let diary_repository =
+            infrastructure::database::diary::DiaryRepositoryImpl::new(pool.clone());
+        let display_repository =
+            infrastructure::database::display::DisplaySessionRepositoryImpl::new(pool.clone());
+        let app = test::init_service(setup_test_app(pool)).await;
 
-        let request = test::TestRequest::get().uri("/diary/3").to_request();
+        // 書き手の日記を用意し、表示端末とペアリングしておく
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        for id in [0, 3] {
+            let diary = Diary::new(
+                DiaryId::new(id).unwrap(),
+                DiaryContent::new("今日は晴れた．".to_string()).unwrap(),
+            )
+            .unwrap();
+            diary_repository
+                .update_all(&user_id, &Local::now().date_naive(), &[diary], &[])
+                .await
+                .unwrap();
+        }
+        let display_id = DisplayId::new(uuid::Uuid::new_v4().to_string());
+        let pairing_code = display_id.as_str()[..6].to_string();
+        display_repository
+            .create(&DisplaySession::new(
+                display_id.clone(),
+                Some(pairing_code.clone()),
+                None,
+                Utc::now().naive_utc(),
+            ))
+            .await
+            .unwrap();
+        display_repository
+            .pair(&display_id, &pairing_code, &user_id)
+            .await
+            .unwrap();
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/diary/3?displayId={}", display_id.as_str()))
+            .to_request();
 
         let response = test::call_service(&app, request).await;
         println!("status:{}", response.status());
@@ -91,7 +421,229 @@ mod tests {
         } else {
             println!("failed to parse response body as DiaryResponse");
         }
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+
+    // 表示端末とペアリングした書き手の日記を、メモリ上のリポジトリに用意する
+    async fn setup_memory_app() -> (
+        App<
+            impl ServiceFactory<
+                ServiceRequest,
+                Response = ServiceResponse<impl MessageBody>,
+                Config = (),
+                Error = actix_web::Error,
+                InitError = (),
+            >,
+        >,
+        DiaryBroadcaster,
+        UserId,
+        DisplayId,
+    ) {
+        let display_repository = InMemoryDisplaySessionRepository::new();
+        let diary_repository = InMemoryDiaryRepository::new();
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        diary_repository
+            .update_all(
+                &user_id,
+                &Local::now().date_naive(),
+                &[diary(0, "今日は晴れた．"), diary(3, "今日は晴れた．")],
+                &[],
+            )
+            .await
+            .unwrap();
+        let display_id = DisplayId::new(uuid::Uuid::new_v4().to_string());
+        display_repository
+            .create(&DisplaySession::new(
+                display_id.clone(),
+                Some("123456".to_string()),
+                None,
+                Utc::now().naive_utc(),
+            ))
+            .await
+            .unwrap();
+        display_repository
+            .pair(&display_id, "123456", &user_id)
+            .await
+            .unwrap();
+
+        let broadcaster = DiaryBroadcaster::new();
+        let app =
+            App::new()
+                .app_data(web::Data::new(GetDiaryUseCase::new(
+                    display_repository,
+                    diary_repository,
+                )))
+                .app_data(web::Data::new(broadcaster.clone()))
+                .service(web::resource("/diary/{clientId}/stream").route(
+                    web::get().to(diary_stream_handler::<
+                        InMemoryDisplaySessionRepository,
+                        InMemoryDiaryRepository,
+                    >),
+                ))
+                .service(web::resource("/ws/diary/{clientId}").route(web::get().to(
+                    diary_ws_handler::<InMemoryDisplaySessionRepository, InMemoryDiaryRepository>,
+                )));
+        (app, broadcaster, user_id, display_id)
+    }
+
+    fn diary(id: i32, text: &str) -> Diary {
+        Diary::new(
+            DiaryId::new(id).unwrap(),
+            DiaryContent::new(text.to_string()).unwrap(),
+        )
+        .unwrap()
     }
 
-    // 他のテストケースも同様に追加
+    fn update(user_id: &UserId, id: i32, text: &str, is_complete: bool) -> DiaryUpdate {
+        DiaryUpdate::new(
+            user_id.clone(),
+            Local::now().date_naive(),
+            diary(id, text),
+            7,
+            is_complete,
+        )
+    }
+
+    async fn next_chunk<B: MessageBody>(body: &mut Pin<Box<B>>) -> web::Bytes {
+        match poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+            Some(Ok(chunk)) => chunk,
+            _ => panic!("response body ended"),
+        }
+    }
+
+    // サーバーから送られる WebSocket のフレームはマスクされないので、長さの後がそのまま中身になる
+    async fn next_text_frame<B: MessageBody>(body: &mut Pin<Box<B>>, buf: &mut Vec<u8>) -> String {
+        loop {
+            if buf.len() >= 2 {
+                let (offset, length) = match buf[1] {
+                    126 if buf.len() >= 4 => (4, u16::from_be_bytes([buf[2], buf[3]]) as usize),
+                    126 => (usize::MAX, 0),
+                    length => (2, length as usize),
+                };
+                if offset != usize::MAX && buf.len() >= offset + length {
+                    assert_eq!(buf[0], 0x81);
+                    let frame: Vec<u8> = buf.drain(..offset + length).collect();
+                    return String::from_utf8(frame[offset..].to_vec()).unwrap();
+                }
+            }
+            buf.extend_from_slice(&next_chunk(body).await);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_diary_stream_handler_sends_progress_and_complete() {
+        let (app, broadcaster, user_id, display_id) = setup_memory_app().await;
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::get()
+            .uri(&format!(
+                "/diary/3/stream?displayId={}",
+                display_id.as_str()
+            ))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/event-stream"
+        );
+        let mut body = Box::pin(response.into_body());
+        // 接続時は現在の日記
+        let first = String::from_utf8(next_chunk(&mut body).await.to_vec()).unwrap();
+        assert!(first.starts_with("event: complete\n"));
+        assert!(first.contains("今日は晴れた．"));
+
+        // 他の書き手や他のペルソナの更新は流さない
+        let other_user = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        broadcaster.publish(update(&other_user, 3, "他人の日記", false));
+        broadcaster.publish(update(&user_id, 1, "別のペルソナ", false));
+        // 表示中より前の日付のエントリの書き換えも流さない
+        broadcaster.publish(DiaryUpdate::new(
+            user_id.clone(),
+            Local::now().date_naive() - Days::new(1),
+            diary(3, "昨日の日記"),
+            7,
+            true,
+        ));
+        broadcaster.publish(update(&user_id, 3, "今日は", false));
+        broadcaster.publish(update(&user_id, 3, "今日は雨だった．", true));
+
+        let progress = String::from_utf8(next_chunk(&mut body).await.to_vec()).unwrap();
+        assert!(progress.starts_with("event: progress\n"));
+        assert!(progress.contains("\"diary\":\"今日は\""));
+        let complete = String::from_utf8(next_chunk(&mut body).await.to_vec()).unwrap();
+        assert!(complete.starts_with("event: complete\n"));
+        assert!(complete.contains("今日は雨だった．"));
+    }
+
+    #[actix_rt::test]
+    async fn test_diary_stream_handler_rejects_invalid_client_id() {
+        let (app, _, _, display_id) = setup_memory_app().await;
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::get()
+            .uri(&format!(
+                "/diary/-1/stream?displayId={}",
+                display_id.as_str()
+            ))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_diary_ws_handler_pushes_complete_updates() {
+        let (app, broadcaster, user_id, display_id) = setup_memory_app().await;
+        let app = test::init_service(app).await;
+        // 受信側が閉じるまで接続を保つよう、終わらない payload を渡す
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<web::Bytes>();
+        let payload: Pin<Box<dyn Stream<Item = Result<web::Bytes, PayloadError>>>> =
+            Box::pin(stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|bytes| (Ok(bytes), receiver))
+            }));
+        let (request, _) = test::TestRequest::get()
+            .uri(&format!("/ws/diary/3?displayId={}", display_id.as_str()))
+            .insert_header(("upgrade", "websocket"))
+            .insert_header(("connection", "upgrade"))
+            .insert_header(("sec-websocket-version", "13"))
+            .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_request()
+            .replace_payload(Payload::Stream { payload });
+
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), http::StatusCode::SWITCHING_PROTOCOLS);
+        let mut body = Box::pin(response.into_body());
+        let mut buf = vec![];
+        let first: DiaryResponse =
+            serde_json::from_str(&next_text_frame(&mut body, &mut buf).await).unwrap();
+        assert_eq!(first.result.diary, "今日は晴れた．");
+
+        // 書き換え途中の更新は送らず、保存した結果だけを送る
+        broadcaster.publish(update(&user_id, 3, "今日は", false));
+        broadcaster.publish(update(&user_id, 3, "今日は雨だった．", true));
+        let pushed: DiaryResponse =
+            serde_json::from_str(&next_text_frame(&mut body, &mut buf).await).unwrap();
+        assert_eq!(pushed.result.diary, "今日は雨だった．");
+        assert_eq!(pushed.result.status, "ok");
+        drop(sender);
+    }
+
+    #[actix_rt::test]
+    async fn test_diary_ws_handler_rejects_invalid_client_id() {
+        let (app, _, _, display_id) = setup_memory_app().await;
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::get()
+            .uri(&format!("/ws/diary/-1?displayId={}", display_id.as_str()))
+            .insert_header(("upgrade", "websocket"))
+            .insert_header(("connection", "upgrade"))
+            .insert_header(("sec-websocket-version", "13"))
+            .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_request();
+
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+    }
 }
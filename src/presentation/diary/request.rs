@@ -5,3 +5,9 @@ pub struct DiaryRequestPath {
     #[serde(rename = "clientId")]
     pub client_id: i32,
 }
+
+#[derive(Deserialize, Clone)]
+pub struct DiaryRequestQuery {
+    #[serde(rename = "displayId")]
+    pub display_id: String,
+}
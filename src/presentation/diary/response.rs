@@ -11,6 +11,8 @@ pub struct DiaryResult {
     pub diary: String,
     #[serde(rename = "mutatedLength")]
     pub mutated_length: MutatedLength,
+    pub status: String,
+    pub reason: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
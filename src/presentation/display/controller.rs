@@ -0,0 +1,150 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::request::PairRequest;
+use super::response::{PairResponse, RegisterDisplayResponse};
+use crate::application::error::ApplicationError;
+use crate::application::usecase::display::DisplayUseCase;
+use crate::auth::extractor::AuthenticatedUser;
+use crate::infrastructure::database::display::DisplaySessionRepositoryImpl;
+
+// 表示端末の起動時に呼ぶ。pairingCode を画面に表示し、displayId は /diary の取得に使う
+pub async fn register_display_handler(
+    display_usecase: web::Data<DisplayUseCase<DisplaySessionRepositoryImpl>>,
+) -> impl Responder {
+    match display_usecase.register().await {
+        Ok(session) => HttpResponse::Ok().json(RegisterDisplayResponse {
+            display_id: session.id().as_str().to_string(),
+            pairing_code: session.pairing_code().clone().unwrap_or_default(),
+        }),
+        Err(_) => HttpResponse::InternalServerError().json("Error Register Display"),
+    }
+}
+
+// 書き手が表示端末のペアリングコードを入力して、自分の日記を表示させる
+pub async fn pair_display_handler(
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    display_usecase: web::Data<DisplayUseCase<DisplaySessionRepositoryImpl>>,
+    body: web::Json<PairRequest>,
+) -> impl Responder {
+    match display_usecase.pair(&user_id, &body.pairing_code).await {
+        Ok(display_id) => HttpResponse::Ok().json(PairResponse {
+            display_id: display_id.as_str().to_string(),
+        }),
+        Err(ApplicationError::NotFound { .. }) => {
+            HttpResponse::NotFound().json("Pairing Code Not Found")
+        },
+        Err(ApplicationError::Validation(message)) => HttpResponse::BadRequest().json(message),
+        Err(ApplicationError::TooManyRequests(message)) => {
+            HttpResponse::TooManyRequests().json(message)
+        },
+        Err(_) => HttpResponse::InternalServerError().json("Error Pair Display"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use actix_web::body::MessageBody;
+    use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::{http, test, web, App};
+    use chrono::Duration;
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use serde_json::json;
+
+    use super::{pair_display_handler, register_display_handler};
+    use crate::auth::jwt::Claims;
+    use crate::auth::keys::JwtKeys;
+    use crate::auth::revocation::RevocationStore;
+    use crate::domain::entity::user::UserId;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::init::DbPool;
+    use crate::presentation::display::response::{PairResponse, RegisterDisplayResponse};
+    use crate::{application, infrastructure};
+
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    fn setup_test_app(
+        pool: DbPool,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Response = ServiceResponse<impl MessageBody>,
+            Config = (),
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        let display_repository =
+            infrastructure::database::display::DisplaySessionRepositoryImpl::new(pool);
+        let display_use_case =
+            application::usecase::display::DisplayUseCase::new(display_repository);
+
+        App::new()
+            .app_data(web::Data::new(display_use_case))
+            .app_data(web::Data::new(test_keys()))
+            .app_data(web::Data::new(RevocationStore::new()))
+            .service(web::resource("/display").route(web::post().to(register_display_handler)))
+            .service(web::resource("/display/pair").route(web::post().to(pair_display_handler)))
+    }
+
+    fn test_keys() -> JwtKeys {
+        JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap()
+    }
+
+    fn generate_test_jwt(user_id: &UserId, keys: &JwtKeys) -> String {
+        keys.encode(&Claims::new(user_id, Duration::hours(1)))
+            .expect("token creation failed")
+    }
+
+    #[actix_rt::test]
+    async fn test_register_and_pair_display() {
+        let pool = create_test_db_pool();
+        let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
+        let app = test::init_service(setup_test_app(pool)).await;
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+
+        let request = test::TestRequest::post().uri("/display").to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let registered: RegisterDisplayResponse = test::read_body_json(response).await;
+
+        let request = test::TestRequest::post()
+            .uri("/display/pair")
+            .insert_header((
+                "Authorization",
+                format!("Bearer {}", generate_test_jwt(&user_id, &test_keys())),
+            ))
+            .set_json(json!({ "pairingCode": registered.pairing_code }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let paired: PairResponse = test::read_body_json(response).await;
+        assert_eq!(paired.display_id, registered.display_id);
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_pair_display_unauthorized() {
+        let app = test::init_service(setup_test_app(create_test_db_pool())).await;
+
+        let request = test::TestRequest::post()
+            .uri("/display/pair")
+            .set_json(json!({ "pairingCode": "000000" }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+pub struct PairRequest {
+    #[serde(rename = "pairingCode")]
+    pub pairing_code: String,
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RegisterDisplayResponse {
+    #[serde(rename = "displayId")]
+    pub display_id: String,
+    #[serde(rename = "pairingCode")]
+    pub pairing_code: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PairResponse {
+    #[serde(rename = "displayId")]
+    pub display_id: String,
+}
@@ -0,0 +1,181 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::request::EntryRequestPath;
+use super::response::{EntriesResponse, EntriesResult, EntryDiary, EntryResponse, EntrySummary};
+use crate::application::error::ApplicationError;
+use crate::application::usecase::entry::GetEntriesUseCase;
+use crate::auth::extractor::AuthenticatedUser;
+use crate::domain::entity::diary::DiaryId;
+use crate::domain::entity::entry::DiaryEntry;
+use crate::infrastructure::database::diary::DiaryRepositoryImpl;
+
+// 認証中のユーザーの日記を新しい日付順に返す
+pub async fn entries_handler(
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    entries_usecase: web::Data<GetEntriesUseCase<DiaryRepositoryImpl>>,
+) -> impl Responder {
+    match entries_usecase.get_entries(&user_id).await {
+        Ok(entries) => HttpResponse::Ok().json(EntriesResponse {
+            result: EntriesResult {
+                entries: entries.iter().map(to_summary).collect(),
+            },
+        }),
+        Err(_) => HttpResponse::InternalServerError().json("Get Entries Error"),
+    }
+}
+
+pub async fn entry_handler(
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    request_path: web::Path<EntryRequestPath>,
+    entries_usecase: web::Data<GetEntriesUseCase<DiaryRepositoryImpl>>,
+) -> impl Responder {
+    let entry_date = request_path.into_inner().date;
+
+    match entries_usecase.get_entry(&user_id, &entry_date).await {
+        Ok(entry) => HttpResponse::Ok().json(EntryResponse {
+            result: to_summary(&entry),
+        }),
+        Err(ApplicationError::NotFound { .. }) => HttpResponse::NotFound().json("Entry Not Found"),
+        Err(_) => HttpResponse::InternalServerError().json("Get Entry Error"),
+    }
+}
+
+// 人間の日記 (id 0) とAIの日記を分けて返す
+fn to_summary(entry: &DiaryEntry) -> EntrySummary {
+    let human_diary_id = DiaryId::new(0).unwrap();
+    EntrySummary {
+        entry_date: entry.date().to_string(),
+        human: entry
+            .find(&human_diary_id)
+            .map(|diary| diary.content().to_value().clone()),
+        diaries: entry
+            .diaries()
+            .iter()
+            .filter(|diary| diary.id() != &human_diary_id)
+            .map(|diary| EntryDiary {
+                client_id: diary.id().to_id(),
+                diary: diary.content().to_value().clone(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use actix_web::body::MessageBody;
+    use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::{http, test, web, App};
+    use chrono::{Duration, NaiveDate};
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+
+    use super::{entries_handler, entry_handler};
+    use crate::auth::jwt::Claims;
+    use crate::auth::keys::JwtKeys;
+    use crate::auth::revocation::RevocationStore;
+    use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+    use crate::domain::entity::user::UserId;
+    use crate::domain::repository::diary::DiaryRepository;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::init::DbPool;
+    use crate::presentation::entry::response::{EntriesResponse, EntryResponse};
+    use crate::{application, infrastructure};
+
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    fn setup_test_app(
+        pool: DbPool,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Response = ServiceResponse<impl MessageBody>,
+            Config = (),
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        let diary_repository = infrastructure::database::diary::DiaryRepositoryImpl::new(pool);
+        let get_entries_use_case =
+            application::usecase::entry::GetEntriesUseCase::new(diary_repository);
+
+        App::new()
+            .app_data(web::Data::new(get_entries_use_case))
+            .app_data(web::Data::new(test_keys()))
+            .app_data(web::Data::new(RevocationStore::new()))
+            .service(web::resource("/entries").route(web::get().to(entries_handler)))
+            .service(web::resource("/entries/{date}").route(web::get().to(entry_handler)))
+    }
+
+    fn test_keys() -> JwtKeys {
+        JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap()
+    }
+
+    fn generate_test_jwt(user_id: &UserId, keys: &JwtKeys) -> String {
+        keys.encode(&Claims::new(user_id, Duration::hours(1)))
+            .expect("token creation failed")
+    }
+
+    #[actix_rt::test]
+    async fn test_entries_handler_lists_and_fetches_entries() {
+        let pool = create_test_db_pool();
+        let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
+        let diary_repository =
+            infrastructure::database::diary::DiaryRepositoryImpl::new(pool.clone());
+        let app = test::init_service(setup_test_app(pool)).await;
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        let entry_date = NaiveDate::from_ymd_opt(2024, 7, 11).unwrap();
+        for (id, text) in [(0, "一日目．"), (1, "いちにちめ．")] {
+            let diary = Diary::new(
+                DiaryId::new(id).unwrap(),
+                DiaryContent::new(text.to_string()).unwrap(),
+            )
+            .unwrap();
+            diary_repository
+                .update_all(&user_id, &entry_date, &[diary], &[])
+                .await
+                .unwrap();
+        }
+        let authorization = format!("Bearer {}", generate_test_jwt(&user_id, &test_keys()));
+
+        let request = test::TestRequest::get()
+            .uri("/entries")
+            .insert_header(("Authorization", authorization.clone()))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let response_body: EntriesResponse = test::read_body_json(response).await;
+        assert_eq!(response_body.result.entries.len(), 1);
+        assert_eq!(response_body.result.entries[0].entry_date, "2024-07-11");
+
+        let request = test::TestRequest::get()
+            .uri("/entries/2024-07-11")
+            .insert_header(("Authorization", authorization.clone()))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let response_body: EntryResponse = test::read_body_json(response).await;
+        assert_eq!(response_body.result.human.as_deref(), Some("一日目．"));
+        assert_eq!(response_body.result.diaries.len(), 1);
+        assert_eq!(response_body.result.diaries[0].diary, "いちにちめ．");
+
+        let request = test::TestRequest::get()
+            .uri("/entries/2024-07-12")
+            .insert_header(("Authorization", authorization))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+}
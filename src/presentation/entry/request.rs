@@ -0,0 +1,7 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+pub struct EntryRequestPath {
+    pub date: NaiveDate,
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EntriesResponse {
+    pub result: EntriesResult,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EntriesResult {
+    pub entries: Vec<EntrySummary>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EntryResponse {
+    pub result: EntrySummary,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EntrySummary {
+    #[serde(rename = "entryDate")]
+    pub entry_date: String,
+    pub human: Option<String>,
+    pub diaries: Vec<EntryDiary>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct EntryDiary {
+    #[serde(rename = "clientId")]
+    pub client_id: i32,
+    pub diary: String,
+}
@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::body::to_bytes;
+use actix_web::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::watch;
+
+use crate::domain::entity::user::UserId;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+// 覚えておいた応答を返したときに付けるヘッダー
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+const DEFAULT_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    content_type: Option<HeaderValue>,
+    body: Bytes,
+}
+
+enum Entry {
+    // 最初のリクエストを処理している間。重複したリクエストは完了を待ってから同じ応答を返す
+    InFlight {
+        fingerprint: String,
+        done: watch::Receiver<Option<StoredResponse>>,
+    },
+    Done {
+        fingerprint: String,
+        response: StoredResponse,
+        stored_at: Instant,
+    },
+}
+
+// (ユーザー, Idempotency-Key) ごとに最初の応答を window の間だけ覚えておく
+// 5xx の応答は覚えずに捨てるので、同じキーで送り直せばもう一度処理する
+// プロセス内でのみ共有し、再起動すると消える
+#[derive(Clone)]
+pub struct IdempotencyStore {
+    window: Duration,
+    entries: Arc<Mutex<HashMap<(String, String), Entry>>>,
+}
+
+enum Claim {
+    Run(watch::Sender<Option<StoredResponse>>),
+    Replay(StoredResponse),
+    Wait(watch::Receiver<Option<StoredResponse>>),
+    Conflict,
+}
+
+impl IdempotencyStore {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let window = env::var("IDEMPOTENCY_WINDOW_SECS")
+            .ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_WINDOW);
+        Self::new(window)
+    }
+
+    // Idempotency-Key が無ければ handler をそのまま実行する
+    // 同じキーで本文が違うリクエストには 409 を返す
+    pub async fn run<T, F>(
+        &self,
+        request: &HttpRequest,
+        user_id: &UserId,
+        body: &T,
+        handler: F,
+    ) -> HttpResponse
+    where
+        T: Serialize,
+        F: Future<Output = HttpResponse>,
+    {
+        let idempotency_key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+            Some(value) => match value.to_str() {
+                Ok(value) if !value.is_empty() => value.to_string(),
+                _ => {
+                    return HttpResponse::BadRequest().json("Invalid Idempotency-Key");
+                },
+            },
+            None => return handler.await,
+        };
+        let key = (user_id.as_str().to_string(), idempotency_key);
+        let fingerprint = fingerprint(body);
+
+        let sender = loop {
+            match self.claim(&key, &fingerprint) {
+                Claim::Run(sender) => break sender,
+                Claim::Replay(response) => return replay(&response),
+                Claim::Conflict => {
+                    return HttpResponse::Conflict()
+                        .json("Idempotency-Key was already used with a different request");
+                },
+                Claim::Wait(mut done) => {
+                    // 最初のリクエストが応答を残さずに終わった場合は、もう一度取り直す
+                    if let Ok(response) = done.wait_for(Option::is_some).await {
+                        return replay(response.as_ref().unwrap());
+                    }
+                },
+            }
+        };
+
+        // handler が途中で打ち切られても InFlight が残らないようにする
+        let mut pending = Pending {
+            store: self,
+            key: Some(key),
+        };
+        let response = handler.await;
+        let status = response.status();
+        let content_type = response.headers().get(CONTENT_TYPE).cloned();
+        let body = match to_bytes(response.into_body()).await {
+            Ok(body) => body,
+            Err(_) => return HttpResponse::InternalServerError().finish(),
+        };
+        let stored = StoredResponse {
+            status,
+            content_type,
+            body,
+        };
+        if !status.is_server_error() {
+            let key = pending.key.take().unwrap();
+            self.entries.lock().unwrap().insert(
+                key,
+                Entry::Done {
+                    fingerprint,
+                    response: stored.clone(),
+                    stored_at: Instant::now(),
+                },
+            );
+            let _ = sender.send(Some(stored.clone()));
+        }
+        respond(&stored)
+    }
+
+    fn claim(&self, key: &(String, String), fingerprint: &str) -> Claim {
+        let mut entries = self.entries.lock().unwrap();
+        let window = self.window;
+        entries.retain(|_, entry| match entry {
+            Entry::Done { stored_at, .. } => stored_at.elapsed() < window,
+            Entry::InFlight { .. } => true,
+        });
+
+        match entries.get(key) {
+            Some(Entry::Done { fingerprint: f, .. })
+            | Some(Entry::InFlight { fingerprint: f, .. })
+                if f != fingerprint =>
+            {
+                Claim::Conflict
+            },
+            Some(Entry::Done { response, .. }) => Claim::Replay(response.clone()),
+            Some(Entry::InFlight { done, .. }) => Claim::Wait(done.clone()),
+            None => {
+                let (sender, done) = watch::channel(None);
+                entries.insert(
+                    key.clone(),
+                    Entry::InFlight {
+                        fingerprint: fingerprint.to_string(),
+                        done,
+                    },
+                );
+                Claim::Run(sender)
+            },
+        }
+    }
+}
+
+struct Pending<'a> {
+    store: &'a IdempotencyStore,
+    key: Option<(String, String)>,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.store.entries.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+fn fingerprint<T: Serialize>(body: &T) -> String {
+    let json = serde_json::to_vec(body).unwrap_or_default();
+    hex::encode(Sha256::digest(json))
+}
+
+fn respond(stored: &StoredResponse) -> HttpResponse {
+    let mut response = HttpResponse::build(stored.status);
+    if let Some(content_type) = &stored.content_type {
+        response.insert_header((CONTENT_TYPE, content_type.clone()));
+    }
+    response.body(stored.body.clone())
+}
+
+fn replay(stored: &StoredResponse) -> HttpResponse {
+    let mut response = respond(stored);
+    response.headers_mut().insert(
+        HeaderName::from_static(REPLAYED_HEADER),
+        HeaderValue::from_static("true"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use actix_web::{http, test, web, App};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Deserialize, Serialize)]
+    struct EchoRequest {
+        text: String,
+    }
+
+    async fn echo_handler(
+        request: HttpRequest,
+        store: web::Data<IdempotencyStore>,
+        calls: web::Data<AtomicUsize>,
+        body: web::Json<EchoRequest>,
+    ) -> HttpResponse {
+        let user_id = UserId::new("idempotency_user".to_string()).unwrap();
+        store
+            .run(&request, &user_id, &*body, async {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                if body.text == "fail" && call == 0 {
+                    return HttpResponse::InternalServerError().json("Error");
+                }
+                HttpResponse::Ok().json(json!({"text": body.text, "call": call}))
+            })
+            .await
+    }
+
+    fn post(key: &str, text: &str) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri("/echo")
+            .insert_header((IDEMPOTENCY_KEY_HEADER, key))
+            .set_json(json!({"text": text}))
+    }
+
+    #[actix_rt::test]
+    async fn test_duplicates_replay_first_response() {
+        let calls = web::Data::new(AtomicUsize::new(0));
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(IdempotencyStore::new(DEFAULT_WINDOW)))
+                .app_data(calls.clone())
+                .route("/echo", web::post().to(echo_handler)),
+        )
+        .await;
+
+        // 処理中に届いた重複も、完了を待って同じ応答を返す
+        let (first, second) = futures_util::join!(
+            test::call_service(&app, post("key-1", "晴れ").to_request()),
+            test::call_service(&app, post("key-1", "晴れ").to_request()),
+        );
+        let third = test::call_service(&app, post("key-1", "晴れ").to_request()).await;
+        let conflict = test::call_service(&app, post("key-1", "雨").to_request()).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(conflict.status(), http::StatusCode::CONFLICT);
+        assert!(third.headers().contains_key(REPLAYED_HEADER));
+        let bodies: Vec<serde_json::Value> = vec![
+            test::read_body_json(first).await,
+            test::read_body_json(second).await,
+            test::read_body_json(third).await,
+        ];
+        assert!(bodies
+            .iter()
+            .all(|body| *body == json!({"text": "晴れ", "call": 0})));
+    }
+
+    #[actix_rt::test]
+    async fn test_server_errors_are_not_replayed() {
+        let calls = web::Data::new(AtomicUsize::new(0));
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(IdempotencyStore::new(DEFAULT_WINDOW)))
+                .app_data(calls.clone())
+                .route("/echo", web::post().to(echo_handler)),
+        )
+        .await;
+
+        let failed = test::call_service(&app, post("key-2", "fail").to_request()).await;
+        let retried = test::call_service(&app, post("key-2", "fail").to_request()).await;
+        // キーが無ければ毎回処理する
+        let plain = test::TestRequest::post()
+            .uri("/echo")
+            .set_json(json!({"text": "fail"}))
+            .to_request();
+        test::call_service(&app, plain).await;
+
+        assert_eq!(failed.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(retried.status(), http::StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,49 +1,39 @@
 use actix_web::{web, HttpResponse, Responder};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{encode, EncodingKey, Header};
-use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::response::InitResponse;
 use crate::application::usecase::init::CreateUserUseCase;
+use crate::application::usecase::token::TokenUsecase;
+use crate::auth::jwt::issue_access_token;
+use crate::auth::keys::JwtKeys;
 use crate::domain::entity::user::UserId;
+use crate::infrastructure::database::token::RefreshTokenRepositoryImpl;
 use crate::infrastructure::database::user::UserRepositoryImpl;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    exp: usize,
-}
-
 pub async fn init_handler(
     data: web::Data<CreateUserUseCase<UserRepositoryImpl>>,
+    token_usecase: web::Data<TokenUsecase<RefreshTokenRepositoryImpl>>,
+    keys: web::Data<JwtKeys>,
 ) -> impl Responder {
     // 新しいユーザーIDを生成
-    let user_id = Uuid::new_v4().to_string();
-
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(1))
-        .expect("valid timestamp")
-        .timestamp() as usize;
-
-    let claims = Claims {
-        sub: user_id.to_owned(),
-        exp: expiration,
-    };
+    let user_id = UserId::new(Uuid::new_v4().to_string()).unwrap();
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(b"your_secret_key"),
-    )
-    .expect("token creation failed");
+    let token = issue_access_token(&keys, &user_id).expect("token creation failed");
 
     println!("hello world");
 
     // ユースケースを実行
-    match data.create_user(&UserId::new(user_id).unwrap()).await {
-        Ok(_) => HttpResponse::Ok().json(InitResponse { token }), // 成功時のレスポンス
-        Err(_) => HttpResponse::InternalServerError().json("Error creating user"), // エラー時のレスポンス
+    if data.create_user(&user_id).await.is_err() {
+        return HttpResponse::InternalServerError().json("Error creating user"); // エラー時のレスポンス
+    }
+
+    // リフレッシュトークンはユーザー作成後に発行する (user_id の外部キーのため)
+    match token_usecase.issue_refresh_token(&user_id).await {
+        Ok(refresh_token) => HttpResponse::Ok().json(InitResponse {
+            token,
+            refresh_token,
+        }), // 成功時のレスポンス
+        Err(_) => HttpResponse::InternalServerError().json("Error issuing refresh token"),
     }
 }
 
@@ -58,6 +48,7 @@ mod tests {
     use diesel::MysqlConnection;
 
     use super::init_handler;
+    use crate::auth::keys::JwtKeys;
     use crate::infrastructure::database::init::DbPool;
     use crate::{application, infrastructure};
 
@@ -86,9 +77,17 @@ mod tests {
         let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
         let create_user_use_case =
             application::usecase::init::CreateUserUseCase::new(user_repository.clone());
+        let token_repository =
+            infrastructure::database::token::RefreshTokenRepositoryImpl::new(pool.clone());
+        let token_use_case = application::usecase::token::TokenUsecase::new(token_repository);
+
+        let keys =
+            JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap();
 
         App::new()
             .app_data(web::Data::new(create_user_use_case))
+            .app_data(web::Data::new(token_use_case))
+            .app_data(web::Data::new(keys))
             .service(web::resource("/init").route(web::get().to(init_handler)))
     }
 
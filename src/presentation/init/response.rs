@@ -3,4 +3,6 @@ use serde::Serialize;
 #[derive(Debug, Serialize)]
 pub struct InitResponse {
     pub token: String,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
 }
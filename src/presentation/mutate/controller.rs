@@ -2,33 +2,143 @@ use std::sync::Arc;
 
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 
-use super::response::{MutateResponse, MutateResult};
-use crate::application::usecase::mutate::MutateUsecase;
-use crate::auth::jwt::get_user_id_from_req;
+use super::response::{
+    MutateJobResponse, MutateJobResult, MutateResponse, MutateResult, PersonaJobStatus,
+    PersonaResult,
+};
+use crate::application::error::ApplicationError;
+use crate::application::usecase::job::MutationJobUseCase;
+use crate::application::usecase::mutate::{MutateUsecase, MutationResult, PersonaOutcome};
+use crate::auth::extractor::AuthenticatedUser;
 use crate::domain::entity::diary::DiaryContent;
-use crate::infrastructure::database::user::UserRepositoryImpl;
-use crate::presentation::mutate::request::MutateRequest;
+use crate::domain::entity::job::{JobId, MutationJob};
+use crate::domain::entity::status::MutationState;
+use crate::domain::repository::diary::DiaryRepository;
+use crate::domain::repository::job::MutationJobRepository;
+use crate::domain::repository::persona::PersonaRepository;
+use crate::domain::repository::revision::DiaryRevisionRepository;
+use crate::domain::repository::user::UserRepository;
+use crate::domain::service::llm::LlmClient;
+use crate::presentation::idempotency::IdempotencyStore;
+use crate::presentation::mutate::request::{MutateJobRequestPath, MutateRequest};
 
-pub async fn mutate_handler(
-    req: HttpRequest,
-    mutate_usecase: web::Data<MutateUsecase<UserRepositoryImpl>>,
+pub async fn mutate_handler<
+    J: MutationJobRepository,
+    R: UserRepository,
+    D: DiaryRepository,
+    V: DiaryRevisionRepository,
+    P: PersonaRepository,
+    C: LlmClient,
+>(
+    request: HttpRequest,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    mutate_usecase: web::Data<MutateUsecase<R, D, V, P, C>>,
+    job_usecase: web::Data<MutationJobUseCase<J, R, D, V, P, C>>,
+    idempotency: web::Data<IdempotencyStore>,
     body: web::Json<MutateRequest>,
 ) -> impl Responder {
-    let user_id = match get_user_id_from_req(req) {
-        Ok(user_id) => user_id,
-        Err(_) => return HttpResponse::Unauthorized().finish(),
-    };
-
-    let usecase_clone = Arc::clone(&mutate_usecase);
-    let target_content = DiaryContent::new(body.target_text.clone()).unwrap();
-
-    match usecase_clone.mutate_text(&user_id, &target_content).await {
-        Ok(response) => HttpResponse::Ok().json(MutateResponse {
-            result: MutateResult {
-                mutated_length: response,
-            },
-        }),
-        Err(_) => HttpResponse::InternalServerError().json("Error creating user"), // エラー時のレスポンス
+    // 通信が不安定で送り直されたリクエストは、書き換えをやり直さずに最初の応答を返す
+    idempotency
+        .run(&request, &user_id, &*body, async {
+            let usecase_clone = Arc::clone(&mutate_usecase);
+            let target_content = DiaryContent::new(body.target_text.clone()).unwrap();
+
+            if body.is_async {
+                return match job_usecase
+                    .enqueue(
+                        &user_id,
+                        &body.entry_date(),
+                        &target_content,
+                        body.cache_mode(),
+                    )
+                    .await
+                {
+                    Ok(job) => HttpResponse::Accepted().json(to_job_response(&job)),
+                    Err(_) => HttpResponse::InternalServerError().json("Enqueue Mutation Error"),
+                };
+            }
+
+            match usecase_clone
+                .mutate_text(
+                    &user_id,
+                    &body.entry_date(),
+                    &target_content,
+                    body.cache_mode(),
+                )
+                .await
+            {
+                Ok(response) => HttpResponse::Ok().json(to_mutate_response(&response)),
+                Err(_) => HttpResponse::InternalServerError().json("Error creating user"), // エラー時のレスポンス
+            }
+        })
+        .await
+}
+
+// 非同期モードで登録したジョブの、ペルソナごとの進み具合を返す
+pub async fn mutate_job_handler<
+    J: MutationJobRepository,
+    R: UserRepository,
+    D: DiaryRepository,
+    V: DiaryRevisionRepository,
+    P: PersonaRepository,
+    C: LlmClient,
+>(
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    request_path: web::Path<MutateJobRequestPath>,
+    job_usecase: web::Data<MutationJobUseCase<J, R, D, V, P, C>>,
+) -> impl Responder {
+    let job_id = JobId::new(request_path.into_inner().job_id);
+
+    match job_usecase.get_job(&user_id, &job_id).await {
+        Ok(job) => HttpResponse::Ok().json(to_job_response(&job)),
+        Err(ApplicationError::NotFound { .. }) => HttpResponse::NotFound().json("Job Not Found"),
+        Err(_) => HttpResponse::InternalServerError().json("Get Mutation Job Error"),
+    }
+}
+
+// 失敗したペルソナは理由を付けて返す。日記は前回のまま残っている
+fn to_mutate_response(result: &MutationResult) -> MutateResponse {
+    MutateResponse {
+        result: MutateResult {
+            mutated_length: *result.mutated_length(),
+            personas: result
+                .personas()
+                .iter()
+                .map(|(persona_id, outcome)| {
+                    let (status, reason) = match outcome {
+                        PersonaOutcome::Saved => (MutationState::Ok.as_str(), None),
+                        PersonaOutcome::Failed(reason) => {
+                            (MutationState::Failed.as_str(), Some(reason.clone()))
+                        },
+                        PersonaOutcome::Superseded => ("superseded", None),
+                    };
+                    PersonaResult {
+                        client_id: persona_id.to_id(),
+                        status: status.to_string(),
+                        reason,
+                    }
+                })
+                .collect(),
+        },
+    }
+}
+
+fn to_job_response(job: &MutationJob) -> MutateJobResponse {
+    MutateJobResponse {
+        result: MutateJobResult {
+            job_id: job.id().as_str().to_string(),
+            entry_date: job.entry_date().to_string(),
+            status: job.status().as_str().to_string(),
+            personas: job
+                .persona_jobs()
+                .iter()
+                .map(|persona_job| PersonaJobStatus {
+                    client_id: persona_job.persona_id().to_id(),
+                    status: persona_job.status().as_str().to_string(),
+                    attempts: *persona_job.attempts(),
+                })
+                .collect(),
+        },
     }
 }
 
@@ -38,14 +148,26 @@ mod tests {
 
     use actix_web::body::MessageBody;
     use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
-    use actix_web::{test, web, App};
-    use chrono::{Duration, Utc};
+    use actix_web::{http, test, web, App};
+    use chrono::Duration;
     use diesel::r2d2::ConnectionManager;
     use diesel::MysqlConnection;
     use serde_json::json;
 
-    use super::mutate_handler;
+    use super::{mutate_handler, mutate_job_handler};
+    use crate::application::broadcast::DiaryBroadcaster;
+    use crate::auth::jwt::Claims;
+    use crate::auth::keys::JwtKeys;
+    use crate::auth::revocation::RevocationStore;
+    use crate::domain::entity::user::UserId;
+    use crate::infrastructure::api::mock::MockLlmClient;
+    use crate::infrastructure::database::diary::DiaryRepositoryImpl;
     use crate::infrastructure::database::init::DbPool;
+    use crate::infrastructure::database::job::MutationJobRepositoryImpl;
+    use crate::infrastructure::database::persona::PersonaRepositoryImpl;
+    use crate::infrastructure::database::revision::DiaryRevisionRepositoryImpl;
+    use crate::infrastructure::database::user::UserRepositoryImpl;
+    use crate::presentation::idempotency::IdempotencyStore;
     use crate::{application, infrastructure};
 
     fn create_test_db_pool() -> DbPool {
@@ -71,50 +193,75 @@ mod tests {
 
         // リポジトリとユースケースの設定
         let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
-        let openai_client = infrastructure::api::openai::OpenAiClient::new();
+        let diary_repository =
+            infrastructure::database::diary::DiaryRepositoryImpl::new(pool.clone());
+        let revision_repository =
+            infrastructure::database::revision::DiaryRevisionRepositoryImpl::new(pool.clone());
+        let persona_repository =
+            infrastructure::database::persona::PersonaRepositoryImpl::new(pool.clone());
+        let llm_client = MockLlmClient::new().with_fixed_response("書き換えた文章．");
+        let job_repository =
+            infrastructure::database::job::MutationJobRepositoryImpl::new(pool.clone());
         let mutate_use_case = application::usecase::mutate::MutateUsecase::new(
-            openai_client,
+            llm_client,
+            "gpt-4-turbo".to_string(),
+            user_repository.clone(),
+            diary_repository,
+            revision_repository,
+            persona_repository.clone(),
+            DiaryBroadcaster::new(),
+        );
+        // ワーカーは起動しないので、登録したジョブは pending のまま残る
+        let job_use_case = application::usecase::job::MutationJobUseCase::new(
+            job_repository,
             user_repository.clone(),
+            persona_repository,
+            mutate_use_case.clone(),
         );
 
         App::new()
             .app_data(web::Data::new(mutate_use_case))
-            .service(web::resource("/mutate").route(web::post().to(mutate_handler)))
+            .app_data(web::Data::new(job_use_case))
+            .app_data(web::Data::new(IdempotencyStore::from_env()))
+            .app_data(web::Data::new(test_keys()))
+            .app_data(web::Data::new(RevocationStore::new()))
+            .service(
+                web::resource("/mutate").route(web::post().to(mutate_handler::<
+                    MutationJobRepositoryImpl,
+                    UserRepositoryImpl,
+                    DiaryRepositoryImpl,
+                    DiaryRevisionRepositoryImpl,
+                    PersonaRepositoryImpl,
+                    MockLlmClient,
+                >)),
+            )
+            .service(
+                web::resource("/mutate/{jobId}").route(web::get().to(mutate_job_handler::<
+                    MutationJobRepositoryImpl,
+                    UserRepositoryImpl,
+                    DiaryRepositoryImpl,
+                    DiaryRevisionRepositoryImpl,
+                    PersonaRepositoryImpl,
+                    MockLlmClient,
+                >)),
+            )
     }
 
-    use jsonwebtoken::{encode, EncodingKey, Header};
-    use serde::{Deserialize, Serialize};
-
-    #[derive(Debug, Serialize, Deserialize)]
-    struct Claims {
-        sub: String,
-        exp: usize,
+    fn test_keys() -> JwtKeys {
+        JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap()
     }
 
-    fn generate_test_jwt(user_id: &str, secret: &[u8]) -> String {
-        let expiration = Utc::now()
-            .checked_add_signed(Duration::hours(1))
-            .expect("valid timestamp")
-            .timestamp() as usize;
-
-        let claims = Claims {
-            sub: user_id.to_owned(),
-            exp: expiration,
-        };
-
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(secret),
-        )
-        .expect("token creation failed")
+    fn generate_test_jwt(user_id: &str, keys: &JwtKeys) -> String {
+        let user_id = UserId::new(user_id.to_string()).unwrap();
+        keys.encode(&Claims::new(&user_id, Duration::hours(1)))
+            .expect("token creation failed")
     }
 
     #[actix_rt::test]
     async fn test_first_mutate_handler() {
         let app = test::init_service(setup_test_app()).await;
 
-        let token = generate_test_jwt("3558d1e0-7997-43e5-9b2f-0a46292942c9", b"your_secret_key");
+        let token = generate_test_jwt("3558d1e0-7997-43e5-9b2f-0a46292942c9", &test_keys());
 
         let request = test::TestRequest::post()
             .uri("/mutate")
@@ -130,19 +277,25 @@ mod tests {
         let response_body = test::read_body(response).await;
         let response_json: serde_json::Value = serde_json::from_slice(&response_body).unwrap();
         assert_eq!(response_json["result"]["mutatedLength"], 18);
+        assert!(response_json["result"]["personas"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|persona| persona["status"] == "ok"));
     }
 
     #[actix_rt::test]
     async fn test_second_mutate_handler() {
         let app = test::init_service(setup_test_app()).await;
 
-        let token = generate_test_jwt("0c9d6d60-3f76-4530-a1f2-1e8d015ff672", b"your_secret_key");
+        let token = generate_test_jwt("0c9d6d60-3f76-4530-a1f2-1e8d015ff672", &test_keys());
 
         let request = test::TestRequest::post()
             .uri("/mutate")
             .insert_header(("Authorization", format!("Bearer {}", token)))
             .set_json(json!({
                 "targetText": "ここに書いてく．ここにも書いてく．さらに書いていく．",
+                "entryDate": "2024-07-11",
             }))
             .to_request();
 
@@ -151,5 +304,53 @@ mod tests {
         assert!(response.status().is_success());
     }
 
+    #[actix_rt::test]
+    async fn test_async_mutate_returns_job() {
+        let app = test::init_service(setup_test_app()).await;
+
+        let token = generate_test_jwt("3558d1e0-7997-43e5-9b2f-0a46292942c9", &test_keys());
+
+        let request = test::TestRequest::post()
+            .uri("/mutate")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .set_json(json!({
+                "targetText": "ここに書いていく．",
+                "async": true,
+            }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), http::StatusCode::ACCEPTED);
+        let response_json: serde_json::Value =
+            serde_json::from_slice(&test::read_body(response).await).unwrap();
+        let job_id = response_json["result"]["jobId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let request = test::TestRequest::get()
+            .uri(&format!("/mutate/{}", job_id))
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let response_json: serde_json::Value =
+            serde_json::from_slice(&test::read_body(response).await).unwrap();
+        assert_eq!(response_json["result"]["status"], "pending");
+        assert!(response_json["result"]["personas"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|persona| persona["status"] == "pending"));
+
+        // 他のユーザーのジョブは見えない
+        let other_token = generate_test_jwt("0c9d6d60-3f76-4530-a1f2-1e8d015ff672", &test_keys());
+        let request = test::TestRequest::get()
+            .uri(&format!("/mutate/{}", job_id))
+            .insert_header(("Authorization", format!("Bearer {}", other_token)))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+    }
+
     // 他のテストケースも同様に追加
 }
@@ -1,7 +1,39 @@
-use serde::Deserialize;
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Clone)]
+use crate::domain::service::llm::CacheMode;
+
+#[derive(Deserialize, Serialize, Clone)]
 pub struct MutateRequest {
     #[serde(rename = "targetText")]
     pub target_text: String,
+    #[serde(rename = "entryDate")]
+    pub entry_date: Option<NaiveDate>,
+    // true ならジョブとして登録し、書き換えを待たずに jobId を返す
+    #[serde(rename = "async", default)]
+    pub is_async: bool,
+    // true なら LLM の応答キャッシュを使わずに書き換える
+    #[serde(rename = "noCache", default)]
+    pub no_cache: bool,
+}
+
+impl MutateRequest {
+    // 指定がなければ会場のローカル日付で今日のエントリに書く
+    pub fn entry_date(&self) -> NaiveDate {
+        self.entry_date.unwrap_or_else(|| Local::now().date_naive())
+    }
+
+    pub fn cache_mode(&self) -> CacheMode {
+        if self.no_cache {
+            CacheMode::Bypass
+        } else {
+            CacheMode::Use
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MutateJobRequestPath {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
 }
@@ -9,4 +9,36 @@ pub struct MutateResponse {
 pub struct MutateResult {
     #[serde(rename = "mutatedLength")]
     pub mutated_length: i32,
+    pub personas: Vec<PersonaResult>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PersonaResult {
+    #[serde(rename = "clientId")]
+    pub client_id: i32,
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MutateJobResponse {
+    pub result: MutateJobResult,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MutateJobResult {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    #[serde(rename = "entryDate")]
+    pub entry_date: String,
+    pub status: String,
+    pub personas: Vec<PersonaJobStatus>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PersonaJobStatus {
+    #[serde(rename = "clientId")]
+    pub client_id: i32,
+    pub status: String,
+    pub attempts: i32,
 }
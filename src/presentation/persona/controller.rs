@@ -0,0 +1,91 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::response::{PersonaSummary, PersonasResponse, PersonasResult};
+use crate::application::usecase::persona::GetPersonasUseCase;
+use crate::infrastructure::database::persona::PersonaRepositoryImpl;
+
+// 表示側が /diary/{clientId} に渡す id の一覧を返す
+pub async fn personas_handler(
+    personas_usecase: web::Data<GetPersonasUseCase<PersonaRepositoryImpl>>,
+) -> impl Responder {
+    match personas_usecase.get_enabled_personas().await {
+        Ok(personas) => HttpResponse::Ok().json(PersonasResponse {
+            result: PersonasResult {
+                personas: personas
+                    .iter()
+                    .map(|persona| PersonaSummary {
+                        client_id: persona.id().to_id(),
+                        name: persona.name().clone(),
+                        display_order: *persona.display_order(),
+                    })
+                    .collect(),
+            },
+        }),
+        Err(_) => HttpResponse::InternalServerError().json("Get Personas Error"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use actix_web::body::MessageBody;
+    use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::{test, web, App};
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+
+    use super::personas_handler;
+    use crate::infrastructure::database::init::DbPool;
+    use crate::presentation::persona::response::PersonasResponse;
+    use crate::{application, infrastructure};
+
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    fn setup_test_app() -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Response = ServiceResponse<impl MessageBody>,
+            Config = (),
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        // テスト用のデータベース接続プールの作成
+        let pool = create_test_db_pool();
+
+        // リポジトリとユースケースの設定
+        let persona_repository =
+            infrastructure::database::persona::PersonaRepositoryImpl::new(pool.clone());
+        let get_personas_use_case =
+            application::usecase::persona::GetPersonasUseCase::new(persona_repository);
+
+        App::new()
+            .app_data(web::Data::new(get_personas_use_case))
+            .service(web::resource("/personas").route(web::get().to(personas_handler)))
+    }
+
+    #[actix_rt::test]
+    async fn test_personas_handler() {
+        let app = test::init_service(setup_test_app()).await;
+
+        let request = test::TestRequest::get().uri("/personas").to_request();
+
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let body = test::read_body(response).await;
+        let personas_response: PersonasResponse = serde_json::from_slice(&body).unwrap();
+        assert!(personas_response
+            .result
+            .personas
+            .iter()
+            .all(|persona| persona.client_id > 0));
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PersonasResponse {
+    pub result: PersonasResult,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PersonasResult {
+    pub personas: Vec<PersonaSummary>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PersonaSummary {
+    #[serde(rename = "clientId")]
+    pub client_id: i32,
+    pub name: String,
+    #[serde(rename = "displayOrder")]
+    pub display_order: i32,
+}
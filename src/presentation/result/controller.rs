@@ -1,32 +1,38 @@
 use actix_web::{web, HttpRequest, HttpResponse, Responder};
 
 use super::request::UpdateResultRequest;
+use crate::application::error::ApplicationError;
 use crate::application::usecase::result::UpdateResultUseCase;
-use crate::auth::jwt::get_user_id_from_req;
+use crate::auth::extractor::AuthenticatedUser;
 use crate::domain::entity::diary::DiaryId;
 use crate::infrastructure::database::user::UserRepositoryImpl;
+use crate::presentation::idempotency::IdempotencyStore;
 
 pub async fn result_handler(
-    req: HttpRequest,
+    request: HttpRequest,
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
     data: web::Data<UpdateResultUseCase<UserRepositoryImpl>>,
+    idempotency: web::Data<IdempotencyStore>,
     body: web::Json<UpdateResultRequest>,
 ) -> impl Responder {
-    let user_id = match get_user_id_from_req(req) {
-        Ok(user_id) => user_id,
-        Err(_) => return HttpResponse::Unauthorized().finish(),
-    };
-
-    // リクエストボディからfavorite_idを取得
-    let favorite_id = DiaryId::new(body.favorite_id).unwrap();
-
-    // ユースケースを実行
-    match data
-        .update_result(&user_id, body.is_public, &favorite_id)
+    idempotency
+        .run(&request, &user_id, &*body, async {
+            // リクエストボディからfavorite_idを取得
+            let favorite_id = DiaryId::new(body.favorite_id).unwrap();
+
+            // ユースケースを実行
+            match data
+                .update_result(&user_id, body.is_public, &favorite_id)
+                .await
+            {
+                Ok(_) => HttpResponse::Ok().json("Success"), // 成功時のレスポンス
+                Err(ApplicationError::Conflict(_)) => {
+                    HttpResponse::Conflict().json("Result was updated by another request")
+                },
+                Err(_) => HttpResponse::InternalServerError().json("Error updating result"), // エラー時のレスポンス
+            }
+        })
         .await
-    {
-        Ok(_) => HttpResponse::Ok().json("Success"), // 成功時のレスポンス
-        Err(_) => HttpResponse::InternalServerError().json("Error updating result"), // エラー時のレスポンス
-    }
 }
 
 #[cfg(test)]
@@ -36,13 +42,18 @@ mod tests {
     use actix_web::body::MessageBody;
     use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
     use actix_web::{http, test, web, App};
-    use chrono::{Duration, Utc};
+    use chrono::Duration;
     use diesel::r2d2::ConnectionManager;
     use diesel::MysqlConnection;
     use serde_json::json;
 
     use super::result_handler;
+    use crate::auth::jwt::Claims;
+    use crate::auth::keys::JwtKeys;
+    use crate::auth::revocation::RevocationStore;
+    use crate::domain::entity::user::UserId;
     use crate::infrastructure::database::init::DbPool;
+    use crate::presentation::idempotency::IdempotencyStore;
     use crate::{application, infrastructure};
 
     fn create_test_db_pool() -> DbPool {
@@ -73,34 +84,20 @@ mod tests {
 
         App::new()
             .app_data(web::Data::new(update_result_use_case))
+            .app_data(web::Data::new(IdempotencyStore::from_env()))
+            .app_data(web::Data::new(test_keys()))
+            .app_data(web::Data::new(RevocationStore::new()))
             .service(web::resource("/result").route(web::post().to(result_handler)))
     }
 
-    use jsonwebtoken::{encode, EncodingKey, Header};
-    use serde::{Deserialize, Serialize};
-
-    #[derive(Debug, Serialize, Deserialize)]
-    struct Claims {
-        sub: String,
-        exp: usize,
+    fn test_keys() -> JwtKeys {
+        JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap()
     }
-    fn generate_test_jwt(user_id: &str, secret: &[u8]) -> String {
-        let expiration = Utc::now()
-            .checked_add_signed(Duration::hours(1))
-            .expect("valid timestamp")
-            .timestamp() as usize;
-
-        let claims = Claims {
-            sub: user_id.to_owned(),
-            exp: expiration,
-        };
-
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(secret),
-        )
-        .expect("token creation failed")
+
+    fn generate_test_jwt(user_id: &str, keys: &JwtKeys) -> String {
+        let user_id = UserId::new(user_id.to_string()).unwrap();
+        keys.encode(&Claims::new(&user_id, Duration::hours(1)))
+            .expect("token creation failed")
     }
 
     #[actix_rt::test]
@@ -108,7 +105,7 @@ mod tests {
         println!("hogeeee");
         let app = test::init_service(setup_test_app()).await;
 
-        let token = generate_test_jwt("3558d1e0-7997-43e5-9b2f-0a46292942c9", b"your_secret_key");
+        let token = generate_test_jwt("3558d1e0-7997-43e5-9b2f-0a46292942c9", &test_keys());
 
         let request = test::TestRequest::post()
             .uri("/result")
@@ -0,0 +1,146 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::response::{RevisionSummary, RevisionsResponse, RevisionsResult};
+use crate::application::usecase::revision::GetRevisionsUseCase;
+use crate::auth::extractor::AuthenticatedUser;
+use crate::infrastructure::database::revision::DiaryRevisionRepositoryImpl;
+
+// 日記の変更履歴を古い順に返す (再生用)
+pub async fn revisions_handler(
+    AuthenticatedUser { user_id, .. }: AuthenticatedUser,
+    revisions_usecase: web::Data<GetRevisionsUseCase<DiaryRevisionRepositoryImpl>>,
+) -> impl Responder {
+    match revisions_usecase.get_revisions(&user_id).await {
+        Ok(revisions) => HttpResponse::Ok().json(RevisionsResponse {
+            result: RevisionsResult {
+                revisions: revisions
+                    .iter()
+                    .map(|revision| RevisionSummary {
+                        entry_date: revision.entry_date().to_string(),
+                        client_id: revision.diary().id().to_id(),
+                        diary: revision.diary().content().to_value().clone(),
+                        target_index: *revision.target_index(),
+                        created_at: revision.created_at().and_utc().to_rfc3339(),
+                    })
+                    .collect(),
+            },
+        }),
+        Err(_) => HttpResponse::InternalServerError().json("Get Revisions Error"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use actix_web::body::MessageBody;
+    use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::{http, test, web, App};
+    use chrono::{Duration, Utc};
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+
+    use super::revisions_handler;
+    use crate::auth::jwt::Claims;
+    use crate::auth::keys::JwtKeys;
+    use crate::auth::revocation::RevocationStore;
+    use crate::domain::entity::diary::{Diary, DiaryContent, DiaryId};
+    use crate::domain::entity::revision::DiaryRevision;
+    use crate::domain::entity::user::UserId;
+    use crate::domain::repository::revision::DiaryRevisionRepository;
+    use crate::domain::repository::user::UserRepository;
+    use crate::infrastructure::database::init::DbPool;
+    use crate::presentation::revision::response::RevisionsResponse;
+    use crate::{application, infrastructure};
+
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    fn setup_test_app(
+        pool: DbPool,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Response = ServiceResponse<impl MessageBody>,
+            Config = (),
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        let revision_repository =
+            infrastructure::database::revision::DiaryRevisionRepositoryImpl::new(pool);
+        let get_revisions_use_case =
+            application::usecase::revision::GetRevisionsUseCase::new(revision_repository);
+
+        App::new()
+            .app_data(web::Data::new(get_revisions_use_case))
+            .app_data(web::Data::new(test_keys()))
+            .app_data(web::Data::new(RevocationStore::new()))
+            .service(web::resource("/revisions").route(web::get().to(revisions_handler)))
+    }
+
+    fn test_keys() -> JwtKeys {
+        JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap()
+    }
+
+    fn generate_test_jwt(user_id: &UserId, keys: &JwtKeys) -> String {
+        keys.encode(&Claims::new(user_id, Duration::hours(1)))
+            .expect("token creation failed")
+    }
+
+    #[actix_rt::test]
+    async fn test_revisions_handler_success() {
+        let pool = create_test_db_pool();
+        let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
+        let revision_repository =
+            infrastructure::database::revision::DiaryRevisionRepositoryImpl::new(pool.clone());
+        let app = test::init_service(setup_test_app(pool)).await;
+
+        let user_id = UserId::new(uuid::Uuid::new_v4().to_string()).unwrap();
+        user_repository.create(&user_id).await.unwrap();
+        let diary = Diary::new(
+            DiaryId::new(0).unwrap(),
+            DiaryContent::new("一文目．".to_string()).unwrap(),
+        )
+        .unwrap();
+        revision_repository
+            .append(
+                &user_id,
+                &DiaryRevision::new(Utc::now().date_naive(), diary, 0, Utc::now().naive_utc()),
+            )
+            .await
+            .unwrap();
+
+        let request = test::TestRequest::get()
+            .uri("/revisions")
+            .insert_header((
+                "Authorization",
+                format!("Bearer {}", generate_test_jwt(&user_id, &test_keys())),
+            ))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert!(response.status().is_success());
+        let response_body: RevisionsResponse = test::read_body_json(response).await;
+        assert_eq!(response_body.result.revisions.len(), 1);
+        assert_eq!(response_body.result.revisions[0].diary, "一文目．");
+
+        user_repository.delete_user(&user_id).await.unwrap();
+    }
+
+    #[actix_rt::test]
+    async fn test_revisions_handler_unauthorized() {
+        let app = test::init_service(setup_test_app(create_test_db_pool())).await;
+
+        let request = test::TestRequest::get().uri("/revisions").to_request();
+        let response = test::call_service(&app, request).await;
+
+        assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
+    }
+}
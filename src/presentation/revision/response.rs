@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RevisionsResponse {
+    pub result: RevisionsResult,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RevisionsResult {
+    pub revisions: Vec<RevisionSummary>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RevisionSummary {
+    #[serde(rename = "entryDate")]
+    pub entry_date: String,
+    #[serde(rename = "clientId")]
+    pub client_id: i32,
+    pub diary: String,
+    #[serde(rename = "targetIndex")]
+    pub target_index: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
@@ -1,15 +1,76 @@
 use actix_web::web;
 
+use super::admin::controller::{admin_delete_user_handler, admin_llm_routes_handler};
 use super::delete::controller::delete_handler;
-use super::diary::controller::diary_handler;
+use super::diary::controller::{diary_handler, diary_stream_handler, diary_ws_handler};
+use super::display::controller::{pair_display_handler, register_display_handler};
+use super::entry::controller::{entries_handler, entry_handler};
 use super::init::controller::init_handler;
+use super::persona::controller::personas_handler;
 use super::result::controller::result_handler;
-use crate::presentation::mutate::controller::mutate_handler;
+use super::revision::controller::revisions_handler;
+use super::token::controller::{admin_token_handler, refresh_handler};
+use crate::auth::guard::RequireRole;
+use crate::infrastructure::api::cache::CachedLlmClient;
+use crate::infrastructure::api::fallback::FallbackLlmClient;
+use crate::infrastructure::database::diary::DiaryRepositoryImpl;
+use crate::infrastructure::database::display::DisplaySessionRepositoryImpl;
+use crate::infrastructure::database::job::MutationJobRepositoryImpl;
+use crate::infrastructure::database::persona::PersonaRepositoryImpl;
+use crate::infrastructure::database::revision::DiaryRevisionRepositoryImpl;
+use crate::infrastructure::database::user::UserRepositoryImpl;
+use crate::presentation::mutate::controller::{mutate_handler, mutate_job_handler};
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.service(web::resource("/mutate").route(web::post().to(mutate_handler)));
+    cfg.service(
+        web::resource("/mutate").route(web::post().to(mutate_handler::<
+            MutationJobRepositoryImpl,
+            UserRepositoryImpl,
+            DiaryRepositoryImpl,
+            DiaryRevisionRepositoryImpl,
+            PersonaRepositoryImpl,
+            CachedLlmClient<FallbackLlmClient>,
+        >)),
+    );
+    cfg.service(
+        web::resource("/mutate/{jobId}").route(web::get().to(mutate_job_handler::<
+            MutationJobRepositoryImpl,
+            UserRepositoryImpl,
+            DiaryRepositoryImpl,
+            DiaryRevisionRepositoryImpl,
+            PersonaRepositoryImpl,
+            CachedLlmClient<FallbackLlmClient>,
+        >)),
+    );
+    cfg.service(web::resource("/personas").route(web::get().to(personas_handler)));
     cfg.service(web::resource("/result").route(web::post().to(result_handler)));
     cfg.service(web::resource("/init").route(web::get().to(init_handler)));
-    cfg.service(web::resource("/diary/{clientId}").route(web::get().to(diary_handler)));
+    cfg.service(web::resource("/display").route(web::post().to(register_display_handler)));
+    cfg.service(web::resource("/display/pair").route(web::post().to(pair_display_handler)));
+    cfg.service(
+        web::resource("/diary/{clientId}").route(
+            web::get().to(diary_handler::<DisplaySessionRepositoryImpl, DiaryRepositoryImpl>),
+        ),
+    );
+    cfg.service(web::resource("/diary/{clientId}/stream").route(
+        web::get().to(diary_stream_handler::<DisplaySessionRepositoryImpl, DiaryRepositoryImpl>),
+    ));
+    cfg.service(web::resource("/ws/diary/{clientId}").route(
+        web::get().to(diary_ws_handler::<DisplaySessionRepositoryImpl, DiaryRepositoryImpl>),
+    ));
+    cfg.service(web::resource("/entries").route(web::get().to(entries_handler)));
+    cfg.service(web::resource("/entries/{date}").route(web::get().to(entry_handler)));
+    cfg.service(web::resource("/revisions").route(web::get().to(revisions_handler)));
     cfg.service(web::resource("/delete").route(web::post().to(delete_handler)));
+    cfg.service(web::resource("/token/refresh").route(web::post().to(refresh_handler)));
+    cfg.service(web::resource("/token/admin").route(web::post().to(admin_token_handler)));
+    cfg.service(
+        web::scope("/admin")
+            .wrap(RequireRole::admin())
+            .route(
+                "/users/{userId}",
+                web::delete().to(admin_delete_user_handler),
+            )
+            .route("/llm", web::get().to(admin_llm_routes_handler)),
+    );
 }
@@ -0,0 +1,142 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use super::request::{AdminTokenRequest, RefreshRequest};
+use super::response::{AdminTokenResponse, TokenResponse};
+use crate::application::error::ApplicationError;
+use crate::application::usecase::token::TokenUsecase;
+use crate::auth::admin::AdminCredential;
+use crate::auth::error::AuthErrorResponse;
+use crate::auth::jwt::{issue_access_token, issue_admin_token};
+use crate::auth::keys::JwtKeys;
+use crate::domain::entity::user::UserId;
+use crate::infrastructure::database::token::RefreshTokenRepositoryImpl;
+
+pub async fn refresh_handler(
+    token_usecase: web::Data<TokenUsecase<RefreshTokenRepositoryImpl>>,
+    keys: web::Data<JwtKeys>,
+    body: web::Json<RefreshRequest>,
+) -> impl Responder {
+    let (user_id, refresh_token) = match token_usecase
+        .rotate_refresh_token(&body.refresh_token)
+        .await
+    {
+        Ok(rotated) => rotated,
+        Err(ApplicationError::Unauthorized(message)) => {
+            return HttpResponse::Unauthorized().json(AuthErrorResponse {
+                error: "invalid_refresh_token".to_string(),
+                message,
+            })
+        },
+        Err(_) => return HttpResponse::InternalServerError().json("Error refreshing token"),
+    };
+
+    match issue_access_token(&keys, &user_id) {
+        Ok(token) => HttpResponse::Ok().json(TokenResponse {
+            token,
+            refresh_token,
+        }),
+        Err(_) => HttpResponse::InternalServerError().json("Error refreshing token"),
+    }
+}
+
+pub async fn admin_token_handler(
+    admin_credential: web::Data<AdminCredential>,
+    keys: web::Data<JwtKeys>,
+    body: web::Json<AdminTokenRequest>,
+) -> impl Responder {
+    if !admin_credential.verify(&body.credential) {
+        return HttpResponse::Unauthorized().json(AuthErrorResponse {
+            error: "invalid_credential".to_string(),
+            message: "Admin credential is invalid.".to_string(),
+        });
+    }
+
+    let operator_id = UserId::new("admin".to_string()).unwrap();
+    match issue_admin_token(&keys, &operator_id) {
+        Ok(token) => HttpResponse::Ok().json(AdminTokenResponse { token }),
+        Err(_) => HttpResponse::InternalServerError().json("Error issuing admin token"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use actix_web::body::MessageBody;
+    use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+    use actix_web::{http, test, web, App};
+    use diesel::r2d2::ConnectionManager;
+    use diesel::MysqlConnection;
+    use serde_json::json;
+
+    use super::refresh_handler;
+    use crate::auth::keys::JwtKeys;
+    use crate::infrastructure::database::init::DbPool;
+    use crate::presentation::init::controller::init_handler;
+    use crate::presentation::token::response::TokenResponse;
+    use crate::{application, infrastructure};
+
+    fn create_test_db_pool() -> DbPool {
+        dotenv::dotenv().ok();
+        let database_url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        let manager = ConnectionManager::<MysqlConnection>::new(database_url);
+        r2d2::Pool::builder()
+            .build(manager)
+            .expect("Failed to create test pool.")
+    }
+
+    fn setup_test_app() -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Response = ServiceResponse<impl MessageBody>,
+            Config = (),
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        // テスト用のデータベース接続プールの作成
+        let pool = create_test_db_pool();
+
+        // リポジトリとユースケースの設定
+        let user_repository = infrastructure::database::user::UserRepositoryImpl::new(pool.clone());
+        let token_repository =
+            infrastructure::database::token::RefreshTokenRepositoryImpl::new(pool.clone());
+        let create_user_use_case =
+            application::usecase::init::CreateUserUseCase::new(user_repository.clone());
+        let token_use_case = application::usecase::token::TokenUsecase::new(token_repository);
+        let keys =
+            JwtKeys::new(vec![("test".to_string(), b"test_secret".to_vec())], "test").unwrap();
+
+        App::new()
+            .app_data(web::Data::new(create_user_use_case))
+            .app_data(web::Data::new(token_use_case))
+            .app_data(web::Data::new(keys))
+            .service(web::resource("/init").route(web::get().to(init_handler)))
+            .service(web::resource("/token/refresh").route(web::post().to(refresh_handler)))
+    }
+
+    #[actix_rt::test]
+    async fn test_refresh_handler_rotates_token() {
+        let app = test::init_service(setup_test_app()).await;
+
+        let request = test::TestRequest::get().uri("/init").to_request();
+        let init_response: TokenResponse = test::call_and_read_body_json(&app, request).await;
+
+        let request = test::TestRequest::post()
+            .uri("/token/refresh")
+            .set_json(json!({ "refreshToken": init_response.refresh_token }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+        let refreshed: TokenResponse = test::read_body_json(response).await;
+        assert_ne!(refreshed.refresh_token, init_response.refresh_token);
+
+        // 一度使ったリフレッシュトークンは再利用できない
+        let request = test::TestRequest::post()
+            .uri("/token/refresh")
+            .set_json(json!({ "refreshToken": init_response.refresh_token }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,12 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+pub struct RefreshRequest {
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AdminTokenRequest {
+    pub credential: String,
+}
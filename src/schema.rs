@@ -1,17 +1,156 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    access_revocation (user_id) {
+        #[max_length = 255]
+        user_id -> Varchar,
+        revoked_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    completion_cache (cache_key) {
+        #[max_length = 64]
+        cache_key -> Char,
+        #[max_length = 255]
+        model -> Varchar,
+        output -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    diary (user_id, entry_date, diary_id) {
+        #[max_length = 255]
+        user_id -> Varchar,
+        entry_date -> Date,
+        diary_id -> Integer,
+        content -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        version -> Integer,
+    }
+}
+
+diesel::table! {
+    diary_revision (revision_id) {
+        revision_id -> Bigint,
+        #[max_length = 255]
+        user_id -> Varchar,
+        entry_date -> Date,
+        diary_id -> Integer,
+        content -> Text,
+        target_index -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    diary_status (user_id, entry_date, diary_id) {
+        #[max_length = 255]
+        user_id -> Varchar,
+        entry_date -> Date,
+        diary_id -> Integer,
+        #[max_length = 16]
+        status -> Varchar,
+        reason -> Nullable<Text>,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    display_session (display_id) {
+        #[max_length = 36]
+        display_id -> Varchar,
+        #[max_length = 6]
+        pairing_code -> Nullable<Char>,
+        #[max_length = 255]
+        user_id -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        paired_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    mutation_job (job_id) {
+        #[max_length = 36]
+        job_id -> Varchar,
+        #[max_length = 255]
+        user_id -> Varchar,
+        entry_date -> Date,
+        content -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    mutation_job_task (job_id, persona_id) {
+        #[max_length = 36]
+        job_id -> Varchar,
+        persona_id -> Integer,
+        #[max_length = 16]
+        status -> Varchar,
+        attempts -> Integer,
+        updated_at -> Timestamp,
+        output -> Nullable<Text>,
+        output_version -> Nullable<Integer>,
+        target_index -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    persona (persona_id) {
+        persona_id -> Integer,
+        #[max_length = 255]
+        name -> Varchar,
+        prompt -> Text,
+        display_order -> Integer,
+        is_enabled -> Bool,
+    }
+}
+
+diesel::table! {
+    refresh_token (token_hash) {
+        #[max_length = 64]
+        token_hash -> Char,
+        #[max_length = 255]
+        user_id -> Varchar,
+        expires_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     user (user_id) {
         #[max_length = 255]
         user_id -> Varchar,
-        human_diary -> Nullable<Text>,
-        ai_diary_1 -> Nullable<Text>,
-        ai_diary_2 -> Nullable<Text>,
-        ai_diary_3 -> Nullable<Text>,
-        ai_diary_4 -> Nullable<Text>,
         is_public -> Nullable<Bool>,
         favorite_id -> Nullable<Integer>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        version -> Integer,
     }
 }
+
+diesel::joinable!(diary -> user (user_id));
+diesel::joinable!(diary_revision -> user (user_id));
+diesel::joinable!(diary_status -> user (user_id));
+diesel::joinable!(display_session -> user (user_id));
+diesel::joinable!(mutation_job -> user (user_id));
+diesel::joinable!(mutation_job_task -> mutation_job (job_id));
+diesel::joinable!(refresh_token -> user (user_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    access_revocation,
+    completion_cache,
+    diary,
+    diary_revision,
+    diary_status,
+    display_session,
+    mutation_job,
+    mutation_job_task,
+    persona,
+    refresh_token,
+    user,
+);